@@ -0,0 +1,193 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Credentials for private module registries, configured with the
+//! `DENO_AUTH_TOKENS` environment variable. It is a semicolon separated list
+//! of `token@host` entries, which are sent as bearer tokens, and
+//! `username:password@host` entries, which are sent with basic
+//! authentication:
+//!
+//! ```text
+//! DENO_AUTH_TOKENS=a1b2c3d4e5f6@deno.land;user:pass@example.com:8080
+//! ```
+use crate::colors;
+use std::env;
+use std::fmt;
+use url::Url;
+
+#[derive(Clone, PartialEq)]
+pub enum AuthTokenData {
+  Bearer(String),
+  Basic { username: String, password: String },
+}
+
+/// Credentials that are sent to a host when downloading remote modules.
+///
+/// `Debug` doesn't print the secret, so that tokens can't end up in logs.
+#[derive(Clone, PartialEq)]
+pub struct AuthToken {
+  host: String,
+  token: AuthTokenData,
+}
+
+impl AuthToken {
+  /// Returns the value of the `Authorization` header.
+  pub fn header_value(&self) -> String {
+    match &self.token {
+      AuthTokenData::Bearer(token) => format!("Bearer {}", token),
+      AuthTokenData::Basic { username, password } => format!(
+        "Basic {}",
+        base64::encode(format!("{}:{}", username, password))
+      ),
+    }
+  }
+
+  /// Replaces the secrets of the token in `message` with "****".
+  pub fn redact(&self, message: &str) -> String {
+    let secrets = match &self.token {
+      AuthTokenData::Bearer(token) => vec![token.clone()],
+      AuthTokenData::Basic { password, .. } => {
+        vec![self.header_value()[6..].to_string(), password.clone()]
+      }
+    };
+    secrets
+      .iter()
+      .filter(|secret| !secret.is_empty())
+      .fold(message.to_string(), |message, secret| {
+        message.replace(secret.as_str(), "****")
+      })
+  }
+
+  /// An entry matches the host and its subdomains. If it has a port, it
+  /// only matches URLs with that port.
+  fn matches(&self, url: &Url) -> bool {
+    let host = match url.host_str() {
+      Some(host) => host,
+      None => return false,
+    };
+    let (entry_host, entry_port) = match self.host.rfind(':') {
+      Some(i) if !self.host.ends_with(']') => {
+        (&self.host[..i], self.host[i + 1..].parse::<u16>().ok())
+      }
+      _ => (self.host.as_str(), None),
+    };
+    if entry_port.is_some() && entry_port != url.port_or_known_default() {
+      return false;
+    }
+    host == entry_host
+      || (host.ends_with(entry_host)
+        && host[..host.len() - entry_host.len()].ends_with('.'))
+  }
+}
+
+impl fmt::Debug for AuthToken {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AuthToken")
+      .field("host", &self.host)
+      .field("token", &"****")
+      .finish()
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuthTokens(Vec<AuthToken>);
+
+impl AuthTokens {
+  /// Parses a list of tokens in the format of `DENO_AUTH_TOKENS`. Badly
+  /// formed entries are discarded with a warning that leaves out the entry.
+  pub fn new(maybe_tokens_str: Option<String>) -> Self {
+    let mut tokens = Vec::new();
+    let tokens_str = maybe_tokens_str.unwrap_or_default();
+    for entry in tokens_str.split(';').map(str::trim) {
+      if entry.is_empty() {
+        continue;
+      }
+      match parse_auth_token(entry) {
+        Some(token) => tokens.push(token),
+        None => eprintln!(
+          "{} Badly formed auth token in DENO_AUTH_TOKENS discarded",
+          colors::yellow("Warning")
+        ),
+      }
+    }
+    Self(tokens)
+  }
+
+  pub fn from_env() -> Self {
+    Self::new(env::var("DENO_AUTH_TOKENS").ok())
+  }
+
+  /// Returns the token that is sent with requests to `url`.
+  pub fn get(&self, url: &Url) -> Option<&AuthToken> {
+    self.0.iter().find(|token| token.matches(url))
+  }
+}
+
+fn parse_auth_token(entry: &str) -> Option<AuthToken> {
+  let mut parts = entry.rsplitn(2, '@');
+  let host = parts.next()?.to_lowercase();
+  let token = parts.next()?;
+  if host.is_empty() || token.is_empty() {
+    return None;
+  }
+  let token = match token.find(':') {
+    Some(i) => AuthTokenData::Basic {
+      username: token[..i].to_string(),
+      password: token[i + 1..].to_string(),
+    },
+    None => AuthTokenData::Bearer(token.to_string()),
+  };
+  Some(AuthToken { host, token })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get(tokens: &AuthTokens, url: &str) -> Option<String> {
+    tokens
+      .get(&Url::parse(url).unwrap())
+      .map(AuthToken::header_value)
+  }
+
+  #[test]
+  fn test_auth_tokens() {
+    let tokens = AuthTokens::new(Some(
+      "abc123@deno.land; user:p@ss@Example.com:8080;".to_string(),
+    ));
+    let bearer = Some("Bearer abc123".to_string());
+    let basic = Some(format!("Basic {}", base64::encode("user:p@ss")));
+    assert_eq!(get(&tokens, "https://deno.land/x/mod.ts"), bearer);
+    assert_eq!(get(&tokens, "https://x.deno.land/mod.ts"), bearer);
+    assert_eq!(get(&tokens, "https://notdeno.land/mod.ts"), None);
+    assert_eq!(get(&tokens, "http://example.com:8080/mod.ts"), basic);
+    assert_eq!(get(&tokens, "http://example.com/mod.ts"), None);
+  }
+
+  #[test]
+  fn test_auth_tokens_badly_formed() {
+    let tokens = AuthTokens::new(Some("abc123;@deno.land;abc123@".into()));
+    assert_eq!(tokens, AuthTokens::default());
+  }
+
+  #[test]
+  fn test_auth_token_redact() {
+    let tokens = AuthTokens::new(Some(
+      "abc123@deno.land;user:secret@example.com".to_string(),
+    ));
+    let bearer = tokens
+      .get(&Url::parse("https://deno.land").unwrap())
+      .unwrap();
+    assert_eq!(
+      bearer.redact("token abc123 rejected"),
+      "token **** rejected"
+    );
+    assert!(!format!("{:?}", bearer).contains("abc123"));
+    let basic = tokens
+      .get(&Url::parse("https://example.com").unwrap())
+      .unwrap();
+    assert_eq!(
+      basic.redact(&format!("{} secret", basic.header_value())),
+      "Basic **** ****"
+    );
+  }
+}
@@ -0,0 +1,1547 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Bundler used by `deno bundle`, which emits a single ES module for a module
+//! graph without going through the TypeScript compiler.
+//!
+//! Every module is parsed with swc, its types are stripped and it's hoisted
+//! into the scope of the bundle: top level bindings are renamed where they'd
+//! clash with those of other modules, imports are replaced by the bindings
+//! they refer to, and only the exports of the root module are kept. Then
+//! top level declarations that nothing refers to anymore, and that can be
+//! dropped without changing behavior, are removed. That way exports that are
+//! never imported don't end up in the bundle.
+use crate::module_graph::ModuleGraph;
+use crate::module_graph::ModuleGraphFile;
+use crate::msg::MediaType;
+use crate::op_error::OpError;
+use crate::resolver::Resolver;
+use crate::swc_common;
+use crate::swc_common::Mark;
+use crate::swc_common::Span;
+use crate::swc_common::Spanned;
+use crate::swc_common::SyntaxContext;
+use crate::swc_common::DUMMY_SP;
+use crate::swc_ecma_ast::*;
+use crate::swc_util::AstParser;
+use crate::swc_util::JsxOptions;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use log::debug;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use swc_atoms::JsWord;
+use swc_ecma_transforms::resolver_with_mark;
+use swc_ecma_visit::Fold;
+use swc_ecma_visit::FoldWith;
+use swc_ecma_visit::Node;
+use swc_ecma_visit::Visit;
+
+#[derive(Debug, Default)]
+pub struct BundleOptions {
+  /// Leave out whitespace and comments.
+  pub minify: bool,
+  /// Functions that JSX is transformed to calls of.
+  pub jsx: JsxOptions,
+}
+
+#[derive(Debug)]
+pub struct BundleOutput {
+  pub code: String,
+  pub source_map: String,
+}
+
+impl BundleOutput {
+  /// Returns the code with the source map inlined as a data URL, which is
+  /// where `SourceMapGetter` looks for the source map of a module that
+  /// wasn't compiled by Deno.
+  pub fn code_with_inline_source_map(&self) -> String {
+    format!(
+      "{}//# sourceMappingURL=data:application/json;base64,{}",
+      self.code,
+      base64::encode(&self.source_map)
+    )
+  }
+}
+
+/// Bundles `root` and its static dependencies from `module_graph` into a
+/// single ES module. Dynamic imports are left in place, with their specifier
+/// resolved to an absolute URL.
+///
+/// The modules must be in the graph already, as no IO is performed.
+pub fn bundle(
+  module_graph: &ModuleGraph,
+  root: &ModuleSpecifier,
+  resolver: &Resolver,
+  options: &BundleOptions,
+) -> Result<BundleOutput, ErrBox> {
+  let parser = AstParser::new();
+  swc_common::GLOBALS.set(&parser.globals, || {
+    let top_level_mark = Mark::fresh(Mark::root());
+    let mut bundler = Bundler {
+      module_graph,
+      resolver,
+      parser: &parser,
+      jsx: &options.jsx,
+      top_level_mark,
+      top_level_ctxt: SyntaxContext::empty().apply_mark(top_level_mark),
+      modules: vec![],
+      indexes: HashMap::new(),
+      visiting: HashSet::new(),
+      idents: HashSet::new(),
+      taken_names: HashSet::new(),
+      namespaces: HashMap::new(),
+    };
+    let body = bundler.bundle(root.as_str())?;
+    let module = Module {
+      span: DUMMY_SP,
+      body,
+      shebang: None,
+    };
+    let (code, source_map) = parser.emit_module(&module, options.minify)?;
+    Ok(BundleOutput { code, source_map })
+  })
+}
+
+/// A binding that an import or export resolves to.
+#[derive(Clone, Debug, PartialEq)]
+enum Binding {
+  /// Top level binding of the bundle, by its final name.
+  Local(JsWord),
+  /// Namespace object of the module with the given URL.
+  Namespace(String),
+}
+
+#[derive(Clone, Debug)]
+enum Export {
+  /// Top level binding of the module itself, by its final name.
+  Local(JsWord),
+  /// Export of another module, like `export { a as b } from "./a.ts"`.
+  Reexport(String, JsWord),
+  /// Namespace of another module, like `export * as a from "./a.ts"`.
+  Namespace(String),
+}
+
+#[derive(Clone, Debug)]
+enum Import {
+  Named(String, JsWord),
+  Namespace(String),
+}
+
+struct HoistedModule {
+  url: String,
+  module: Module,
+  /// Resolved URLs of the specifiers of imports, re-exports and dynamic
+  /// imports.
+  specifiers: HashMap<String, String>,
+  /// Names of all identifiers in the module.
+  idents: HashSet<JsWord>,
+  /// Final names of top level bindings and imports.
+  renames: HashMap<JsWord, JsWord>,
+  imports: Vec<(JsWord, Import)>,
+  exports: BTreeMap<String, Export>,
+  star_exports: Vec<String>,
+  import_meta: Option<JsWord>,
+}
+
+struct Bundler<'a> {
+  module_graph: &'a ModuleGraph,
+  resolver: &'a Resolver,
+  parser: &'a AstParser,
+  jsx: &'a JsxOptions,
+  top_level_mark: Mark,
+  top_level_ctxt: SyntaxContext,
+  /// Modules in the order they are evaluated.
+  modules: Vec<HoistedModule>,
+  indexes: HashMap<String, usize>,
+  visiting: HashSet<String>,
+  /// Names of all identifiers in the modules. New names are chosen from
+  /// those that aren't used yet, so that they aren't shadowed by a binding
+  /// in a nested scope.
+  idents: HashSet<JsWord>,
+  /// Final names of top level bindings, and globals that are referred to.
+  taken_names: HashSet<JsWord>,
+  /// Final names of the namespace objects that were created, by module URL.
+  namespaces: HashMap<String, (JsWord, Vec<ModuleItem>)>,
+}
+
+impl<'a> Bundler<'a> {
+  fn bundle(&mut self, root: &str) -> Result<Vec<ModuleItem>, ErrBox> {
+    let root = self.load(root)?;
+    for module in &mut self.modules {
+      let mut idents = IdentCollector::default();
+      idents.visit_module(&module.module, &module.module);
+      self.idents.extend(idents.all.iter().cloned());
+      self.taken_names.extend(idents.globals);
+      module.idents = idents.all;
+    }
+
+    for index in 0..self.modules.len() {
+      self.declare(index)?;
+    }
+    for index in 0..self.modules.len() {
+      self.avoid_shadowing(index)?;
+    }
+    for index in 0..self.modules.len() {
+      self.link(index)?;
+    }
+
+    let root_index = self.indexes[&root];
+    let mut root_exports = vec![];
+    for name in self.export_names(&root, &mut HashSet::new()) {
+      match self.resolve_export(&root, &name, &mut HashSet::new())? {
+        Some(binding) => {
+          let local = self.binding_name(&binding)?;
+          root_exports.push((local, name));
+        }
+        None => debug!("Skipping unresolved export \"{}\" of {}", name, root),
+      }
+    }
+
+    // Namespace objects only refer to bindings from their getters, so they
+    // go first, where they can be used by modules that are part of a cycle.
+    let mut items = vec![];
+    for module in &self.modules {
+      if let Some((_, namespace_items)) = self.namespaces.get_mut(&module.url) {
+        items.append(namespace_items);
+      }
+    }
+    for index in 0..self.modules.len() {
+      items.extend(self.hoist(index, index == root_index));
+    }
+
+    let live: HashSet<JsWord> = root_exports
+      .iter()
+      .map(|(local, _)| local.clone())
+      .collect();
+    let mut items = remove_unused(items, &live, self.top_level_ctxt);
+    if !root_exports.is_empty() {
+      let specifiers = root_exports
+        .into_iter()
+        .map(|(local, name)| {
+          let exported = if &*local == name.as_str() {
+            None
+          } else {
+            Some(Ident::new(name.into(), DUMMY_SP))
+          };
+          ExportSpecifier::Named(ExportNamedSpecifier {
+            span: DUMMY_SP,
+            orig: self.ident(local),
+            exported,
+          })
+        })
+        .collect();
+      items.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(
+        NamedExport {
+          span: DUMMY_SP,
+          specifiers,
+          src: None,
+          type_only: false,
+        },
+      )));
+    }
+    Ok(items)
+  }
+
+  /// Parses the module and its static dependencies, which are added to
+  /// `modules` in the order they're evaluated. Returns the URL the module
+  /// was found at.
+  fn load(&mut self, specifier: &str) -> Result<String, ErrBox> {
+    let file = self.get_file(specifier)?;
+    let url = file.url.clone();
+    if self.indexes.contains_key(&url) || !self.visiting.insert(url.clone()) {
+      return Ok(url);
+    }
+    match file.media_type {
+      MediaType::JavaScript
+      | MediaType::JSX
+      | MediaType::TypeScript
+      | MediaType::TSX => {}
+      _ => {
+        return Err(
+          OpError::other(format!(
+            "Bundling {} modules is not supported: {}",
+            crate::msg::enum_name_media_type(file.media_type),
+            url
+          ))
+          .into(),
+        )
+      }
+    }
+
+    let module = self.parser.parse_module(
+      &url,
+      file.media_type,
+      &file.source_code,
+      |parse_result| parse_result,
+    )?;
+    let module = self.parser.strip_types(module, file.media_type, self.jsx);
+    let module = normalize_default_export(module);
+    let module = module.fold_with(&mut resolver_with_mark(self.top_level_mark));
+
+    let mut collector = SpecifierCollector::default();
+    collector.visit_module(&module, &module);
+    let mut specifiers = HashMap::new();
+    for specifier in collector.dynamic {
+      let resolved = self.resolve_specifier(file, &specifier)?;
+      specifiers.insert(specifier, resolved);
+    }
+    for specifier in collector.static_ {
+      let resolved = self.resolve_specifier(file, &specifier)?;
+      let resolved = self.load(&resolved)?;
+      specifiers.insert(specifier, resolved);
+    }
+
+    self.visiting.remove(&url);
+    self.indexes.insert(url.clone(), self.modules.len());
+    self.modules.push(HoistedModule {
+      url: url.clone(),
+      module,
+      specifiers,
+      idents: HashSet::new(),
+      renames: HashMap::new(),
+      imports: vec![],
+      exports: BTreeMap::new(),
+      star_exports: vec![],
+      import_meta: None,
+    });
+    Ok(url)
+  }
+
+  /// Returns the module that `specifier` leads to, following redirects.
+  fn get_file(&self, specifier: &str) -> Result<&'a ModuleGraphFile, ErrBox> {
+    let file = self.module_graph.get(specifier).ok_or_else(|| {
+      OpError::other(format!("Module not found in graph: {}", specifier))
+    })?;
+    match file.redirect.last() {
+      Some(target) => self.get_file(target),
+      None => Ok(file),
+    }
+  }
+
+  fn resolve_specifier(
+    &self,
+    file: &ModuleGraphFile,
+    specifier: &str,
+  ) -> Result<String, ErrBox> {
+    let import = file.imports.iter().find(|i| i.specifier == specifier);
+    let resolved = match import {
+      Some(import) => import.resolved_specifier.to_string(),
+      None => self.resolver.resolve(specifier, &file.url)?.to_string(),
+    };
+    Ok(resolved)
+  }
+
+  /// Gives the top level bindings of the module their final names and
+  /// collects its imports and exports.
+  fn declare(&mut self, index: usize) -> Result<(), ErrBox> {
+    let mut bindings = TopLevelBindings {
+      top_level_ctxt: self.top_level_ctxt,
+      names: vec![],
+    };
+    let module = &self.modules[index].module;
+    bindings.visit_module(module, module);
+    let mut import_meta_finder = ImportMetaFinder::default();
+    import_meta_finder.visit_module(module, module);
+
+    let mut renames = HashMap::new();
+    for name in bindings.names {
+      if renames.contains_key(&name) {
+        continue;
+      }
+      let final_name = if self.taken_names.contains(&name) {
+        self.fresh_name(&name)
+      } else {
+        self.taken_names.insert(name.clone());
+        name.clone()
+      };
+      renames.insert(name, final_name);
+    }
+    let import_meta = if import_meta_finder.found {
+      Some(self.fresh_name("importMeta"))
+    } else {
+      None
+    };
+
+    let module = &self.modules[index];
+    let mut imports = vec![];
+    let mut exports = BTreeMap::new();
+    let mut star_exports = vec![];
+    for item in &module.module.body {
+      let module_decl = match item {
+        ModuleItem::ModuleDecl(module_decl) => module_decl,
+        ModuleItem::Stmt(_) => continue,
+      };
+      match module_decl {
+        ModuleDecl::Import(import_decl) => {
+          let src = &module.specifiers[&*import_decl.src.value];
+          for specifier in &import_decl.specifiers {
+            let (local, import) = match specifier {
+              ImportSpecifier::Named(named) => {
+                let imported = named.imported.as_ref().unwrap_or(&named.local);
+                (
+                  &named.local,
+                  Import::Named(src.clone(), imported.sym.clone()),
+                )
+              }
+              ImportSpecifier::Default(default) => (
+                &default.local,
+                Import::Named(src.clone(), "default".into()),
+              ),
+              ImportSpecifier::Namespace(namespace) => {
+                (&namespace.local, Import::Namespace(src.clone()))
+              }
+            };
+            imports.push((local.sym.clone(), import));
+          }
+        }
+        ModuleDecl::ExportDecl(export_decl) => {
+          for name in decl_names(&export_decl.decl) {
+            let final_name = renames.get(&name).cloned();
+            let final_name = final_name.unwrap_or_else(|| name.clone());
+            exports.insert(name.to_string(), Export::Local(final_name));
+          }
+        }
+        ModuleDecl::ExportNamed(named_export) => {
+          let maybe_src = named_export
+            .src
+            .as_ref()
+            .map(|src| module.specifiers[&*src.value].clone());
+          for specifier in &named_export.specifiers {
+            let (name, export) = match (specifier, &maybe_src) {
+              (ExportSpecifier::Named(named), Some(src)) => {
+                let exported = named.exported.as_ref().unwrap_or(&named.orig);
+                (
+                  exported.sym.to_string(),
+                  Export::Reexport(src.clone(), named.orig.sym.clone()),
+                )
+              }
+              (ExportSpecifier::Named(named), None) => {
+                let exported = named.exported.as_ref().unwrap_or(&named.orig);
+                let orig = &named.orig.sym;
+                let import = imports.iter().find(|(local, _)| local == orig);
+                let export = match (import, renames.get(orig)) {
+                  (Some((_, Import::Named(src, name))), _) => {
+                    Export::Reexport(src.clone(), name.clone())
+                  }
+                  (Some((_, Import::Namespace(src))), _) => {
+                    Export::Namespace(src.clone())
+                  }
+                  (None, Some(final_name)) => Export::Local(final_name.clone()),
+                  (None, None) => {
+                    return Err(
+                      OpError::other(format!(
+                        "Export \"{}\" is not defined in {}",
+                        orig, module.url
+                      ))
+                      .into(),
+                    )
+                  }
+                };
+                (exported.sym.to_string(), export)
+              }
+              (ExportSpecifier::Namespace(namespace), Some(src)) => (
+                namespace.name.sym.to_string(),
+                Export::Namespace(src.clone()),
+              ),
+              (ExportSpecifier::Default(default), Some(src)) => (
+                default.exported.sym.to_string(),
+                Export::Reexport(src.clone(), "default".into()),
+              ),
+              _ => continue,
+            };
+            exports.insert(name, export);
+          }
+        }
+        ModuleDecl::ExportAll(export_all) => {
+          star_exports.push(module.specifiers[&*export_all.src.value].clone());
+        }
+        // Default exports were turned into named exports by
+        // `normalize_default_export`.
+        ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+          unreachable!()
+        }
+        ModuleDecl::TsImportEquals(_)
+        | ModuleDecl::TsExportAssignment(_)
+        | ModuleDecl::TsNamespaceExport(_) => {
+          return Err(
+            OpError::other(format!(
+              "Bundling CommonJS style TypeScript imports and exports is not supported: {}",
+              module.url
+            ))
+            .into(),
+          )
+        }
+      }
+    }
+
+    let module = &mut self.modules[index];
+    module.renames = renames;
+    module.imports = imports;
+    module.exports = exports;
+    module.star_exports = star_exports;
+    module.import_meta = import_meta;
+    Ok(())
+  }
+
+  /// Gives a fresh name to each binding that an import resolves to, if its
+  /// final name is used by the importing module for something else, like in
+  /// `import { a as b } from "./a.ts"; function f(a) { return b; }`.
+  fn avoid_shadowing(&mut self, index: usize) -> Result<(), ErrBox> {
+    let imports = self.modules[index].imports.clone();
+    for (local, import) in imports {
+      let binding = match import {
+        Import::Named(src, name) => {
+          self.resolve_export(&src, &name, &mut HashSet::new())?
+        }
+        Import::Namespace(_) => continue,
+      };
+      let final_name = match binding {
+        Some(Binding::Local(final_name)) => final_name,
+        // Namespace objects already have names that aren't used anywhere,
+        // and missing exports are reported by `link`.
+        _ => continue,
+      };
+      if final_name == local
+        || !self.modules[index].idents.contains(&final_name)
+      {
+        continue;
+      }
+      let fresh = self.fresh_name(&final_name);
+      // Final names are unique, so this only renames the exported binding.
+      for module in &mut self.modules {
+        for renamed in module.renames.values_mut() {
+          if *renamed == final_name {
+            *renamed = fresh.clone();
+          }
+        }
+        for export in module.exports.values_mut() {
+          if let Export::Local(name) = export {
+            if *name == final_name {
+              *name = fresh.clone();
+            }
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Renames imports to the final names of the bindings they resolve to.
+  fn link(&mut self, index: usize) -> Result<(), ErrBox> {
+    let imports = self.modules[index].imports.clone();
+    for (local, import) in imports {
+      let binding = match import {
+        Import::Named(src, name) => {
+          match self.resolve_export(&src, &name, &mut HashSet::new())? {
+            Some(binding) => binding,
+            None => {
+              return Err(
+                OpError::other(format!(
+                  "The module \"{}\" imported from \"{}\" does not provide an export named \"{}\"",
+                  src, self.modules[index].url, name
+                ))
+                .into(),
+              )
+            }
+          }
+        }
+        Import::Namespace(src) => Binding::Namespace(src),
+      };
+      let final_name = self.binding_name(&binding)?;
+      self.modules[index].renames.insert(local, final_name);
+    }
+    Ok(())
+  }
+
+  fn resolve_export(
+    &self,
+    url: &str,
+    name: &str,
+    visited: &mut HashSet<(String, String)>,
+  ) -> Result<Option<Binding>, ErrBox> {
+    if !visited.insert((url.to_string(), name.to_string())) {
+      return Ok(None);
+    }
+    let module = &self.modules[self.indexes[url]];
+    match module.exports.get(name) {
+      Some(Export::Local(final_name)) => {
+        Ok(Some(Binding::Local(final_name.clone())))
+      }
+      Some(Export::Reexport(src, orig)) => {
+        self.resolve_export(src, orig, visited)
+      }
+      Some(Export::Namespace(src)) => Ok(Some(Binding::Namespace(src.clone()))),
+      // `export *` doesn't re-export default exports.
+      None if name == "default" => Ok(None),
+      None => {
+        for src in &module.star_exports {
+          if let Some(binding) = self.resolve_export(src, name, visited)? {
+            return Ok(Some(binding));
+          }
+        }
+        Ok(None)
+      }
+    }
+  }
+
+  fn export_names(
+    &self,
+    url: &str,
+    visited: &mut HashSet<String>,
+  ) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    if !visited.insert(url.to_string()) {
+      return names;
+    }
+    let module = &self.modules[self.indexes[url]];
+    for src in &module.star_exports {
+      names.extend(
+        self
+          .export_names(src, visited)
+          .into_iter()
+          .filter(|name| name != "default"),
+      );
+    }
+    names.extend(module.exports.keys().cloned());
+    names
+  }
+
+  /// Returns the final name of a binding, creating the namespace object of
+  /// a module the first time it's needed.
+  fn binding_name(&mut self, binding: &Binding) -> Result<JsWord, ErrBox> {
+    let url = match binding {
+      Binding::Local(final_name) => return Ok(final_name.clone()),
+      Binding::Namespace(url) => url,
+    };
+    if let Some((name, _)) = self.namespaces.get(url) {
+      return Ok(name.clone());
+    }
+    let name = self.fresh_name(&format!("{}_ns", module_stem(url)));
+    self.namespaces.insert(url.clone(), (name.clone(), vec![]));
+
+    // Exports are getters so that they are live bindings, like those of a
+    // real module namespace object.
+    let mut props =
+      vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Ident(Ident::new("__proto__".into(), DUMMY_SP)),
+        value: Box::new(Expr::Lit(Lit::Null(Null { span: DUMMY_SP }))),
+      })))];
+    for export_name in self.export_names(url, &mut HashSet::new()) {
+      let binding =
+        match self.resolve_export(url, &export_name, &mut HashSet::new())? {
+          Some(binding) => binding,
+          None => continue,
+        };
+      let local = self.binding_name(&binding)?;
+      props.push(PropOrSpread::Prop(Box::new(Prop::Getter(GetterProp {
+        span: DUMMY_SP,
+        key: PropName::Str(Str {
+          span: DUMMY_SP,
+          value: export_name.into(),
+          has_escape: false,
+        }),
+        type_ann: None,
+        body: Some(BlockStmt {
+          span: DUMMY_SP,
+          stmts: vec![Stmt::Return(ReturnStmt {
+            span: DUMMY_SP,
+            arg: Some(Box::new(Expr::Ident(self.ident(local)))),
+          })],
+        }),
+      }))));
+    }
+    let item = self.const_decl(
+      name.clone(),
+      Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props,
+      }),
+    );
+    self.namespaces.get_mut(url).unwrap().1.push(item);
+    Ok(name)
+  }
+
+  /// Returns the items of the module with bindings renamed to their final
+  /// names and without imports and exports.
+  fn hoist(&mut self, index: usize, is_main: bool) -> Vec<ModuleItem> {
+    let hoisted = &mut self.modules[index];
+    let module = std::mem::replace(
+      &mut hoisted.module,
+      Module {
+        span: DUMMY_SP,
+        body: vec![],
+        shebang: None,
+      },
+    );
+    let mut renamer = Renamer {
+      top_level_ctxt: self.top_level_ctxt,
+      renames: &hoisted.renames,
+      specifiers: &hoisted.specifiers,
+      import_meta: hoisted.import_meta.as_ref(),
+    };
+    let module = module.fold_with(&mut renamer);
+    let url = hoisted.url.clone();
+    let import_meta = hoisted.import_meta.clone();
+
+    let mut items = vec![];
+    if let Some(import_meta) = import_meta {
+      let props = vec![
+        ("url", Lit::Str(self.str(&url))),
+        (
+          "main",
+          Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value: is_main,
+          }),
+        ),
+      ];
+      let props = props
+        .into_iter()
+        .map(|(key, value)| {
+          PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+            key: PropName::Ident(Ident::new(key.into(), DUMMY_SP)),
+            value: Box::new(Expr::Lit(value)),
+          })))
+        })
+        .collect();
+      items.push(self.const_decl(
+        import_meta,
+        Expr::Object(ObjectLit {
+          span: DUMMY_SP,
+          props,
+        }),
+      ));
+    }
+    for item in module.body {
+      match item {
+        ModuleItem::Stmt(stmt) => items.push(ModuleItem::Stmt(stmt)),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export_decl)) => {
+          items.push(ModuleItem::Stmt(Stmt::Decl(export_decl.decl)))
+        }
+        ModuleItem::ModuleDecl(_) => {}
+      }
+    }
+    items
+  }
+
+  /// Returns `name`, or `name` with a numeric suffix, whichever isn't the
+  /// name of any identifier yet.
+  fn fresh_name(&mut self, name: &str) -> JsWord {
+    let mut fresh: JsWord = name.into();
+    let mut i = 1;
+    while self.idents.contains(&fresh) || self.taken_names.contains(&fresh) {
+      fresh = format!("{}_{}", name, i).into();
+      i += 1;
+    }
+    self.taken_names.insert(fresh.clone());
+    fresh
+  }
+
+  fn ident(&self, sym: JsWord) -> Ident {
+    Ident::new(sym, DUMMY_SP.with_ctxt(self.top_level_ctxt))
+  }
+
+  fn str(&self, value: &str) -> Str {
+    Str {
+      span: DUMMY_SP,
+      value: value.into(),
+      has_escape: false,
+    }
+  }
+
+  fn const_decl(&self, name: JsWord, init: Expr) -> ModuleItem {
+    ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
+      span: DUMMY_SP,
+      kind: VarDeclKind::Const,
+      declare: false,
+      decls: vec![VarDeclarator {
+        span: DUMMY_SP,
+        name: Pat::Ident(self.ident(name)),
+        init: Some(Box::new(init)),
+        definite: false,
+      }],
+    })))
+  }
+}
+
+/// Returns a valid identifier based on the file name of the module, used to
+/// name its namespace object.
+fn module_stem(url: &str) -> String {
+  let file_name = url.rsplit('/').next().unwrap_or("");
+  let stem = file_name.split('.').next().unwrap_or("");
+  let stem: String = stem
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect();
+  match stem.chars().next() {
+    Some(c) if !c.is_ascii_digit() => stem,
+    _ => format!("_{}", stem),
+  }
+}
+
+/// Turns `export default` into a declaration and `export { a as default }`,
+/// so that the resolver sees the declaration and all exports can be handled
+/// the same way.
+fn normalize_default_export(module: Module) -> Module {
+  let mut idents = IdentCollector::default();
+  idents.visit_module(&module, &module);
+  let mut default_name: JsWord = "_default".into();
+  let mut i = 1;
+  while idents.all.contains(&default_name) {
+    default_name = format!("_default_{}", i).into();
+    i += 1;
+  }
+  let export_default = |span: Span, local: &Ident| {
+    ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+      span,
+      specifiers: vec![ExportSpecifier::Named(ExportNamedSpecifier {
+        span,
+        orig: local.clone(),
+        exported: Some(Ident::new("default".into(), DUMMY_SP)),
+      })],
+      src: None,
+      type_only: false,
+    }))
+  };
+
+  let mut body = vec![];
+  for item in module.body {
+    match item {
+      ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export)) => {
+        let span = export.span;
+        let decl = match export.decl {
+          DefaultDecl::Fn(FnExpr { ident, function }) => Decl::Fn(FnDecl {
+            ident: ident
+              .unwrap_or_else(|| Ident::new(default_name.clone(), DUMMY_SP)),
+            declare: false,
+            function,
+          }),
+          DefaultDecl::Class(ClassExpr { ident, class }) => {
+            Decl::Class(ClassDecl {
+              ident: ident
+                .unwrap_or_else(|| Ident::new(default_name.clone(), DUMMY_SP)),
+              declare: false,
+              class,
+            })
+          }
+          DefaultDecl::TsInterfaceDecl(_) => continue,
+        };
+        let local = match &decl {
+          Decl::Fn(fn_decl) => fn_decl.ident.clone(),
+          Decl::Class(class_decl) => class_decl.ident.clone(),
+          _ => unreachable!(),
+        };
+        body.push(ModuleItem::Stmt(Stmt::Decl(decl)));
+        body.push(export_default(span, &local));
+      }
+      ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) => {
+        let local = Ident::new(default_name.clone(), DUMMY_SP);
+        body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
+          span: export.span,
+          kind: VarDeclKind::Const,
+          declare: false,
+          decls: vec![VarDeclarator {
+            span: export.span,
+            name: Pat::Ident(local.clone()),
+            init: Some(export.expr),
+            definite: false,
+          }],
+        }))));
+        body.push(export_default(export.span, &local));
+      }
+      item => body.push(item),
+    }
+  }
+  Module { body, ..module }
+}
+
+/// Names of the bindings that a declaration introduces.
+fn decl_names(decl: &Decl) -> Vec<JsWord> {
+  let mut names = vec![];
+  match decl {
+    Decl::Fn(fn_decl) => names.push(fn_decl.ident.sym.clone()),
+    Decl::Class(class_decl) => names.push(class_decl.ident.sym.clone()),
+    Decl::Var(var_decl) => {
+      for declarator in &var_decl.decls {
+        let mut idents = vec![];
+        pat_idents(&declarator.name, &mut idents);
+        names.extend(idents.into_iter().map(|ident| ident.sym.clone()));
+      }
+    }
+    _ => {}
+  }
+  names
+}
+
+fn pat_idents<'p>(pat: &'p Pat, idents: &mut Vec<&'p Ident>) {
+  match pat {
+    Pat::Ident(ident) => idents.push(ident),
+    Pat::Array(array_pat) => {
+      for elem in array_pat.elems.iter().flatten() {
+        pat_idents(elem, idents);
+      }
+    }
+    Pat::Object(object_pat) => {
+      for prop in &object_pat.props {
+        match prop {
+          ObjectPatProp::KeyValue(key_value) => {
+            pat_idents(&key_value.value, idents)
+          }
+          ObjectPatProp::Assign(assign) => idents.push(&assign.key),
+          ObjectPatProp::Rest(rest) => pat_idents(&rest.arg, idents),
+        }
+      }
+    }
+    Pat::Rest(rest) => pat_idents(&rest.arg, idents),
+    Pat::Assign(assign) => pat_idents(&assign.left, idents),
+    _ => {}
+  }
+}
+
+/// Collects the name of every identifier, and of those that refer to a
+/// global, which the resolver leaves without a syntax context.
+#[derive(Default)]
+struct IdentCollector {
+  all: HashSet<JsWord>,
+  globals: HashSet<JsWord>,
+}
+
+impl Visit for IdentCollector {
+  fn visit_ident(&mut self, ident: &Ident, _parent: &dyn Node) {
+    self.all.insert(ident.sym.clone());
+  }
+
+  fn visit_expr(&mut self, expr: &Expr, parent: &dyn Node) {
+    if let Expr::Ident(ident) = expr {
+      if ident.span.ctxt() == SyntaxContext::empty() {
+        self.globals.insert(ident.sym.clone());
+      }
+    }
+    swc_ecma_visit::visit_expr(self, expr, parent);
+  }
+}
+
+/// Collects the specifiers of static imports and re-exports, and the string
+/// literal specifiers of dynamic imports.
+#[derive(Default)]
+struct SpecifierCollector {
+  static_: Vec<String>,
+  dynamic: Vec<String>,
+}
+
+impl Visit for SpecifierCollector {
+  fn visit_module_decl(&mut self, module_decl: &ModuleDecl, parent: &dyn Node) {
+    let src = match module_decl {
+      ModuleDecl::Import(import_decl) => Some(&import_decl.src),
+      ModuleDecl::ExportNamed(named_export) => named_export.src.as_ref(),
+      ModuleDecl::ExportAll(export_all) => Some(&export_all.src),
+      _ => None,
+    };
+    if let Some(src) = src {
+      self.static_.push(src.value.to_string());
+    }
+    swc_ecma_visit::visit_module_decl(self, module_decl, parent);
+  }
+
+  fn visit_call_expr(&mut self, call_expr: &CallExpr, parent: &dyn Node) {
+    swc_ecma_visit::visit_call_expr(self, call_expr, parent);
+    if let Some(specifier) = dynamic_import_specifier(call_expr) {
+      self.dynamic.push(specifier.value.to_string());
+    }
+  }
+}
+
+fn dynamic_import_specifier(call_expr: &CallExpr) -> Option<&Str> {
+  match &call_expr.callee {
+    ExprOrSuper::Expr(expr) => match &**expr {
+      Expr::Ident(ident) if &*ident.sym == "import" => {}
+      _ => return None,
+    },
+    ExprOrSuper::Super(_) => return None,
+  }
+  match call_expr.args.get(0).map(|arg| &*arg.expr) {
+    Some(Expr::Lit(Lit::Str(specifier))) => Some(specifier),
+    _ => None,
+  }
+}
+
+fn is_import_meta(expr: &Expr) -> bool {
+  match expr {
+    Expr::MetaProp(MetaPropExpr { meta, prop }) => {
+      &*meta.sym == "import" && &*prop.sym == "meta"
+    }
+    _ => false,
+  }
+}
+
+#[derive(Default)]
+struct ImportMetaFinder {
+  found: bool,
+}
+
+impl Visit for ImportMetaFinder {
+  fn visit_expr(&mut self, expr: &Expr, parent: &dyn Node) {
+    self.found |= is_import_meta(expr);
+    swc_ecma_visit::visit_expr(self, expr, parent);
+  }
+}
+
+/// Collects the names of bindings in the top level scope of a module. This
+/// includes `var` declarations in blocks, which are hoisted to it.
+struct TopLevelBindings {
+  top_level_ctxt: SyntaxContext,
+  names: Vec<JsWord>,
+}
+
+impl TopLevelBindings {
+  fn add(&mut self, ident: &Ident) {
+    if ident.span.ctxt() == self.top_level_ctxt {
+      self.names.push(ident.sym.clone());
+    }
+  }
+}
+
+impl Visit for TopLevelBindings {
+  fn visit_var_declarator(
+    &mut self,
+    declarator: &VarDeclarator,
+    parent: &dyn Node,
+  ) {
+    let mut idents = vec![];
+    pat_idents(&declarator.name, &mut idents);
+    for ident in idents {
+      self.add(ident);
+    }
+    swc_ecma_visit::visit_var_declarator(self, declarator, parent);
+  }
+
+  fn visit_fn_decl(&mut self, fn_decl: &FnDecl, parent: &dyn Node) {
+    self.add(&fn_decl.ident);
+    swc_ecma_visit::visit_fn_decl(self, fn_decl, parent);
+  }
+
+  fn visit_class_decl(&mut self, class_decl: &ClassDecl, parent: &dyn Node) {
+    self.add(&class_decl.ident);
+    swc_ecma_visit::visit_class_decl(self, class_decl, parent);
+  }
+}
+
+/// Renames references to top level bindings and imports, replaces
+/// `import.meta` and resolves the specifiers of dynamic imports.
+struct Renamer<'r> {
+  top_level_ctxt: SyntaxContext,
+  renames: &'r HashMap<JsWord, JsWord>,
+  specifiers: &'r HashMap<String, String>,
+  import_meta: Option<&'r JsWord>,
+}
+
+impl<'r> Renamer<'r> {
+  fn final_name(&self, ident: &Ident) -> Option<&'r JsWord> {
+    if ident.span.ctxt() == self.top_level_ctxt {
+      self
+        .renames
+        .get(&ident.sym)
+        .filter(|name| **name != ident.sym)
+    } else {
+      None
+    }
+  }
+}
+
+impl<'r> Fold for Renamer<'r> {
+  fn fold_ident(&mut self, ident: Ident) -> Ident {
+    match self.final_name(&ident) {
+      Some(final_name) => Ident {
+        sym: final_name.clone(),
+        ..ident
+      },
+      None => ident,
+    }
+  }
+
+  fn fold_expr(&mut self, expr: Expr) -> Expr {
+    if is_import_meta(&expr) {
+      if let Some(import_meta) = self.import_meta {
+        return Expr::Ident(Ident::new(
+          import_meta.clone(),
+          DUMMY_SP.with_ctxt(self.top_level_ctxt),
+        ));
+      }
+    }
+    expr.fold_children_with(self)
+  }
+
+  fn fold_member_expr(&mut self, member_expr: MemberExpr) -> MemberExpr {
+    let obj = member_expr.obj.fold_with(self);
+    let prop = if member_expr.computed {
+      Box::new((*member_expr.prop).fold_with(self))
+    } else {
+      member_expr.prop
+    };
+    MemberExpr {
+      obj,
+      prop,
+      ..member_expr
+    }
+  }
+
+  fn fold_prop_name(&mut self, prop_name: PropName) -> PropName {
+    match prop_name {
+      PropName::Computed(computed) => {
+        PropName::Computed(computed.fold_with(self))
+      }
+      prop_name => prop_name,
+    }
+  }
+
+  /// `{ a }` has to become `{ a: a_1 }` when `a` is renamed.
+  fn fold_prop(&mut self, prop: Prop) -> Prop {
+    match prop {
+      Prop::Shorthand(ident) => match self.final_name(&ident) {
+        Some(final_name) => Prop::KeyValue(KeyValueProp {
+          key: PropName::Ident(Ident::new(
+            ident.sym.clone(),
+            ident.span.with_ctxt(SyntaxContext::empty()),
+          )),
+          value: Box::new(Expr::Ident(Ident {
+            sym: final_name.clone(),
+            ..ident
+          })),
+        }),
+        None => Prop::Shorthand(ident),
+      },
+      prop => prop.fold_children_with(self),
+    }
+  }
+
+  /// `const { a } = b` has to become `const { a: a_1 } = b` when `a` is
+  /// renamed.
+  fn fold_object_pat_prop(&mut self, prop: ObjectPatProp) -> ObjectPatProp {
+    match prop {
+      ObjectPatProp::Assign(assign) => match self.final_name(&assign.key) {
+        Some(final_name) => {
+          let key = PropName::Ident(Ident::new(
+            assign.key.sym.clone(),
+            assign.key.span.with_ctxt(SyntaxContext::empty()),
+          ));
+          let binding = Pat::Ident(Ident {
+            sym: final_name.clone(),
+            ..assign.key
+          });
+          let value = match assign.value {
+            Some(default) => Pat::Assign(AssignPat {
+              span: assign.span,
+              left: Box::new(binding),
+              right: Box::new((*default).fold_with(self)),
+              type_ann: None,
+            }),
+            None => binding,
+          };
+          ObjectPatProp::KeyValue(KeyValuePatProp {
+            key,
+            value: Box::new(value),
+          })
+        }
+        None => ObjectPatProp::Assign(assign.fold_children_with(self)),
+      },
+      prop => prop.fold_children_with(self),
+    }
+  }
+
+  fn fold_call_expr(&mut self, call_expr: CallExpr) -> CallExpr {
+    let mut call_expr = call_expr.fold_children_with(self);
+    let resolved = dynamic_import_specifier(&call_expr)
+      .and_then(|specifier| self.specifiers.get(&*specifier.value));
+    if let Some(resolved) = resolved {
+      call_expr.args[0].expr = Box::new(Expr::Lit(Lit::Str(Str {
+        span: call_expr.args[0].expr.span(),
+        value: resolved.as_str().into(),
+        has_escape: false,
+      })));
+    }
+    call_expr
+  }
+}
+
+/// Collects the names of top level bindings that are referred to.
+struct References {
+  top_level_ctxt: SyntaxContext,
+  names: HashSet<JsWord>,
+}
+
+impl Visit for References {
+  fn visit_ident(&mut self, ident: &Ident, _parent: &dyn Node) {
+    if ident.span.ctxt() == self.top_level_ctxt {
+      self.names.insert(ident.sym.clone());
+    }
+  }
+}
+
+/// Removes top level declarations that nothing else refers to, as long as
+/// evaluating them has no side effects. Bindings in `live` are kept.
+fn remove_unused(
+  items: Vec<ModuleItem>,
+  live: &HashSet<JsWord>,
+  top_level_ctxt: SyntaxContext,
+) -> Vec<ModuleItem> {
+  let declared: Vec<Vec<JsWord>> = items
+    .iter()
+    .map(|item| match item {
+      ModuleItem::Stmt(Stmt::Decl(decl)) if is_pure_decl(decl) => {
+        decl_names(decl)
+      }
+      _ => vec![],
+    })
+    .collect();
+  let references: Vec<HashSet<JsWord>> = items
+    .iter()
+    .zip(&declared)
+    .map(|(item, declared)| {
+      let mut references = References {
+        top_level_ctxt,
+        names: HashSet::new(),
+      };
+      references.visit_module_item(item, item);
+      // A declaration referring to itself doesn't keep it alive.
+      for name in declared {
+        references.names.remove(name);
+      }
+      references.names
+    })
+    .collect();
+
+  let mut removed = vec![false; items.len()];
+  loop {
+    let mut referenced = live.clone();
+    for (i, names) in references.iter().enumerate() {
+      if !removed[i] {
+        referenced.extend(names.iter().cloned());
+      }
+    }
+    let mut changed = false;
+    for (i, names) in declared.iter().enumerate() {
+      if !removed[i]
+        && !names.is_empty()
+        && names.iter().all(|name| !referenced.contains(name))
+      {
+        removed[i] = true;
+        changed = true;
+      }
+    }
+    if !changed {
+      break;
+    }
+  }
+
+  items
+    .into_iter()
+    .zip(removed)
+    .filter(|(_, removed)| !removed)
+    .map(|(item, _)| item)
+    .collect()
+}
+
+/// Whether evaluating the declaration can't have side effects.
+fn is_pure_decl(decl: &Decl) -> bool {
+  match decl {
+    Decl::Fn(_) => true,
+    Decl::Class(class_decl) => is_pure_class(&class_decl.class),
+    Decl::Var(var_decl) => var_decl.decls.iter().all(|declarator| {
+      let is_ident = match declarator.name {
+        Pat::Ident(_) => true,
+        _ => false,
+      };
+      is_ident && declarator.init.as_ref().map_or(true, |e| is_pure_expr(e))
+    }),
+    _ => false,
+  }
+}
+
+fn is_pure_class(class: &Class) -> bool {
+  let is_pure_super_class = match class.super_class.as_ref().map(|e| &**e) {
+    None | Some(Expr::Ident(_)) => true,
+    _ => false,
+  };
+  class.decorators.is_empty()
+    && is_pure_super_class
+    && class.body.iter().all(|member| match member {
+      ClassMember::Constructor(_) => true,
+      ClassMember::Method(method) => is_pure_prop_name(&method.key),
+      ClassMember::PrivateMethod(_) => true,
+      ClassMember::ClassProp(prop) => {
+        !prop.computed
+          && (!prop.is_static
+            || prop.value.as_ref().map_or(true, |e| is_pure_expr(e)))
+      }
+      ClassMember::PrivateProp(prop) => {
+        !prop.is_static || prop.value.as_ref().map_or(true, |e| is_pure_expr(e))
+      }
+      _ => false,
+    })
+}
+
+fn is_pure_prop_name(prop_name: &PropName) -> bool {
+  match prop_name {
+    PropName::Computed(_) => false,
+    _ => true,
+  }
+}
+
+fn is_pure_expr(expr: &Expr) -> bool {
+  match expr {
+    Expr::Lit(_)
+    | Expr::Ident(_)
+    | Expr::Fn(_)
+    | Expr::Arrow(_)
+    | Expr::This(_) => true,
+    Expr::Class(class_expr) => is_pure_class(&class_expr.class),
+    Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+    Expr::Paren(paren) => is_pure_expr(&paren.expr),
+    Expr::Unary(unary) => match unary.op {
+      UnaryOp::Minus
+      | UnaryOp::Plus
+      | UnaryOp::Bang
+      | UnaryOp::Tilde
+      | UnaryOp::TypeOf
+      | UnaryOp::Void => is_pure_expr(&unary.arg),
+      UnaryOp::Delete => false,
+    },
+    Expr::Array(array) => array.elems.iter().all(|elem| match elem {
+      Some(ExprOrSpread { spread: None, expr }) => is_pure_expr(expr),
+      Some(_) => false,
+      None => true,
+    }),
+    Expr::Object(object) => object.props.iter().all(|prop| match prop {
+      PropOrSpread::Prop(prop) => match &**prop {
+        Prop::Shorthand(_) => true,
+        Prop::KeyValue(key_value) => {
+          is_pure_prop_name(&key_value.key) && is_pure_expr(&key_value.value)
+        }
+        Prop::Getter(getter) => is_pure_prop_name(&getter.key),
+        Prop::Setter(setter) => is_pure_prop_name(&setter.key),
+        Prop::Method(method) => is_pure_prop_name(&method.key),
+        _ => false,
+      },
+      PropOrSpread::Spread(_) => false,
+    }),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::doc::Location;
+  use crate::module_graph::ImportDescriptor;
+
+  fn bundle_sources(sources: &[(&str, &str)]) -> Result<String, ErrBox> {
+    let mut module_graph = ModuleGraph::new();
+    for (url, source_code) in sources {
+      let imports = sources
+        .iter()
+        .map(|(other, _)| {
+          let specifier = format!("./{}", other.rsplit('/').next().unwrap());
+          ImportDescriptor {
+            resolved_specifier: ModuleSpecifier::resolve_url(other).unwrap(),
+            specifier,
+            type_directive: None,
+            resolved_type_directive: None,
+            location: Location {
+              filename: url.to_string(),
+              line: 0,
+              col: 0,
+            },
+          }
+        })
+        .collect();
+      module_graph.insert(
+        url.to_string(),
+        ModuleGraphFile {
+          specifier: url.to_string(),
+          url: url.to_string(),
+          redirect: vec![],
+          filename: url.to_string(),
+          version_hash: "".to_string(),
+          imports,
+          referenced_files: vec![],
+          lib_directives: vec![],
+          types_directives: vec![],
+          type_headers: vec![],
+          media_type: MediaType::TypeScript,
+          source_code: source_code.to_string(),
+        },
+      );
+    }
+    let root = ModuleSpecifier::resolve_url(sources[0].0).unwrap();
+    let output = bundle(
+      &module_graph,
+      &root,
+      &Resolver::default(),
+      &BundleOptions::default(),
+    )?;
+    Ok(output.code)
+  }
+
+  #[test]
+  fn test_bundle_hoists_modules() {
+    let code = bundle_sources(&[
+      (
+        "file:///main.ts",
+        r#"
+          import { double } from "./double.ts";
+          const value: number = 2;
+          console.log(double(value));
+        "#,
+      ),
+      (
+        "file:///double.ts",
+        "const value = 2;\nexport function double(n: number) { return n * value; }",
+      ),
+    ])
+    .unwrap();
+    assert!(!code.contains("import"));
+    assert!(!code.contains(": number"));
+    // The module that is imported is evaluated first.
+    let double = code.find("function double").unwrap();
+    let log = code.find("console.log").unwrap();
+    assert!(double < log);
+    // Clashing top level bindings are renamed.
+    assert!(code.contains("const value = 2"));
+    assert!(code.contains("const value_1 = 2"));
+    assert!(code.contains("n * value;"));
+    assert!(code.contains("double(value_1)"));
+  }
+
+  #[test]
+  fn test_bundle_removes_unused_exports() {
+    let code = bundle_sources(&[
+      (
+        "file:///main.ts",
+        "import { used } from \"./lib.ts\";\nexport const main = used();",
+      ),
+      (
+        "file:///lib.ts",
+        r#"
+          export function used() { return helper(); }
+          function helper() { return 1; }
+          export function unused() { return 2; }
+          export const unusedValue = { a: 1 };
+          export const sideEffect = console.log("kept");
+        "#,
+      ),
+    ])
+    .unwrap();
+    assert!(code.contains("function used"));
+    assert!(code.contains("function helper"));
+    assert!(!code.contains("unused"));
+    assert!(code.contains("console.log(\"kept\")"));
+    assert!(code.contains("export { main }"));
+  }
+
+  #[test]
+  fn test_bundle_reexports_and_namespaces() {
+    let code = bundle_sources(&[
+      (
+        "file:///main.ts",
+        r#"
+          import * as lib from "./lib.ts";
+          import other from "./other.ts";
+          export * from "./lib.ts";
+          export { other as renamed };
+          console.log(lib.a, other);
+        "#,
+      ),
+      ("file:///lib.ts", "export const a = 1;\nexport let b = 2;"),
+      (
+        "file:///other.ts",
+        "export default function () { return 3; }",
+      ),
+    ])
+    .unwrap();
+    assert!(code.contains("const lib_ns = {"));
+    assert!(code.contains("get \"a\"()"));
+    assert!(code.contains("function _default()"));
+    assert!(code.contains("console.log(lib_ns.a, _default)"));
+    assert!(code.contains("_default as renamed"));
+    assert!(code.contains("a,"));
+    assert!(code.contains("b"));
+  }
+
+  #[test]
+  fn test_bundle_circular_namespaces() {
+    let code = bundle_sources(&[
+      (
+        "file:///a.ts",
+        "import * as b from \"./b.ts\";\nexport function f() {}\nb.g();",
+      ),
+      (
+        "file:///b.ts",
+        "import * as a from \"./a.ts\";\nexport function g() {}\na.f();",
+      ),
+    ])
+    .unwrap();
+    // `b.ts` is evaluated first and uses the namespace of `a.ts`, so the
+    // namespace objects have to be declared before any module.
+    let a_ns = code.find("const a_ns = {").unwrap();
+    let b_ns = code.find("const b_ns = {").unwrap();
+    let a_call = code.find("a_ns.f()").unwrap();
+    let b_call = code.find("b_ns.g()").unwrap();
+    assert!(a_ns < a_call && b_ns < a_call);
+    assert!(a_call < b_call);
+  }
+
+  #[test]
+  fn test_bundle_import_meta() {
+    let code = bundle_sources(&[(
+      "file:///main.ts",
+      "if (import.meta.main) console.log(import.meta.url);",
+    )])
+    .unwrap();
+    assert!(code.contains("url: \"file:///main.ts\""));
+    assert!(code.contains("main: true"));
+    assert!(code.contains("if (importMeta.main) console.log(importMeta.url)"));
+  }
+
+  #[test]
+  fn test_bundle_shadowed_import() {
+    let code = bundle_sources(&[
+      (
+        "file:///main.ts",
+        r#"
+          import { value as v } from "./lib.ts";
+          export function f(value) { return v + value; }
+        "#,
+      ),
+      ("file:///lib.ts", "export const value = 1;"),
+    ])
+    .unwrap();
+    // The parameter would shadow the exported binding if it kept its name.
+    assert!(code.contains("const value_1 = 1"));
+    assert!(code.contains("function f(value)"));
+    assert!(code.contains("return value_1 + value;"));
+  }
+
+  #[test]
+  fn test_bundle_missing_export() {
+    let err = bundle_sources(&[
+      (
+        "file:///main.ts",
+        "import { b } from \"./lib.ts\";\nconsole.log(b);",
+      ),
+      ("file:///lib.ts", "export const a = 1;"),
+    ])
+    .unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("does not provide an export named \"b\""));
+  }
+
+  #[test]
+  fn test_module_stem() {
+    assert_eq!(module_stem("https://deno.land/std/fs/mod.ts"), "mod");
+    assert_eq!(module_stem("file:///a/some-file.d.ts"), "some_file");
+    assert_eq!(module_stem("file:///a/1.ts"), "_1");
+  }
+}
@@ -0,0 +1,574 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Garbage collection of `$DENO_DIR`, run with `deno cache clean` or
+//! `deno cache --gc`.
+//!
+//! Files are grouped into entries that are removed together: a remote module
+//! in `deps/` with its `.metadata.json`, and the `.js`, `.js.map`, `.meta` and
+//! `.buildinfo` files that the compiler emitted for a module to `gen/`. Build
+//! info is additionally keyed by a hash of the compiler config, which is part
+//! of its extension. The files are removed while holding the locks of both
+//! caches, unless they changed after the caches were scanned.
+//!
+//! Stale entries are always removed. Compiler output is stale when its source
+//! is no longer cached, when its `.meta` file is missing or corrupt, or when
+//! `CompiledFileMetadata::source_hash` doesn't match the source anymore; the
+//! compiler config that it was compiled with doesn't matter. A remote module
+//! is stale when its metadata is missing or corrupt. Then entries older than
+//! `max_age` are removed, and the oldest until the cache fits in `max_size`.
+use crate::fs::FileLock;
+use crate::global_state::GlobalState;
+use crate::http_cache::url_to_filename;
+use crate::http_cache::Metadata;
+use crate::permissions::Permissions;
+use crate::tsc::source_code_hash;
+use crate::tsc::CompiledFileMetadata;
+use crate::tsc::BUILD_INFO_HASH_LEN;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use url::Url;
+use walkdir::WalkDir;
+
+/// Extensions of the files that the compiler writes to `gen/`, longest first.
+const GEN_EXTENSIONS: [&str; 4] = [".js.map", ".buildinfo", ".meta", ".js"];
+const BUILD_INFO_EXTENSION: &str = ".buildinfo";
+const METADATA_EXTENSION: &str = ".metadata.json";
+const TEMP_EXTENSION: &str = ".tmp";
+/// Temporary files of `write_file_atomic` that are older than this were left
+/// behind by a process that was killed while writing.
+const TEMP_FILE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Default)]
+pub struct GcOptions {
+  pub max_size: Option<u64>,
+  pub max_age: Option<Duration>,
+  pub dry_run: bool,
+}
+
+impl GcOptions {
+  /// Converts a `--max-age` in days, or returns `None` if it overflows.
+  pub fn max_age_from_days(days: u64) -> Option<Duration> {
+    days.checked_mul(24 * 60 * 60).map(Duration::from_secs)
+  }
+}
+
+/// Files that are removed together.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+  files: Vec<PathBuf>,
+  size: u64,
+  modified: SystemTime,
+  stale: bool,
+  /// For compiler output of a remote module, the cached source in `deps/`.
+  source: Option<PathBuf>,
+}
+
+impl CacheEntry {
+  fn new() -> Self {
+    Self {
+      files: vec![],
+      size: 0,
+      modified: SystemTime::UNIX_EPOCH,
+      stale: false,
+      source: None,
+    }
+  }
+
+  fn add_file(&mut self, path: PathBuf, metadata: &fs::Metadata) {
+    self.size += metadata.len();
+    if let Ok(modified) = metadata.modified() {
+      self.modified = self.modified.max(modified);
+    }
+    self.files.push(path);
+  }
+
+  /// Whether the files still have the size and modification time that they
+  /// had when the entry was scanned.
+  fn is_unchanged(&self) -> bool {
+    let mut entry = CacheEntry::new();
+    for file in &self.files {
+      match fs::metadata(file) {
+        Ok(metadata) => entry.add_file(file.clone(), &metadata),
+        Err(_) => return false,
+      }
+    }
+    entry.size == self.size && entry.modified == self.modified
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct GcReport {
+  /// Files that were removed, or would be removed with `dry_run`.
+  pub files: Vec<PathBuf>,
+  pub reclaimed_bytes: u64,
+}
+
+/// Removes stale and old files from `deps/` and `gen/` of `$DENO_DIR`.
+pub fn collect_garbage(
+  global_state: &GlobalState,
+  options: &GcOptions,
+) -> Result<GcReport, ErrBox> {
+  let deps_dir = &global_state.file_fetcher.http_cache.location;
+  let gen_dir = &global_state.dir.gen_cache.location;
+
+  let mut entries = deps_entries(deps_dir)?;
+  entries.extend(temp_file_entries(deps_dir)?);
+  entries.extend(temp_file_entries(gen_dir)?);
+  for mut entry in gen_entries(gen_dir)?.into_iter() {
+    let stem = gen_stem(&entry.files[0]).unwrap();
+    match gen_path_to_url(stem.strip_prefix(gen_dir).unwrap()) {
+      Some(url) => {
+        entry.stale = is_output_stale(global_state, &url, &stem);
+        if url.scheme() != "file" {
+          entry.source = Some(deps_dir.join(url_to_filename(&url)));
+        }
+      }
+      // Output that can't be mapped back to a module is left alone.
+      None => continue,
+    }
+    entries.push(entry);
+  }
+
+  let mut victims = select_victims(entries, options, SystemTime::now());
+  let _locks = if options.dry_run {
+    None
+  } else {
+    let gen_lock = global_state.dir.gen_cache.lock()?;
+    let deps_lock = if deps_dir.is_dir() {
+      Some(FileLock::exclusive(&FileLock::filename(deps_dir))?)
+    } else {
+      None
+    };
+    // The caches are scanned before taking their locks, because reading a
+    // cached module takes a shared lock. Entries written since are kept.
+    victims.retain(CacheEntry::is_unchanged);
+    Some((gen_lock, deps_lock))
+  };
+
+  let mut report = GcReport::default();
+  for entry in victims {
+    report.reclaimed_bytes += entry.size;
+    report.files.extend(entry.files);
+  }
+  report.files.sort();
+
+  if !options.dry_run {
+    for file in &report.files {
+      match fs::remove_file(file) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+      }
+    }
+    remove_empty_dirs(deps_dir);
+    remove_empty_dirs(gen_dir);
+  }
+
+  Ok(report)
+}
+
+/// Checks the compiler output with `stem` against the cached source of `url`.
+fn is_output_stale(global_state: &GlobalState, url: &Url, stem: &Path) -> bool {
+  let specifier = ModuleSpecifier::from(url.clone());
+  let source_file = match global_state
+    .file_fetcher
+    .fetch_cached_source_file(&specifier, Permissions::allow_all())
+  {
+    Some(source_file) => source_file,
+    None => return true,
+  };
+  let mut meta_path = stem.as_os_str().to_owned();
+  meta_path.push(".meta");
+  let metadata = match fs::read_to_string(meta_path)
+    .ok()
+    .and_then(|s| CompiledFileMetadata::from_json_string(s).ok())
+  {
+    Some(metadata) => metadata,
+    None => return true,
+  };
+  match metadata.source_hash {
+    Some(hash) => hash != source_code_hash(&source_file.source_code),
+    // The version hash can't be checked without the compiler config that the
+    // output was compiled with, so it's left to `max_age` and `max_size`.
+    None => false,
+  }
+}
+
+/// Returns the entries that are removed: stale ones, ones older than
+/// `max_age` and then the oldest ones until the rest fits in `max_size`.
+/// Compiler output of a remote module is removed with the module.
+fn select_victims(
+  entries: Vec<CacheEntry>,
+  options: &GcOptions,
+  now: SystemTime,
+) -> Vec<CacheEntry> {
+  let is_too_old = |entry: &CacheEntry| match options.max_age {
+    Some(max_age) => now
+      .duration_since(entry.modified)
+      .map(|age| age > max_age)
+      .unwrap_or(false),
+    None => false,
+  };
+  let (mut victims, mut kept): (Vec<_>, Vec<_>) = entries
+    .into_iter()
+    .partition(|entry| entry.stale || is_too_old(entry));
+
+  if let Some(max_size) = options.max_size {
+    // Oldest last, so they are popped first.
+    kept.sort_by(|a, b| b.modified.cmp(&a.modified));
+    let mut size: u64 = kept.iter().map(|entry| entry.size).sum();
+    while size > max_size {
+      let entry = kept.pop().unwrap();
+      size -= entry.size;
+      victims.push(entry);
+    }
+  }
+
+  let removed_sources: HashSet<PathBuf> = victims
+    .iter()
+    .flat_map(|entry| entry.files.iter().cloned())
+    .collect();
+  let (orphans, _): (Vec<_>, Vec<_>) =
+    kept.into_iter().partition(|entry| match &entry.source {
+      Some(source) => removed_sources.contains(source),
+      None => false,
+    });
+  victims.extend(orphans);
+  victims
+}
+
+/// Groups the remote modules in `deps/` with their metadata.
+fn deps_entries(deps_dir: &Path) -> Result<Vec<CacheEntry>, ErrBox> {
+  let mut entries: HashMap<PathBuf, CacheEntry> = HashMap::new();
+  for (path, metadata) in walk_files(deps_dir, false)? {
+    let stem = strip_extension(&path, &[METADATA_EXTENSION, ""]).unwrap();
+    entries
+      .entry(stem)
+      .or_insert_with(CacheEntry::new)
+      .add_file(path, &metadata);
+  }
+  Ok(
+    entries
+      .into_iter()
+      .map(|(stem, mut entry)| {
+        entry.stale = !stem.is_file() || Metadata::read(&stem).is_err();
+        entry
+      })
+      .collect(),
+  )
+}
+
+/// Groups the files that the compiler emitted for the same module.
+fn gen_entries(gen_dir: &Path) -> Result<Vec<CacheEntry>, ErrBox> {
+  let mut entries: HashMap<PathBuf, CacheEntry> = HashMap::new();
+  for (path, metadata) in walk_files(gen_dir, false)? {
+    if let Some(stem) = gen_stem(&path) {
+      entries
+        .entry(stem)
+        .or_insert_with(CacheEntry::new)
+        .add_file(path, &metadata);
+    }
+  }
+  Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Temporary files that are old enough to be left behind, as stale entries.
+fn temp_file_entries(dir: &Path) -> Result<Vec<CacheEntry>, ErrBox> {
+  let now = SystemTime::now();
+  let mut entries = vec![];
+  for (path, metadata) in walk_files(dir, true)? {
+    let mut entry = CacheEntry::new();
+    entry.add_file(path, &metadata);
+    entry.stale = true;
+    if now.duration_since(entry.modified).unwrap_or_default()
+      > TEMP_FILE_MAX_AGE
+    {
+      entries.push(entry);
+    }
+  }
+  Ok(entries)
+}
+
+/// Returns the temporary files of `write_file_atomic` in `dir` if
+/// `temp_files` is set, and the other files otherwise. The lock file of the
+/// cache is skipped.
+fn walk_files(
+  dir: &Path,
+  temp_files: bool,
+) -> Result<Vec<(PathBuf, fs::Metadata)>, ErrBox> {
+  let mut files = vec![];
+  if !dir.is_dir() {
+    return Ok(files);
+  }
+  let lock_filename = FileLock::filename(dir);
+  for entry in WalkDir::new(dir) {
+    let entry = entry.map_err(io::Error::from)?;
+    if entry.path() == lock_filename {
+      continue;
+    }
+    let name = entry.file_name().to_string_lossy();
+    let is_temp_file = name.starts_with('.') && name.ends_with(TEMP_EXTENSION);
+    if entry.file_type().is_file() && is_temp_file == temp_files {
+      let metadata = entry.metadata().map_err(io::Error::from)?;
+      files.push((entry.into_path(), metadata));
+    }
+  }
+  Ok(files)
+}
+
+/// Strips one of `extensions` from `path`.
+fn strip_extension(path: &Path, extensions: &[&str]) -> Option<PathBuf> {
+  let path_str = path.to_str()?;
+  extensions
+    .iter()
+    .find(|ext| path_str.ends_with(*ext))
+    .map(|ext| PathBuf::from(&path_str[..path_str.len() - ext.len()]))
+}
+
+/// Strips the extension of a file in `gen/`, including the compiler config
+/// hash of `.buildinfo` files.
+fn gen_stem(path: &Path) -> Option<PathBuf> {
+  let stem = strip_extension(path, &GEN_EXTENSIONS)?;
+  if strip_extension(path, &[BUILD_INFO_EXTENSION]).is_none() {
+    return Some(stem);
+  }
+  let is_config_hash = match stem.extension().and_then(|ext| ext.to_str()) {
+    Some(ext) => {
+      ext.len() == BUILD_INFO_HASH_LEN
+        && ext.chars().all(|c| c.is_ascii_hexdigit())
+    }
+    None => false,
+  };
+  if is_config_hash {
+    Some(stem.with_extension(""))
+  } else {
+    Some(stem)
+  }
+}
+
+/// Reverses `DiskCache::get_cache_filename` for a path relative to `gen/`.
+fn gen_path_to_url(path: &Path) -> Option<Url> {
+  let mut components = path.iter().map(|c| c.to_str());
+  let scheme = components.next()??;
+  let rest: Vec<&str> = components.collect::<Option<_>>()?;
+  match scheme {
+    "http" | "https" => {
+      let (host, segments) = rest.split_first()?;
+      // Ports are stored as "host_PORT8080".
+      let host = match host.rfind("_PORT") {
+        Some(i) => format!("{}:{}", &host[..i], &host[i + 5..]),
+        None => host.to_string(),
+      };
+      Url::parse(&format!("{}://{}/{}", scheme, host, segments.join("/"))).ok()
+    }
+    "file" if cfg!(windows) => {
+      let (disk, segments) = rest.split_first()?;
+      if disk.len() != 1 {
+        return None;
+      }
+      let path = format!("{}:\\{}", disk, segments.join("\\"));
+      Url::from_file_path(path).ok()
+    }
+    "file" => Url::from_file_path(format!("/{}", rest.join("/"))).ok(),
+    _ => None,
+  }
+}
+
+/// Removes the empty directories below `dir`, which is kept.
+fn remove_empty_dirs(dir: &Path) {
+  let empty_dirs = WalkDir::new(dir)
+    .min_depth(1)
+    .contents_first(true)
+    .into_iter()
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().is_dir());
+  for entry in empty_dirs {
+    // Fails for directories that aren't empty.
+    let _ = fs::remove_dir(entry.path());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+  fn entry(name: &str, size: u64, age_days: u64, stale: bool) -> CacheEntry {
+    CacheEntry {
+      files: vec![PathBuf::from(name)],
+      size,
+      modified: SystemTime::UNIX_EPOCH + DAY * (100 - age_days as u32),
+      stale,
+      source: None,
+    }
+  }
+
+  fn select(entries: Vec<CacheEntry>, options: GcOptions) -> Vec<String> {
+    let now = SystemTime::UNIX_EPOCH + DAY * 100;
+    let mut names: Vec<String> = select_victims(entries, &options, now)
+      .into_iter()
+      .map(|entry| entry.files[0].to_string_lossy().to_string())
+      .collect();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn test_select_stale() {
+    let entries = vec![entry("a", 10, 1, true), entry("b", 10, 50, false)];
+    assert_eq!(select(entries, GcOptions::default()), vec!["a"]);
+  }
+
+  #[test]
+  fn test_select_max_age() {
+    let entries = vec![
+      entry("a", 10, 1, false),
+      entry("b", 10, 31, false),
+      entry("c", 10, 29, false),
+    ];
+    let options = GcOptions {
+      max_age: Some(DAY * 30),
+      ..GcOptions::default()
+    };
+    assert_eq!(select(entries, options), vec!["b"]);
+  }
+
+  #[test]
+  fn test_select_max_size() {
+    let entries = vec![
+      entry("a", 10, 1, false),
+      entry("b", 10, 3, false),
+      entry("c", 10, 2, false),
+      entry("d", 100, 0, true),
+    ];
+    let options = GcOptions {
+      max_size: Some(15),
+      ..GcOptions::default()
+    };
+    assert_eq!(select(entries, options), vec!["b", "c", "d"]);
+  }
+
+  #[test]
+  fn test_select_removes_output_of_removed_source() {
+    let mut output = entry("gen/https/deno.land/mod.ts.js", 10, 0, false);
+    output.source = Some(PathBuf::from("deps/https/deno.land/abc"));
+    let entries = vec![output, entry("deps/https/deno.land/abc", 10, 9, true)];
+    assert_eq!(
+      select(entries, GcOptions::default()),
+      vec!["deps/https/deno.land/abc", "gen/https/deno.land/mod.ts.js"]
+    );
+  }
+
+  #[test]
+  fn test_max_age_from_days() {
+    assert_eq!(GcOptions::max_age_from_days(30), Some(DAY * 30));
+    assert_eq!(GcOptions::max_age_from_days(std::u64::MAX), None);
+  }
+
+  #[test]
+  fn test_gen_path_to_url() {
+    let url =
+      |path: &str| gen_path_to_url(Path::new(path)).map(|u| u.to_string());
+    assert_eq!(
+      url("https/deno.land/std/http/server.ts"),
+      Some("https://deno.land/std/http/server.ts".to_string())
+    );
+    assert_eq!(
+      url("http/localhost_PORT4545/cli/tests/002_hello.ts"),
+      Some("http://localhost:4545/cli/tests/002_hello.ts".to_string())
+    );
+    assert_eq!(url("blob/abc"), None);
+    if cfg!(not(windows)) {
+      assert_eq!(
+        url("file/home/deno/main.ts"),
+        Some("file:///home/deno/main.ts".to_string())
+      );
+    }
+  }
+
+  #[test]
+  fn test_gen_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().join("file/home");
+    fs::create_dir_all(&dir).unwrap();
+    for name in &[
+      "a.ts.js",
+      "a.ts.js.map",
+      "a.ts.meta",
+      "a.ts.0123456789abcdef.buildinfo",
+      "b.js.meta",
+      "c.txt",
+      ".a.ts.js.0123abcd.tmp",
+    ] {
+      fs::write(dir.join(name), "1234").unwrap();
+    }
+    fs::write(FileLock::filename(temp_dir.path()), "").unwrap();
+    let mut entries = gen_entries(temp_dir.path()).unwrap();
+    entries.sort_by(|a, b| a.files.len().cmp(&b.files.len()));
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].files, vec![dir.join("b.js.meta")]);
+    assert_eq!(entries[1].files.len(), 4);
+    assert_eq!(entries[1].size, 16);
+    let temp_files = walk_files(temp_dir.path(), true).unwrap();
+    assert_eq!(temp_files.len(), 1);
+    // The temporary file might still be written to.
+    assert!(temp_file_entries(temp_dir.path()).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_deps_entries_stale() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().join("https/deno.land");
+    fs::create_dir_all(&dir).unwrap();
+    let metadata = r#"{ "headers": {}, "url": "https://deno.land/a.ts" }"#;
+    fs::write(dir.join("aaa"), "export {}").unwrap();
+    fs::write(dir.join("aaa.metadata.json"), metadata).unwrap();
+    fs::write(dir.join("bbb"), "export {}").unwrap();
+    fs::write(dir.join("ccc"), "export {}").unwrap();
+    fs::write(dir.join("ccc.metadata.json"), "{").unwrap();
+    fs::write(dir.join("ddd.metadata.json"), metadata).unwrap();
+    fs::write(FileLock::filename(temp_dir.path()), "").unwrap();
+    let mut stale: Vec<(usize, bool)> = deps_entries(temp_dir.path())
+      .unwrap()
+      .into_iter()
+      .map(|entry| (entry.files.len(), entry.stale))
+      .collect();
+    stale.sort();
+    assert_eq!(stale, vec![(1, true), (1, true), (2, false), (2, true)]);
+  }
+
+  #[test]
+  fn test_entry_is_unchanged() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("a.ts.js");
+    fs::write(&path, "1234").unwrap();
+    let mut entry = CacheEntry::new();
+    entry.add_file(path.clone(), &fs::metadata(&path).unwrap());
+    assert!(entry.is_unchanged());
+    fs::write(&path, "123456").unwrap();
+    assert!(!entry.is_unchanged());
+    fs::remove_file(&path).unwrap();
+    assert!(!entry.is_unchanged());
+  }
+
+  #[test]
+  fn test_remove_empty_dirs() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("a/b/c")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("d")).unwrap();
+    fs::write(temp_dir.path().join("d/e"), "").unwrap();
+    remove_empty_dirs(temp_dir.path());
+    assert!(temp_dir.path().is_dir());
+    assert!(!temp_dir.path().join("a").exists());
+    assert!(temp_dir.path().join("d/e").exists());
+  }
+}
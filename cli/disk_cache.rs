@@ -1,4 +1,5 @@
 use crate::fs as deno_fs;
+use crate::fs::FileLock;
 use std::ffi::OsStr;
 use std::fs;
 use std::io;
@@ -134,13 +135,32 @@ impl DiskCache {
     fs::read(&path)
   }
 
+  /// Waits for the exclusive lock of the cache, which callers of `set` hold
+  /// while they write the files that belong together.
+  pub fn lock(&self) -> std::io::Result<FileLock> {
+    self.ensure_dir_exists(&self.location)?;
+    let path = FileLock::filename(&self.location);
+    FileLock::exclusive(&path)
+      .map_err(|e| with_io_context(&e, format!("{:#?}", &path)))
+  }
+
+  /// Waits for a shared lock of the cache, so that files that belong
+  /// together are read while no other process writes them.
+  pub fn lock_shared(&self) -> std::io::Result<Option<FileLock>> {
+    let path = FileLock::filename(&self.location);
+    FileLock::shared(&path)
+      .map_err(|e| with_io_context(&e, format!("{:#?}", &path)))
+  }
+
+  /// Writes the file atomically, so that processes sharing the cache never
+  /// see a partially written file. The caller holds the lock of the cache.
   pub fn set(&self, filename: &Path, data: &[u8]) -> std::io::Result<()> {
     let path = self.location.join(filename);
     match path.parent() {
       Some(ref parent) => self.ensure_dir_exists(parent),
       None => Ok(()),
     }?;
-    deno_fs::write_file(&path, data, 0o666)
+    deno_fs::write_file_atomic(&path, data, 0o666)
       .map_err(|e| with_io_context(&e, format!("{:#?}", &path)))
   }
 
@@ -1,16 +1,19 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::auth_tokens::AuthTokens;
 use crate::colors;
 use crate::http_cache::HttpCache;
 use crate::http_util;
-use crate::http_util::create_http_client;
+use crate::http_util::create_http_client_with_options;
 use crate::http_util::FetchOnceResult;
+use crate::http_util::HttpClientOptions;
 use crate::msg;
 use crate::op_error::OpError;
 use crate::permissions::Permissions;
+use crate::progress::DownloadProgress;
 use deno_core::ErrBox;
 use deno_core::ModuleSpecifier;
 use futures::future::FutureExt;
-use log::info;
+use rand::Rng;
 use std::collections::HashMap;
 use std::fs;
 use std::future::Future;
@@ -22,15 +25,19 @@ use std::result::Result;
 use std::str;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 use url::Url;
 
 /// Structure representing local or remote file.
 ///
 /// In case of remote file `url` might be different than originally requested URL, if so
-/// `redirect_source_url` will contain original URL and `url` will be equal to final location.
+/// `redirects` will contain original URL followed by every intermediate redirect and `url`
+/// will be equal to final location.
 #[derive(Debug, Clone)]
 pub struct SourceFile {
   pub url: Url,
+  pub redirects: Vec<Url>,
   pub filename: PathBuf,
   pub types_header: Option<String>,
   pub media_type: msg::MediaType,
@@ -59,6 +66,21 @@ impl SourceFileCache {
 
 const SUPPORTED_URL_SCHEMES: [&str; 3] = ["http", "https", "file"];
 
+/// Number of concurrent downloads, unless `--max-downloads` is passed.
+pub const DEFAULT_MAX_DOWNLOADS: usize = 16;
+
+/// Number of times that a download is retried after a transient failure.
+const MAX_DOWNLOAD_RETRIES: u32 = 3;
+
+/// Returns the delay before retry number `attempt`, starting at 1. It
+/// doubles with every attempt and has random jitter, so that downloads
+/// that failed together aren't retried in lockstep.
+fn retry_delay(attempt: u32) -> Duration {
+  let base = 250 * 2u64.pow(attempt - 1);
+  let jitter: f64 = rand::thread_rng().gen_range(0.5, 1.0);
+  Duration::from_millis((base as f64 * jitter) as u64)
+}
+
 #[derive(Clone)]
 pub struct SourceFileFetcher {
   source_file_cache: SourceFileCache,
@@ -67,6 +89,11 @@ pub struct SourceFileFetcher {
   no_remote: bool,
   cached_only: bool,
   http_client: reqwest::Client,
+  /// Credentials from `DENO_AUTH_TOKENS` that are sent with downloads.
+  auth_tokens: AuthTokens,
+  /// Limits the number of requests that are in flight at once.
+  download_permits: Arc<Semaphore>,
+  progress: DownloadProgress,
   // This field is public only to expose it's location
   pub http_cache: HttpCache,
 }
@@ -78,7 +105,8 @@ impl SourceFileFetcher {
     cache_blocklist: Vec<String>,
     no_remote: bool,
     cached_only: bool,
-    ca_file: Option<String>,
+    http_client_options: HttpClientOptions,
+    max_downloads: usize,
   ) -> Result<Self, ErrBox> {
     let file_fetcher = Self {
       http_cache,
@@ -87,7 +115,10 @@ impl SourceFileFetcher {
       use_disk_cache,
       no_remote,
       cached_only,
-      http_client: create_http_client(ca_file)?,
+      http_client: create_http_client_with_options(http_client_options)?,
+      auth_tokens: AuthTokens::from_env(),
+      download_permits: Arc::new(Semaphore::new(max_downloads)),
+      progress: DownloadProgress::new(),
     };
 
     Ok(file_fetcher)
@@ -316,6 +347,7 @@ impl SourceFileFetcher {
     let media_type = map_content_type(&filepath, None);
     Ok(SourceFile {
       url: module_url.clone(),
+      redirects: vec![],
       filename: filepath,
       media_type,
       source_code,
@@ -353,6 +385,10 @@ impl SourceFileFetcher {
             return Ok(None);
           }
         }
+        // Corrupt metadata is a cache miss, the module is downloaded again.
+        if e.downcast_ref::<serde_json::Error>().is_some() {
+          return Ok(None);
+        }
         return Err(e);
       }
       Ok(c) => c,
@@ -371,8 +407,28 @@ impl SourceFileFetcher {
           return Err(e.into());
         }
       };
-      return self
-        .fetch_cached_remote_source(&redirect_url, redirect_limit - 1);
+      // Jump straight to the module if all the hops of the redirect were
+      // recorded, otherwise follow the redirect one hop at a time.
+      let mut hops = self
+        .http_cache
+        .get_metadata(&module_url)?
+        .redirects
+        .iter()
+        .map(|url| Url::parse(url))
+        .collect::<Result<Vec<Url>, _>>()?;
+      if hops.is_empty() {
+        hops.push(redirect_url);
+      }
+      let target = hops.pop().unwrap();
+      let redirect_limit = redirect_limit - hops.len() as i64 - 1;
+      let maybe_source_file =
+        self.fetch_cached_remote_source(&target, redirect_limit)?;
+      return Ok(maybe_source_file.map(|mut source_file| {
+        hops.insert(0, module_url.clone());
+        hops.append(&mut source_file.redirects);
+        source_file.redirects = hops;
+        source_file
+      }));
     }
 
     let mut source_code = Vec::new();
@@ -387,6 +443,7 @@ impl SourceFileFetcher {
     let types_header = headers.get("x-typescript-types").map(|e| e.to_string());
     Ok(Some(SourceFile {
       url: module_url.clone(),
+      redirects: vec![],
       filename: cache_filename,
       media_type,
       source_code,
@@ -448,7 +505,7 @@ impl SourceFileFetcher {
       .boxed_local();
     }
 
-    info!("{} {}", colors::green("Download"), module_url.to_string());
+    let mut download = self.progress.start(module_url);
 
     let dir = self.clone();
     let module_url = module_url.clone();
@@ -458,10 +515,47 @@ impl SourceFileFetcher {
     };
     let permissions = permissions.clone();
     let http_client = self.http_client.clone();
+    let maybe_auth_token = self.auth_tokens.get(&module_url).cloned();
+    let download_permits = self.download_permits.clone();
+    let progress = self.progress.clone();
     // Single pass fetch, either yields code or yields redirect.
     let f = async move {
-      match http_util::fetch_once(http_client, &module_url, module_etag).await?
-      {
+      let mut attempt = 0;
+      let result = loop {
+        let permit = download_permits.acquire().await;
+        let result = http_util::fetch_once(
+          http_client.clone(),
+          &module_url,
+          module_etag.clone(),
+          maybe_auth_token.clone(),
+        )
+        .await;
+        drop(permit);
+        match result {
+          Err(err)
+            if attempt < MAX_DOWNLOAD_RETRIES
+              && http_util::is_retryable_error(&err) =>
+          {
+            attempt += 1;
+            let delay = retry_delay(attempt);
+            progress.println(&format!(
+              "{} {} ({}), retrying in {}ms",
+              colors::yellow("Retry"),
+              module_url,
+              err,
+              delay.as_millis()
+            ));
+            tokio::time::delay_for(delay).await;
+          }
+          result => break result,
+        }
+      };
+      if let Ok(FetchOnceResult::Code(source, _)) = &result {
+        download.set_bytes(source.len());
+      }
+      drop(download);
+
+      match result? {
         FetchOnceResult::NotModified => {
           let source_file =
             dir.fetch_cached_remote_source(&module_url, 10)?.unwrap();
@@ -469,11 +563,8 @@ impl SourceFileFetcher {
           Ok(source_file)
         }
         FetchOnceResult::Redirect(new_module_url, headers) => {
-          // If redirects, update module_name and filename for next looped call.
-          dir.http_cache.set(&module_url, headers, &[])?;
-
           // Recurse
-          dir
+          let mut source_file = dir
             .fetch_remote_source(
               &new_module_url,
               use_disk_cache,
@@ -481,7 +572,15 @@ impl SourceFileFetcher {
               redirect_limit - 1,
               &permissions,
             )
-            .await
+            .await?;
+
+          // The redirect is cached with all the hops that follow it.
+          let mut hops = source_file.redirects.clone();
+          hops.push(source_file.url.clone());
+          dir.http_cache.set_redirect(&module_url, headers, &hops)?;
+
+          source_file.redirects.insert(0, module_url);
+          Ok(source_file)
         }
         FetchOnceResult::Code(source, headers) => {
           // We land on the code.
@@ -500,6 +599,7 @@ impl SourceFileFetcher {
 
           let source_file = SourceFile {
             url: module_url.clone(),
+            redirects: vec![],
             filename: cache_filepath,
             media_type,
             source_code: source,
@@ -644,7 +744,8 @@ mod tests {
       vec![],
       false,
       false,
-      None,
+      HttpClientOptions::default(),
+      DEFAULT_MAX_DOWNLOADS,
     )
     .expect("setup fail")
   }
@@ -1096,6 +1197,35 @@ mod tests {
 
     // Examine the meta result.
     assert_eq!(mod_meta.url, target_url);
+    assert_eq!(
+      mod_meta.redirects,
+      vec![double_redirect_url.clone(), redirect_url.clone()]
+    );
+
+    // Every hop is recorded with the redirect.
+    let metadata = fetcher
+      .http_cache
+      .get_metadata(&double_redirect_url)
+      .unwrap();
+    assert_eq!(
+      metadata.redirects,
+      vec![redirect_url.to_string(), target_url.to_string()]
+    );
+    let metadata = fetcher.http_cache.get_metadata(&redirect_url).unwrap();
+    assert_eq!(metadata.redirects, vec![target_url.to_string()]);
+
+    // The cached redirect leads straight to the target, without reading
+    // the intermediate redirect.
+    fs::remove_file(&redirect_path).unwrap();
+    let source_file = fetcher
+      .fetch_cached_remote_source(&double_redirect_url, 10)
+      .unwrap()
+      .unwrap();
+    assert_eq!(source_file.url, target_url);
+    assert_eq!(
+      source_file.redirects,
+      vec![double_redirect_url, redirect_url]
+    );
 
     drop(http_server_guard);
   }
@@ -1,7 +1,8 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use std::env::current_dir;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
 use std::path::{Component, Path, PathBuf};
 
 use deno_core::ErrBox;
@@ -46,6 +47,128 @@ pub fn write_file_2<T: AsRef<[u8]>>(
   file.write_all(data.as_ref())
 }
 
+/// Writes `data` to a temporary file next to `filename` and renames it to
+/// `filename`, so that readers see either the old or the new contents, but
+/// never a partially written file.
+pub fn write_file_atomic<T: AsRef<[u8]>>(
+  filename: &Path,
+  data: T,
+  mode: u32,
+) -> io::Result<()> {
+  let mut temp_name = OsString::from(".");
+  temp_name.push(filename.file_name().unwrap_or_default());
+  temp_name.push(format!(".{:08x}.tmp", rand::random::<u32>()));
+  let temp_filename = filename.with_file_name(temp_name);
+  let result = write_file(&temp_filename, data, mode)
+    .and_then(|_| fs::rename(&temp_filename, filename));
+  if result.is_err() {
+    let _ = fs::remove_file(&temp_filename);
+  }
+  result
+}
+
+/// An advisory lock on a file, which is released when it is dropped. Other
+/// processes only respect it when they lock the same file too.
+///
+/// The caches in `$DENO_DIR` each have a single lock file, see
+/// `FileLock::filename`. Files that belong together are written while holding
+/// the exclusive lock and read while holding a shared lock. The locks aren't
+/// reentrant, so a process must not lock the same cache again while it holds
+/// its lock.
+pub struct FileLock {
+  file: File,
+}
+
+impl FileLock {
+  /// Waits for an exclusive lock on `filename`, which is created if it
+  /// doesn't exist.
+  pub fn exclusive(filename: &Path) -> io::Result<Self> {
+    let file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .open(filename)?;
+    lock_file(&file, true)?;
+    Ok(Self { file })
+  }
+
+  /// Waits for a shared lock on `filename`. Returns `None` if it doesn't
+  /// exist, as then nothing was ever written while holding the lock.
+  pub fn shared(filename: &Path) -> io::Result<Option<Self>> {
+    let file = match OpenOptions::new().read(true).open(filename) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    lock_file(&file, false)?;
+    Ok(Some(Self { file }))
+  }
+
+  /// Returns the name of the lock file of the cache in `dir`.
+  pub fn filename(dir: &Path) -> PathBuf {
+    dir.join(".lock")
+  }
+}
+
+impl Drop for FileLock {
+  fn drop(&mut self) {
+    let _ = unlock_file(&self.file);
+  }
+}
+
+#[cfg(unix)]
+fn lock_file(file: &File, exclusive: bool) -> io::Result<()> {
+  use std::os::unix::io::AsRawFd;
+  let operation = if exclusive {
+    libc::LOCK_EX
+  } else {
+    libc::LOCK_SH
+  };
+  match unsafe { libc::flock(file.as_raw_fd(), operation) } {
+    0 => Ok(()),
+    _ => Err(io::Error::last_os_error()),
+  }
+}
+
+#[cfg(unix)]
+fn unlock_file(file: &File) -> io::Result<()> {
+  use std::os::unix::io::AsRawFd;
+  match unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } {
+    0 => Ok(()),
+    _ => Err(io::Error::last_os_error()),
+  }
+}
+
+#[cfg(windows)]
+fn lock_file(file: &File, exclusive: bool) -> io::Result<()> {
+  use std::os::windows::io::AsRawHandle;
+  use winapi::um::fileapi::LockFileEx;
+  use winapi::um::minwinbase::{LOCKFILE_EXCLUSIVE_LOCK, OVERLAPPED};
+  let flags = if exclusive {
+    LOCKFILE_EXCLUSIVE_LOCK
+  } else {
+    0
+  };
+  let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+  let ret = unsafe {
+    LockFileEx(file.as_raw_handle(), flags, 0, !0, !0, &mut overlapped)
+  };
+  match ret {
+    0 => Err(io::Error::last_os_error()),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(windows)]
+fn unlock_file(file: &File) -> io::Result<()> {
+  use std::os::windows::io::AsRawHandle;
+  use winapi::um::fileapi::UnlockFile;
+  match unsafe { UnlockFile(file.as_raw_handle(), 0, 0, !0, !0) } {
+    0 => Err(io::Error::last_os_error()),
+    _ => Ok(()),
+  }
+}
+
 /// Normalize all itermediate components of the path (ie. remove "./" and "../" components).
 /// Similar to `fs::canonicalize()` but doesn't resolve symlinks.
 ///
@@ -129,6 +252,39 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_write_file_atomic() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let filename = temp_dir.path().join("a.txt");
+    write_file_atomic(&filename, "hello", 0o666).unwrap();
+    write_file_atomic(&filename, "world", 0o666).unwrap();
+    assert_eq!(fs::read_to_string(&filename).unwrap(), "world");
+    // No temporary files are left behind.
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+  }
+
+  #[test]
+  fn test_file_lock() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let filename = FileLock::filename(temp_dir.path());
+    assert_eq!(filename, temp_dir.path().join(".lock"));
+    assert!(FileLock::shared(&filename).unwrap().is_none());
+
+    let lock = FileLock::exclusive(&filename).unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let filename_ = filename.clone();
+    let handle = std::thread::spawn(move || {
+      let _lock = FileLock::shared(&filename_).unwrap().unwrap();
+      tx.send(()).unwrap();
+    });
+    // The shared lock waits for the exclusive lock to be released.
+    let timeout = std::time::Duration::from_millis(100);
+    assert!(rx.recv_timeout(timeout).is_err());
+    drop(lock);
+    rx.recv().unwrap();
+    handle.join().unwrap();
+  }
+
   // TODO: Get a good expected value here for Windows.
   #[cfg(not(windows))]
   #[test]
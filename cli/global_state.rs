@@ -1,16 +1,25 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::colors;
 use crate::deno_dir;
+use crate::file_fetcher::SourceFile;
 use crate::file_fetcher::SourceFileFetcher;
+use crate::file_fetcher::DEFAULT_MAX_DOWNLOADS;
 use crate::flags;
 use crate::http_cache;
+use crate::http_util::HttpClientOptions;
+use crate::http_util::ProxyConfig;
+use crate::import_map::find_import_map;
+use crate::import_map::is_remote_import_map;
 use crate::import_map::ImportMap;
 use crate::lockfile::Lockfile;
+use crate::module_graph::ModuleGraph;
 use crate::module_graph::ModuleGraphFile;
 use crate::module_graph::ModuleGraphLoader;
 use crate::msg;
 use crate::msg::MediaType;
 use crate::op_error::OpError;
 use crate::permissions::Permissions;
+use crate::resolver::Resolver;
 use crate::state::exit_unstable;
 use crate::tsc::CompiledModule;
 use crate::tsc::TargetLib;
@@ -41,7 +50,13 @@ pub struct GlobalStateInner {
   pub ts_compiler: TsCompiler,
   pub lockfile: Option<Mutex<Lockfile>>,
   pub compiler_starts: AtomicUsize,
-  pub maybe_import_map: Option<ImportMap>,
+  /// Resolves imports of all modules, using the import map if one was
+  /// passed with `--importmap`.
+  pub resolver: Resolver,
+  /// Configuration of all HTTP clients: the one of the file fetcher, the
+  /// ones of `fetch()`, see `State::http_client`, and the clients created
+  /// with `Deno.createHttpClient()`.
+  pub http_client_options: HttpClientOptions,
   compile_lock: AsyncMutex<()>,
 }
 
@@ -53,11 +68,20 @@ impl Deref for GlobalState {
 }
 
 impl GlobalState {
-  pub fn new(flags: flags::Flags) -> Result<Self, ErrBox> {
+  /// Remote import maps are fetched on the runtime of the caller.
+  pub async fn new(flags: flags::Flags) -> Result<Self, ErrBox> {
     let custom_root = env::var("DENO_DIR").map(String::into).ok();
     let dir = deno_dir::DenoDir::new(custom_root)?;
     let deps_cache_location = dir.root.join("deps");
     let http_cache = http_cache::HttpCache::new(&deps_cache_location);
+    let http_client_options = HttpClientOptions {
+      ca_file: flags.ca_file.clone(),
+      proxies: ProxyConfig::from_flags(&flags)?,
+      unsafely_ignore_certificate_errors: flags
+        .unsafely_ignore_certificate_errors
+        .clone(),
+      ..Default::default()
+    };
 
     let file_fetcher = SourceFileFetcher::new(
       http_cache,
@@ -65,9 +89,20 @@ impl GlobalState {
       flags.cache_blocklist.clone(),
       flags.no_remote,
       flags.cached_only,
-      flags.ca_file.clone(),
+      http_client_options.clone(),
+      flags.max_downloads.unwrap_or(DEFAULT_MAX_DOWNLOADS),
     )?;
 
+    if !flags.unsafely_ignore_certificate_errors.is_empty() {
+      info!(
+        "{}",
+        colors::yellow(&format!(
+          "DANGER: TLS certificate validation is disabled for: {}",
+          flags.unsafely_ignore_certificate_errors.join(", ")
+        ))
+      );
+    }
+
     let ts_compiler = TsCompiler::new(
       file_fetcher.clone(),
       flags.clone(),
@@ -81,16 +116,34 @@ impl GlobalState {
       None
     };
 
-    let maybe_import_map: Option<ImportMap> =
-      match flags.import_map_path.as_ref() {
-        None => None,
-        Some(file_path) => {
-          if !flags.unstable {
-            exit_unstable("--importmap")
-          }
-          Some(ImportMap::load(file_path)?)
+    let import_map_path = match flags.import_map_path.clone() {
+      Some(import_map_path) => {
+        if !flags.unstable {
+          exit_unstable("--importmap")
         }
-      };
+        Some(import_map_path)
+      }
+      // Without `--importmap` the import map may be named by the project
+      // configuration file.
+      None if flags.unstable && loads_modules(&flags.subcommand) => {
+        match env::current_dir() {
+          Ok(cwd) => find_import_map(&cwd).unwrap_or_else(|err| {
+            eprintln!("{} {}", colors::yellow("Warning"), err);
+            None
+          }),
+          Err(_) => None,
+        }
+      }
+      None => None,
+    };
+    let maybe_import_map: Option<ImportMap> = match import_map_path {
+      None => None,
+      Some(file_path) if is_remote_import_map(&file_path) => Some(
+        fetch_import_map(&file_fetcher, &lockfile, &flags, &file_path).await?,
+      ),
+      Some(file_path) => Some(ImportMap::load(&file_path)?),
+    };
+    let resolver = Resolver::new(maybe_import_map);
 
     let inner = GlobalStateInner {
       dir,
@@ -99,7 +152,8 @@ impl GlobalState {
       file_fetcher,
       ts_compiler,
       lockfile,
-      maybe_import_map,
+      resolver,
+      http_client_options,
       compiler_starts: AtomicUsize::new(0),
       compile_lock: AsyncMutex::new(()),
     };
@@ -117,7 +171,6 @@ impl GlobalState {
     target_lib: TargetLib,
     permissions: Permissions,
     is_dyn_import: bool,
-    maybe_import_map: Option<ImportMap>,
   ) -> Result<(), ErrBox> {
     let module_specifier = module_specifier.clone();
 
@@ -127,7 +180,7 @@ impl GlobalState {
 
     let mut module_graph_loader = ModuleGraphLoader::new(
       self.file_fetcher.clone(),
-      maybe_import_map,
+      self.resolver.clone(),
       permissions.clone(),
       is_dyn_import,
       false,
@@ -136,10 +189,32 @@ impl GlobalState {
       .add_to_graph(&module_specifier, maybe_referrer)
       .await?;
     let module_graph = module_graph_loader.get_graph();
+    self
+      .compile_module_graph(
+        &module_specifier,
+        &module_graph,
+        target_lib,
+        permissions,
+      )
+      .await?;
+
+    drop(compile_lock);
 
+    Ok(())
+  }
+
+  /// Type checks and emits the modules of a graph that was already loaded
+  /// for `module_specifier`, or only strips their types with `--no-check`.
+  pub async fn compile_module_graph(
+    &self,
+    module_specifier: &ModuleSpecifier,
+    module_graph: &ModuleGraph,
+    target_lib: TargetLib,
+    permissions: Permissions,
+  ) -> Result<(), ErrBox> {
     let out = self
       .file_fetcher
-      .fetch_cached_source_file(&module_specifier, permissions.clone())
+      .fetch_cached_source_file(module_specifier, permissions.clone())
       .expect("Source file not found");
 
     // Check if we need to compile files.
@@ -152,21 +227,23 @@ impl GlobalState {
     let allow_js = should_allow_js(&module_graph_files);
 
     if should_compile {
-      self
-        .ts_compiler
-        .compile(
-          self.clone(),
-          &out,
-          target_lib,
-          permissions,
-          module_graph,
-          allow_js,
-        )
-        .await?;
+      if self.flags.no_check {
+        self.ts_compiler.transpile(module_graph)?;
+      } else {
+        self
+          .ts_compiler
+          .compile(
+            self.clone(),
+            &out,
+            target_lib,
+            permissions,
+            module_graph,
+            allow_js,
+          )
+          .await?;
+      }
     }
 
-    drop(compile_lock);
-
     Ok(())
   }
 
@@ -202,7 +279,7 @@ impl GlobalState {
       _ => false,
     };
 
-    let compiled_module = if was_compiled {
+    let mut compiled_module = if was_compiled {
       state1
         .ts_compiler
         .get_compiled_module(&out.url)
@@ -217,47 +294,114 @@ impl GlobalState {
       CompiledModule {
         code: String::from_utf8(out.source_code.clone())?,
         name: out.url.to_string(),
+        redirects: vec![],
       }
     };
+    compiled_module.redirects =
+      out.redirects.iter().map(|url| url.to_string()).collect();
 
     drop(compile_lock);
 
+    // Modules are checked against the lockfile by their final URL, no matter
+    // which redirect they were imported through.
     if let Some(ref lockfile) = state2.lockfile {
-      let mut g = lockfile.lock().unwrap();
-      if state2.flags.lock_write {
-        g.insert(&out.url, out.source_code);
-      } else {
-        let check = match g.check(&out.url, out.source_code) {
-          Err(e) => return Err(ErrBox::from(e)),
-          Ok(v) => v,
-        };
-        if !check {
-          eprintln!(
-            "Subresource integrity check failed --lock={}\n{}",
-            g.filename, compiled_module.name
-          );
-          std::process::exit(10);
-        }
+      if !check_integrity(lockfile, state2.flags.lock_write, &out)? {
+        eprintln!(
+          "Subresource integrity check failed --lock={}\n{}",
+          lockfile.lock().unwrap().filename,
+          compiled_module.name
+        );
+        std::process::exit(10);
       }
     }
     Ok(compiled_module)
   }
 
+  /// Remote import maps aren't used in tests, so `GlobalState::new` doesn't
+  /// need a runtime.
   #[cfg(test)]
   pub fn mock(
     argv: Vec<String>,
     maybe_flags: Option<flags::Flags>,
   ) -> GlobalState {
-    if let Some(in_flags) = maybe_flags {
-      GlobalState::new(flags::Flags { argv, ..in_flags }).unwrap()
-    } else {
-      GlobalState::new(flags::Flags {
+    let flags = match maybe_flags {
+      Some(in_flags) => flags::Flags { argv, ..in_flags },
+      None => flags::Flags {
         argv,
         ..flags::Flags::default()
-      })
-      .unwrap()
+      },
+    };
+    futures::executor::block_on(GlobalState::new(flags)).unwrap()
+  }
+}
+
+/// Checks `source_file` against the lockfile, or adds it to the lockfile with
+/// `--lock-write`. Returns false if the check fails.
+fn check_integrity(
+  lockfile: &Mutex<Lockfile>,
+  lock_write: bool,
+  source_file: &SourceFile,
+) -> Result<bool, ErrBox> {
+  let mut g = lockfile.lock().unwrap();
+  if lock_write {
+    g.insert(&source_file.url, source_file.source_code.clone());
+    return Ok(true);
+  }
+  g.check(&source_file.url, source_file.source_code.clone())
+    .map_err(ErrBox::from)
+}
+
+/// Returns true if `subcommand` loads modules, so that it needs the import
+/// map that is named by the project configuration file.
+fn loads_modules(subcommand: &flags::DenoSubcommand) -> bool {
+  use flags::DenoSubcommand::*;
+  match subcommand {
+    Bundle { .. }
+    | Cache { .. }
+    | Doc { .. }
+    | Eval { .. }
+    | Info { .. }
+    | Repl
+    | Run { .. }
+    | Test { .. } => true,
+    _ => false,
+  }
+}
+
+/// Fetches a remote import map through `file_fetcher`, so that it is cached
+/// in `$DENO_DIR` and checked against the lockfile like any remote module.
+/// `--reload`, `--no-remote` and `--cached-only` apply.
+///
+/// It is exempt from `--allow-net`, like the static imports of the main
+/// module: its URL is given on the command line, and it only redirects
+/// imports, which are loaded with the same permissions as without it.
+/// `deno cache` and `deno bundle` have no permission flags and rely on this.
+async fn fetch_import_map(
+  file_fetcher: &SourceFileFetcher,
+  lockfile: &Option<Mutex<Lockfile>>,
+  flags: &flags::Flags,
+  url: &str,
+) -> Result<ImportMap, ErrBox> {
+  let specifier = ModuleSpecifier::resolve_url(url)?;
+  let source_file = file_fetcher
+    .fetch_source_file(&specifier, None, Permissions::allow_all())
+    .await?;
+  if let Some(lockfile) = lockfile {
+    if !check_integrity(lockfile, flags.lock_write, &source_file)? {
+      return Err(
+        OpError::other(format!(
+          "Subresource integrity check failed --lock={}\n{}",
+          lockfile.lock().unwrap().filename,
+          source_file.url
+        ))
+        .into(),
+      );
     }
   }
+  let json_string = String::from_utf8(source_file.source_code)?;
+  // Relative values of the import map resolve against its final URL.
+  ImportMap::from_json(source_file.url.as_str(), &json_string)
+    .map_err(ErrBox::from)
 }
 
 /// Determine if TS compiler should be run with `allowJs` setting on. This
@@ -330,7 +474,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file.ts".to_string(),
       url: "file:///some/file.ts".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file.ts".to_string(),
       imports: vec![],
       version_hash: "1".to_string(),
@@ -344,7 +488,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file1.js".to_string(),
       url: "file:///some/file1.js".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file1.js".to_string(),
       version_hash: "1".to_string(),
       imports: vec![ImportDescriptor {
@@ -374,7 +518,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file.jsx".to_string(),
       url: "file:///some/file.jsx".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file.jsx".to_string(),
       imports: vec![],
       version_hash: "1".to_string(),
@@ -388,7 +532,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file.ts".to_string(),
       url: "file:///some/file.ts".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file.ts".to_string(),
       version_hash: "1".to_string(),
       imports: vec![ImportDescriptor {
@@ -418,7 +562,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file.js".to_string(),
       url: "file:///some/file.js".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file.js".to_string(),
       imports: vec![],
       referenced_files: vec![],
@@ -432,7 +576,7 @@ fn test_should_allow_js() {
     &ModuleGraphFile {
       specifier: "file:///some/file1.js".to_string(),
       url: "file:///some/file1.js".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file1.js".to_string(),
       imports: vec![ImportDescriptor {
         specifier: "./file.js".to_string(),
@@ -467,7 +611,7 @@ fn test_needs_compilation() {
     &[&ModuleGraphFile {
       specifier: "some/file.js".to_string(),
       url: "file:///some/file.js".to_string(),
-      redirect: None,
+      redirect: vec![],
       filename: "some/file.js".to_string(),
       imports: vec![],
       referenced_files: vec![],
@@ -492,7 +636,7 @@ fn test_needs_compilation() {
       &ModuleGraphFile {
         specifier: "file:///some/file.ts".to_string(),
         url: "file:///some/file.ts".to_string(),
-        redirect: None,
+        redirect: vec![],
         filename: "some/file.ts".to_string(),
         imports: vec![],
         referenced_files: vec![],
@@ -506,7 +650,7 @@ fn test_needs_compilation() {
       &ModuleGraphFile {
         specifier: "file:///some/file1.js".to_string(),
         url: "file:///some/file1.js".to_string(),
-        redirect: None,
+        redirect: vec![],
         filename: "some/file1.js".to_string(),
         imports: vec![],
         referenced_files: vec![],
@@ -8,6 +8,7 @@ use std::error::Error;
 use std::fmt;
 use std::fs;
 use std::io;
+use std::path::Path;
 use url::Url;
 
 #[derive(Debug)]
@@ -468,6 +469,57 @@ impl ImportMap {
   }
 }
 
+/// Name of the project configuration file that may name an import map, so
+/// that it doesn't have to be passed with `--importmap` to every command.
+pub const PROJECT_CONFIG_FILENAME: &str = "deno.json";
+
+/// Returns true if the import map should be fetched rather than read from
+/// disk.
+pub fn is_remote_import_map(path: &str) -> bool {
+  match Url::parse(path) {
+    Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+    Err(_) => false,
+  }
+}
+
+/// Looks for a project configuration file in `dir` and its ancestors and
+/// returns the import map named by its `"importMap"` field. Relative paths
+/// are resolved against the directory of the configuration file. The
+/// nearest configuration file wins, even if it doesn't name an import map.
+pub fn find_import_map(dir: &Path) -> Result<Option<String>, ErrBox> {
+  for dir in dir.ancestors() {
+    let config_path = dir.join(PROJECT_CONFIG_FILENAME);
+    if !config_path.is_file() {
+      continue;
+    }
+    let config = fs::read_to_string(&config_path)?;
+    let config: Value = serde_json::from_str(&config).map_err(|err| {
+      ImportMapError::new(&format!(
+        "Unable to parse {}: {}",
+        config_path.display(),
+        err
+      ))
+    })?;
+    return match config.get("importMap") {
+      None => Ok(None),
+      Some(Value::String(path)) if is_remote_import_map(path) => {
+        Ok(Some(path.to_string()))
+      }
+      Some(Value::String(path)) => {
+        Ok(Some(dir.join(path).to_string_lossy().to_string()))
+      }
+      Some(_) => Err(
+        ImportMapError::new(&format!(
+          "\"importMap\" in {} must be a string",
+          config_path.display()
+        ))
+        .into(),
+      ),
+    };
+  }
+  Ok(None)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -478,6 +530,55 @@ mod tests {
     assert!(ImportMap::load(file_path).is_err());
   }
 
+  #[test]
+  fn find_import_map_in_ancestors() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    let nested = project.join("src").join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    assert_eq!(find_import_map(&nested).unwrap(), None);
+
+    fs::write(
+      project.join(PROJECT_CONFIG_FILENAME),
+      r#"{ "importMap": "./import_map.json" }"#,
+    )
+    .unwrap();
+    let path = find_import_map(&nested).unwrap().unwrap();
+    assert_eq!(Path::new(&path), project.join("import_map.json"));
+
+    // The nearest configuration file wins.
+    fs::write(nested.join(PROJECT_CONFIG_FILENAME), "{}").unwrap();
+    assert_eq!(find_import_map(&nested).unwrap(), None);
+
+    fs::write(
+      nested.join(PROJECT_CONFIG_FILENAME),
+      r#"{ "importMap": "https://example.com/import_map.json" }"#,
+    )
+    .unwrap();
+    assert_eq!(
+      find_import_map(&nested).unwrap(),
+      Some("https://example.com/import_map.json".to_string())
+    );
+
+    fs::write(
+      nested.join(PROJECT_CONFIG_FILENAME),
+      r#"{ "importMap": 1 }"#,
+    )
+    .unwrap();
+    assert!(find_import_map(&nested).is_err());
+  }
+
+  #[test]
+  fn remote_import_map() {
+    assert!(is_remote_import_map("https://example.com/import_map.json"));
+    assert!(is_remote_import_map(
+      "http://localhost:4545/import_map.json"
+    ));
+    assert!(!is_remote_import_map("./import_map.json"));
+    assert!(!is_remote_import_map("/project/import_map.json"));
+    assert!(!is_remote_import_map("file:///project/import_map.json"));
+  }
+
   #[test]
   fn from_json_1() {
     let base_url = "https://deno.land";
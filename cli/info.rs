@@ -0,0 +1,438 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Machine readable output formats of `deno info` for the dependency graph
+//! of a module: JSON (`--json`), Graphviz DOT (`--dot`) and the import paths
+//! that reach a module (`--why`).
+use crate::global_state::GlobalState;
+use crate::module_graph::ImportDescriptor;
+use crate::module_graph::ModuleGraph;
+use crate::module_graph::ModuleGraphFile;
+use crate::module_graph::ModuleGraphLoader;
+use crate::module_graph::ReferenceDescriptor;
+use crate::msg;
+use crate::permissions::Permissions;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// Fetches `root` and all of its dependencies into a `ModuleGraph`.
+pub async fn build_module_graph(
+  global_state: &GlobalState,
+  root: &ModuleSpecifier,
+) -> Result<ModuleGraph, ErrBox> {
+  let mut loader = ModuleGraphLoader::new(
+    global_state.file_fetcher.clone(),
+    global_state.resolver.clone(),
+    Permissions::allow_all(),
+    false,
+    false,
+  );
+  loader.add_to_graph(root, None).await?;
+  Ok(loader.get_graph())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+  /// An `import` or `export ... from` statement.
+  Import,
+  /// A `@deno-types` directive in front of an import.
+  TypeDirective,
+  /// An `X-TypeScript-Types` header.
+  TypeHeader,
+  /// A `/// <reference path="..." />` directive.
+  Reference,
+  /// A `/// <reference types="..." />` directive.
+  Types,
+  /// A `/// <reference lib="..." />` directive.
+  Lib,
+}
+
+/// An edge of the dependency graph. Redirects are already followed, so
+/// `specifier` is the module that was actually loaded.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Dependency<'a> {
+  pub specifier: &'a str,
+  pub kind: DependencyKind,
+}
+
+/// Follows the redirects of `specifier` to the module that was loaded.
+fn resolve_redirects<'a>(
+  graph: &'a ModuleGraph,
+  specifier: &'a str,
+) -> &'a str {
+  match graph.get(specifier).and_then(|f| f.redirect.last()) {
+    Some(redirect) => redirect,
+    None => specifier,
+  }
+}
+
+/// Returns the sorted and deduplicated dependencies of `file`.
+pub fn dependencies<'a>(
+  graph: &'a ModuleGraph,
+  file: &'a ModuleGraphFile,
+) -> Vec<Dependency<'a>> {
+  let mut deps = BTreeSet::new();
+  let mut add = |specifier: &'a ModuleSpecifier, kind: DependencyKind| {
+    let specifier = specifier.as_str();
+    let specifier = graph
+      .get_key_value(specifier)
+      .map(|(key, _)| resolve_redirects(graph, key))
+      .unwrap_or(specifier);
+    deps.insert(Dependency { specifier, kind });
+  };
+  for import in &file.imports {
+    add(&import.resolved_specifier, DependencyKind::Import);
+    if let Some(type_directive) = &import.resolved_type_directive {
+      add(type_directive, DependencyKind::TypeDirective);
+    }
+  }
+  let references = [
+    (&file.type_headers, DependencyKind::TypeHeader),
+    (&file.referenced_files, DependencyKind::Reference),
+    (&file.types_directives, DependencyKind::Types),
+    (&file.lib_directives, DependencyKind::Lib),
+  ];
+  for &(descriptors, kind) in references.iter() {
+    for descriptor in descriptors {
+      add(&descriptor.resolved_specifier, kind);
+    }
+  }
+  deps.into_iter().collect()
+}
+
+/// Modules of `graph` that were loaded, i.e. that aren't redirects, sorted
+/// by specifier.
+fn loaded_modules(graph: &ModuleGraph) -> Vec<&ModuleGraphFile> {
+  let mut files: Vec<&ModuleGraphFile> =
+    graph.values().filter(|f| f.redirect.is_empty()).collect();
+  files.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+  files
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModuleInfo<'a> {
+  specifier: &'a str,
+  redirect: &'a [String],
+  local: &'a str,
+  media_type: &'static str,
+  size: usize,
+  compiled: Option<String>,
+  map: Option<String>,
+  imports: &'a [ImportDescriptor],
+  referenced_files: &'a [ReferenceDescriptor],
+  lib_directives: &'a [ReferenceDescriptor],
+  types_directives: &'a [ReferenceDescriptor],
+  type_headers: &'a [ReferenceDescriptor],
+  dependencies: Vec<Dependency<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphInfo<'a> {
+  root: &'a str,
+  total_size: usize,
+  modules: Vec<ModuleInfo<'a>>,
+}
+
+/// Returns the output of `deno info --json`. Modules are sorted by
+/// specifier; `compiled` and `map` are only set for modules that have
+/// already been compiled to `$DENO_DIR/gen`, nothing is compiled here.
+pub fn graph_to_json(
+  global_state: &GlobalState,
+  root: &ModuleSpecifier,
+  graph: &ModuleGraph,
+) -> serde_json::Value {
+  let mut files: Vec<&ModuleGraphFile> = graph.values().collect();
+  files.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+
+  let modules: Vec<ModuleInfo> = files
+    .into_iter()
+    .map(|file| {
+      let (compiled, map) = match ModuleSpecifier::resolve_url(&file.url) {
+        Ok(specifier) if file.redirect.is_empty() => {
+          let ts_compiler = &global_state.ts_compiler;
+          let compiled = ts_compiler
+            .get_compiled_source_file(specifier.as_url())
+            .ok()
+            .map(|f| f.filename.to_string_lossy().to_string());
+          let map = ts_compiler
+            .get_source_map_file(&specifier)
+            .ok()
+            .map(|f| f.filename.to_string_lossy().to_string());
+          (compiled, map)
+        }
+        _ => (None, None),
+      };
+      ModuleInfo {
+        specifier: &file.specifier,
+        redirect: &file.redirect,
+        local: &file.filename,
+        media_type: msg::enum_name_media_type(file.media_type),
+        size: file.source_code.len(),
+        compiled,
+        map,
+        imports: &file.imports,
+        referenced_files: &file.referenced_files,
+        lib_directives: &file.lib_directives,
+        types_directives: &file.types_directives,
+        type_headers: &file.type_headers,
+        dependencies: dependencies(graph, file),
+      }
+    })
+    .collect();
+
+  let info = GraphInfo {
+    root: resolve_redirects(graph, root.as_str()),
+    total_size: modules.iter().map(|m| m.size).sum(),
+    modules,
+  };
+  serde_json::to_value(info).unwrap()
+}
+
+fn dot_quote(s: &str) -> String {
+  format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns the output of `deno info --dot`, a directed graph in the DOT
+/// language of Graphviz. Edges that only carry types are dashed.
+pub fn graph_to_dot(root: &ModuleSpecifier, graph: &ModuleGraph) -> String {
+  let root = resolve_redirects(graph, root.as_str());
+  let mut out = format!("digraph {} {{\n", dot_quote(root));
+  out.push_str(&format!("  {} [shape=box];\n", dot_quote(root)));
+  for file in loaded_modules(graph) {
+    let mut previous = None;
+    for dep in dependencies(graph, file) {
+      // A module can be a dependency of several kinds, but gets one edge.
+      if previous.replace(dep.specifier) == Some(dep.specifier) {
+        continue;
+      }
+      let style = match dep.kind {
+        DependencyKind::Import | DependencyKind::Reference => "",
+        _ => " [style=dashed]",
+      };
+      out.push_str(&format!(
+        "  {} -> {}{};\n",
+        dot_quote(&file.specifier),
+        dot_quote(dep.specifier),
+        style
+      ));
+    }
+  }
+  out.push_str("}\n");
+  out
+}
+
+/// Returns, for every module that imports `target`, the shortest path of
+/// imports from `root` to `target` through that module. Each path starts
+/// with `root` and ends with `target`.
+pub fn import_paths(
+  graph: &ModuleGraph,
+  root: &ModuleSpecifier,
+  target: &ModuleSpecifier,
+) -> Vec<Vec<String>> {
+  let root = resolve_redirects(graph, root.as_str());
+  let target = resolve_redirects(graph, target.as_str());
+  if root == target {
+    return vec![vec![root.to_string()]];
+  }
+
+  // Breadth first search that doesn't continue past `target`, so the parent
+  // links form shortest paths that contain `target` only at the end.
+  let mut parents: HashMap<&str, &str> = HashMap::new();
+  let mut importers = vec![];
+  let mut queue = VecDeque::new();
+  queue.push_back(root);
+  while let Some(current) = queue.pop_front() {
+    let file = match graph.get(current) {
+      Some(file) => file,
+      None => continue,
+    };
+    let mut deps: Vec<&str> = dependencies(graph, file)
+      .iter()
+      .map(|d| d.specifier)
+      .collect();
+    deps.dedup();
+    for dep in deps {
+      if dep == target {
+        importers.push(current);
+      } else if dep != root && !parents.contains_key(dep) {
+        parents.insert(dep, current);
+        queue.push_back(dep);
+      }
+    }
+  }
+
+  importers
+    .into_iter()
+    .map(|importer| {
+      let mut path = vec![target.to_string()];
+      let mut current = importer;
+      loop {
+        path.push(current.to_string());
+        match parents.get(current) {
+          Some(parent) => current = *parent,
+          None => break,
+        }
+      }
+      path.reverse();
+      path
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::doc::Location;
+  use crate::msg::MediaType;
+
+  fn file(
+    specifier: &str,
+    redirect: &[&str],
+    imports: &[&str],
+  ) -> ModuleGraphFile {
+    let imports = imports
+      .iter()
+      .map(|import| ImportDescriptor {
+        specifier: import.to_string(),
+        resolved_specifier: ModuleSpecifier::resolve_url(import).unwrap(),
+        type_directive: None,
+        resolved_type_directive: None,
+        location: Location {
+          filename: specifier.to_string(),
+          line: 1,
+          col: 0,
+        },
+      })
+      .collect();
+    ModuleGraphFile {
+      specifier: specifier.to_string(),
+      url: specifier.to_string(),
+      redirect: redirect.iter().map(|r| r.to_string()).collect(),
+      filename: "".to_string(),
+      version_hash: "".to_string(),
+      imports,
+      referenced_files: vec![],
+      lib_directives: vec![],
+      types_directives: vec![],
+      type_headers: vec![],
+      media_type: MediaType::TypeScript,
+      source_code: "".to_string(),
+    }
+  }
+
+  /// a.ts imports b.ts and c.ts, which both import d.ts. b.ts is imported
+  /// through a redirect and c.ts imports a.ts back.
+  fn graph() -> ModuleGraph {
+    let mut graph = ModuleGraph::new();
+    for file in vec![
+      file(
+        "file:///a.ts",
+        &[],
+        &["https://example.com/b", "file:///c.ts"],
+      ),
+      file("https://example.com/b", &["https://example.com/b.ts"], &[]),
+      file("https://example.com/b.ts", &[], &["file:///d.ts"]),
+      file("file:///c.ts", &[], &["file:///a.ts", "file:///d.ts"]),
+      file("file:///d.ts", &[], &[]),
+    ] {
+      graph.insert(file.specifier.clone(), file);
+    }
+    graph
+  }
+
+  fn specifier(s: &str) -> ModuleSpecifier {
+    ModuleSpecifier::resolve_url(s).unwrap()
+  }
+
+  #[test]
+  fn test_dependencies_follow_redirects() {
+    let graph = graph();
+    let deps = dependencies(&graph, &graph["file:///a.ts"]);
+    let deps: Vec<&str> = deps.iter().map(|d| d.specifier).collect();
+    assert_eq!(deps, vec!["file:///c.ts", "https://example.com/b.ts"]);
+  }
+
+  #[test]
+  fn test_graph_to_dot() {
+    let dot = graph_to_dot(&specifier("file:///a.ts"), &graph());
+    assert_eq!(
+      dot,
+      r#"digraph "file:///a.ts" {
+  "file:///a.ts" [shape=box];
+  "file:///a.ts" -> "file:///c.ts";
+  "file:///a.ts" -> "https://example.com/b.ts";
+  "file:///c.ts" -> "file:///a.ts";
+  "file:///c.ts" -> "file:///d.ts";
+  "https://example.com/b.ts" -> "file:///d.ts";
+}
+"#
+    );
+  }
+
+  #[test]
+  fn test_import_paths() {
+    let graph = graph();
+    let paths = import_paths(
+      &graph,
+      &specifier("file:///a.ts"),
+      &specifier("file:///d.ts"),
+    );
+    assert_eq!(
+      paths,
+      vec![
+        vec!["file:///a.ts", "file:///c.ts", "file:///d.ts"],
+        vec!["file:///a.ts", "https://example.com/b.ts", "file:///d.ts"],
+      ]
+    );
+    let paths = import_paths(
+      &graph,
+      &specifier("file:///c.ts"),
+      &specifier("https://example.com/b"),
+    );
+    assert_eq!(
+      paths,
+      vec![vec![
+        "file:///c.ts",
+        "file:///a.ts",
+        "https://example.com/b.ts"
+      ]]
+    );
+    let paths = import_paths(
+      &graph,
+      &specifier("file:///d.ts"),
+      &specifier("file:///a.ts"),
+    );
+    assert!(paths.is_empty());
+  }
+
+  #[test]
+  fn test_import_paths_diamonds() {
+    // A chain of 32 diamonds has 2^32 simple paths to its end.
+    let mut graph = ModuleGraph::new();
+    for i in 0..32 {
+      let top = format!("file:///{}.ts", i);
+      let left = format!("file:///{}l.ts", i);
+      let right = format!("file:///{}r.ts", i);
+      let bottom = format!("file:///{}.ts", i + 1);
+      graph.insert(top.clone(), file(&top, &[], &[&left, &right]));
+      graph.insert(left.clone(), file(&left, &[], &[&bottom]));
+      graph.insert(right.clone(), file(&right, &[], &[&bottom]));
+    }
+    graph.insert("file:///32.ts".to_string(), file("file:///32.ts", &[], &[]));
+    let paths = import_paths(
+      &graph,
+      &specifier("file:///0.ts"),
+      &specifier("file:///32.ts"),
+    );
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0].len(), 65);
+    assert_eq!(paths[0][63], "file:///31l.ts");
+    assert_eq!(paths[1][63], "file:///31r.ts");
+  }
+}
@@ -4,8 +4,10 @@
 //! https://chromedevtools.github.io/devtools-protocol/
 //! https://hyperandroid.com/2020/02/12/v8-inspector-from-an-embedder-standpoint/
 
+use crate::op_error::OpError;
 use core::convert::Infallible as Never; // Alias for the future `!` type.
 use deno_core::v8;
+use deno_core::ErrBox;
 use futures::channel::mpsc;
 use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
@@ -89,7 +91,8 @@ impl InspectorServer {
 struct InspectorInfo {
   host: SocketAddr,
   uuid: Uuid,
-  thread_name: Option<String>,
+  name: Option<String>,
+  url: String,
   new_websocket_tx: UnboundedSender<WebSocketProxy>,
   canary_rx: oneshot::Receiver<Never>,
 }
@@ -103,7 +106,7 @@ impl InspectorInfo {
       "id": self.uuid.to_string(),
       "title": self.get_title(),
       "type": "deno",
-      // TODO(ry): "url": "file://",
+      "url": self.url,
       "webSocketDebuggerUrl": self.get_websocket_debugger_url(),
     })
   }
@@ -124,7 +127,7 @@ impl InspectorInfo {
       "[{}] deno{}",
       process::id(),
       self
-        .thread_name
+        .name
         .as_ref()
         .map(|n| format!(" - {}", n))
         .unwrap_or_default()
@@ -303,7 +306,7 @@ pub struct DenoInspector {
   flags: RefCell<InspectorFlags>,
   waker: Arc<InspectorWaker>,
   _canary_tx: oneshot::Sender<Never>,
-  pub debugger_url: String,
+  pub debugger_url: Option<String>,
 }
 
 impl Deref for DenoInspector {
@@ -371,9 +374,18 @@ impl Future for DenoInspector {
 impl DenoInspector {
   const CONTEXT_GROUP_ID: i32 = 1;
 
+  /// Creates a new inspector for the isolate. When `host` is `None` the
+  /// inspector is not registered with the websocket server, and can only be
+  /// driven through an `InProcessInspectorSession`.
+  ///
+  /// `name` is used as the title of the target listed by the server; when it
+  /// is empty the name of the current thread is used instead. `url` is the
+  /// module that the isolate runs.
   pub fn new(
     isolate: &mut deno_core::CoreIsolate,
-    host: SocketAddr,
+    host: Option<SocketAddr>,
+    name: &str,
+    url: &str,
   ) -> Box<Self> {
     let core_state_rc = deno_core::CoreIsolate::state(isolate);
     let core_state = core_state_rc.borrow();
@@ -384,13 +396,21 @@ impl DenoInspector {
       mpsc::unbounded::<WebSocketProxy>();
     let (canary_tx, canary_rx) = oneshot::channel::<Never>();
 
-    let info = InspectorInfo {
+    let current_thread = thread::current();
+    let name = if name.is_empty() {
+      current_thread.name()
+    } else {
+      Some(name)
+    };
+
+    let info = host.map(|host| InspectorInfo {
       host,
       uuid: Uuid::new_v4(),
-      thread_name: thread::current().name().map(|n| n.to_owned()),
+      name: name.map(|n| n.to_owned()),
+      url: url.to_owned(),
       new_websocket_tx,
       canary_rx,
-    };
+    });
 
     // Create DenoInspector instance.
     let mut self_ = new_box_with(|self_ptr| {
@@ -410,7 +430,9 @@ impl DenoInspector {
         flags,
         waker,
         _canary_tx: canary_tx,
-        debugger_url: info.get_websocket_debugger_url(),
+        debugger_url: info
+          .as_ref()
+          .map(|info| info.get_websocket_debugger_url()),
       }
     });
 
@@ -420,7 +442,9 @@ impl DenoInspector {
     self_.context_created(context, Self::CONTEXT_GROUP_ID, context_name);
 
     // Register this inspector with the server thread.
-    InspectorServer::register_inspector(info);
+    if let Some(info) = info {
+      InspectorServer::register_inspector(info);
+    }
 
     // Poll the session handler so we will get notified whenever there is
     // new_incoming debugger activity.
@@ -534,6 +558,15 @@ impl DenoInspector {
     }
   }
 
+  /// Connects a new session that is driven from the isolate thread itself
+  /// instead of by a remote debugger. The session must be dropped before the
+  /// inspector is.
+  pub fn create_in_process_session(
+    &mut self,
+  ) -> Box<InProcessInspectorSession> {
+    InProcessInspectorSession::new(self)
+  }
+
   /// This function blocks the thread until at least one inspector client has
   /// established a websocket connection and successfully completed the
   /// handshake. After that, it instructs V8 to pause at the next statement.
@@ -792,6 +825,124 @@ impl Future for DenoInspectorSession {
   }
 }
 
+/// An inspector session that doesn't have a websocket attached to it. Instead,
+/// protocol messages are dispatched by calling `post_message()`. The inspector
+/// answers the methods we use (e.g. `Profiler.stop`,
+/// `HeapProfiler.takeHeapSnapshot`) synchronously, so the response is
+/// available as soon as the dispatch returns.
+pub struct InProcessInspectorSession {
+  v8_channel: v8::inspector::ChannelBase,
+  v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
+  next_message_id: i32,
+  responses: HashMap<i32, serde_json::Value>,
+  notifications: Vec<serde_json::Value>,
+}
+
+impl InProcessInspectorSession {
+  const CONTEXT_GROUP_ID: i32 = 1;
+
+  fn new(inspector: &mut DenoInspector) -> Box<Self> {
+    new_box_with(move |self_ptr| {
+      let v8_channel = v8::inspector::ChannelBase::new::<Self>();
+      let v8_session = inspector.connect(
+        Self::CONTEXT_GROUP_ID,
+        unsafe { &mut *self_ptr },
+        v8::inspector::StringView::empty(),
+      );
+
+      Self {
+        v8_channel,
+        v8_session,
+        next_message_id: 1,
+        responses: HashMap::new(),
+        notifications: vec![],
+      }
+    })
+  }
+
+  /// Dispatches a protocol method to the inspector and returns the `result`
+  /// field of its response.
+  pub fn post_message(
+    &mut self,
+    method: &str,
+    params: Option<serde_json::Value>,
+  ) -> Result<serde_json::Value, ErrBox> {
+    let id = self.next_message_id;
+    self.next_message_id += 1;
+
+    let message = json!({
+      "id": id,
+      "method": method,
+      "params": params.unwrap_or_else(|| json!({})),
+    });
+    let message = serde_json::to_string(&message).unwrap();
+    let message = v8::inspector::StringView::from(message.as_bytes());
+    self.v8_session.dispatch_protocol_message(message);
+
+    let mut response = match self.responses.remove(&id) {
+      Some(response) => response,
+      None => {
+        return Err(
+          OpError::other(format!("Inspector did not respond to {}", method))
+            .into(),
+        )
+      }
+    };
+    if let Some(error) = response.get("error") {
+      let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown error");
+      return Err(
+        OpError::other(format!("Inspector error in {}: {}", method, message))
+          .into(),
+      );
+    }
+    Ok(response["result"].take())
+  }
+
+  /// Returns all notifications received since the last call, in order.
+  pub fn take_notifications(&mut self) -> Vec<serde_json::Value> {
+    take(&mut self.notifications)
+  }
+
+  fn parse_message(
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) -> serde_json::Value {
+    let message = message.unwrap().string().to_string();
+    serde_json::from_str(&message).unwrap()
+  }
+}
+
+impl v8::inspector::ChannelImpl for InProcessInspectorSession {
+  fn base(&self) -> &v8::inspector::ChannelBase {
+    &self.v8_channel
+  }
+
+  fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+    &mut self.v8_channel
+  }
+
+  fn send_response(
+    &mut self,
+    call_id: i32,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let response = Self::parse_message(message);
+    self.responses.insert(call_id, response);
+  }
+
+  fn send_notification(
+    &mut self,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
+  ) {
+    let notification = Self::parse_message(message);
+    self.notifications.push(notification);
+  }
+
+  fn flush_protocol_notifications(&mut self) {}
+}
+
 fn new_box_with<T>(new_fn: impl FnOnce(*mut T) -> T) -> Box<T> {
   let b = Box::new(MaybeUninit::<T>::uninit());
   let p = Box::into_raw(b) as *mut T;
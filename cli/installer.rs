@@ -207,7 +207,24 @@ pub fn install(
     }
   }
 
-  if flags.unstable {
+  // Import maps are unstable, so the script can't be run with one without
+  // `--unstable`.
+  let unstable = flags.unstable || flags.import_map_path.is_some();
+
+  if let Some(import_map_path) = flags.import_map_path {
+    // Local import maps are resolved against the current directory, as the
+    // installed script is run from wherever the user happens to be.
+    let import_map_path = if is_remote_url(&import_map_path) {
+      import_map_path
+    } else {
+      let cwd = env::current_dir().unwrap();
+      cwd.join(import_map_path).to_string_lossy().to_string()
+    };
+    executable_args.push("--importmap".to_string());
+    executable_args.push(import_map_path);
+  }
+
+  if unstable {
     executable_args.push("--unstable".to_string());
   }
 
@@ -401,6 +418,39 @@ mod tests {
     ));
   }
 
+  #[test]
+  fn install_import_map() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let bin_dir = temp_dir.path().join("bin");
+    std::fs::create_dir(&bin_dir).unwrap();
+
+    install(
+      Flags {
+        import_map_path: Some(
+          "http://localhost:4545/cli/tests/importmaps/import_map.json"
+            .to_string(),
+        ),
+        ..Flags::default()
+      },
+      "http://localhost:4545/cli/tests/echo_server.ts",
+      vec![],
+      Some("echo_test".to_string()),
+      Some(temp_dir.path().to_path_buf()),
+      false,
+    )
+    .expect("Install failed");
+
+    let mut file_path = bin_dir.join("echo_test");
+    if cfg!(windows) {
+      file_path = file_path.with_extension("cmd");
+    }
+
+    let content = fs::read_to_string(file_path).unwrap();
+    assert!(content.contains(
+      r#""run" "--importmap" "http://localhost:4545/cli/tests/importmaps/import_map.json" "--unstable""#
+    ));
+  }
+
   #[test]
   fn install_inferred_name() {
     let temp_dir = TempDir::new().expect("tempdir fail");
@@ -664,12 +664,12 @@ function buildSourceFileCache(
       const importedFile = sourceFileMap[importDesc.resolvedSpecifier];
       // IMPORTANT: due to HTTP redirects we might end up in situation
       // where URL points to a file with completely different URL.
-      // In that case we take value of `redirect` field and cache
-      // resolved specifier pointing to the value of the redirect.
+      // In that case we take the last hop of the `redirect` field and cache
+      // resolved specifier pointing to the target of the redirect.
       // It's not very elegant solution and should be rethinked.
       assert(importedFile);
-      if (importedFile.redirect) {
-        mappedUrl = importedFile.redirect;
+      if (importedFile.redirect.length > 0) {
+        mappedUrl = importedFile.redirect[importedFile.redirect.length - 1];
       }
       const isJsOrJsx =
         importedFile.mediaType === MediaType.JavaScript ||
@@ -735,7 +735,6 @@ interface BundleWriteFileState {
 // Update carefully!
 enum CompilerRequestType {
   Compile = 0,
-  Bundle = 1,
   RuntimeCompile = 2,
   RuntimeBundle = 3,
   RuntimeTranspile = 4,
@@ -1186,7 +1185,8 @@ interface SourceFileMapEntry {
   url: string;
   sourceCode: string;
   mediaType: MediaType;
-  redirect?: string;
+  // every hop of the redirect, ending with the URL of the module
+  redirect: string[];
   imports: ImportDescriptor[];
   referencedFiles: ReferenceDescriptor[];
   libDirectives: ReferenceDescriptor[];
@@ -1211,20 +1211,6 @@ interface CompileRequest {
   buildInfo?: string;
 }
 
-/** Used when "deno bundle" is invoked */
-interface BundleRequest {
-  type: CompilerRequestType.Bundle;
-  target: CompilerHostTarget;
-  rootNames: string[];
-  configPath?: string;
-  config?: string;
-  unstable: boolean;
-  performance: boolean;
-  cwd: string;
-  // key value is fully resolved URL
-  sourceFileMap: Record<string, SourceFileMapEntry>;
-}
-
 /** Used when "Deno.compile()" API is called */
 interface RuntimeCompileRequest {
   type: CompilerRequestType.RuntimeCompile;
@@ -1254,7 +1240,6 @@ interface RuntimeTranspileRequest {
 
 type CompilerRequest =
   | CompileRequest
-  | BundleRequest
   | RuntimeCompileRequest
   | RuntimeBundleRequest
   | RuntimeTranspileRequest;
@@ -1266,12 +1251,6 @@ interface CompileResponse {
   stats?: Stats;
 }
 
-interface BundleResponse {
-  bundleOutput?: string;
-  diagnostics: Diagnostic;
-  stats?: Stats;
-}
-
 interface RuntimeCompileResponse {
   emitMap: Record<string, EmittedSource>;
   diagnostics: DiagnosticItem[];
@@ -1380,102 +1359,6 @@ function compile({
   };
 }
 
-function bundle({
-  type,
-  config,
-  configPath,
-  rootNames,
-  target,
-  unstable,
-  performance,
-  cwd,
-  sourceFileMap,
-}: BundleRequest): BundleResponse {
-  if (performance) {
-    performanceStart();
-  }
-  log(">>> start start", {
-    rootNames,
-    type: CompilerRequestType[type],
-  });
-
-  // When a programme is emitted, TypeScript will call `writeFile` with
-  // each file that needs to be emitted.  The Deno compiler host delegates
-  // this, to make it easier to perform the right actions, which vary
-  // based a lot on the request.
-  const state: BundleWriteFileState = {
-    rootNames,
-    bundleOutput: undefined,
-  };
-  const host = new Host({
-    bundle: true,
-    target,
-    unstable,
-    writeFile: createBundleWriteFile(state),
-  });
-  state.host = host;
-  let diagnostics: readonly ts.Diagnostic[] = [];
-
-  // if there is a configuration supplied, we need to parse that
-  if (config && config.length && configPath) {
-    const configResult = host.configure(cwd, configPath, config);
-    diagnostics = processConfigureResponse(configResult, configPath) || [];
-  }
-
-  buildSourceFileCache(sourceFileMap);
-  // if there was a configuration and no diagnostics with it, we will continue
-  // to generate the program and possibly emit it.
-  if (diagnostics.length === 0) {
-    const options = host.getCompilationSettings();
-    const program = ts.createProgram({
-      rootNames,
-      options,
-      host,
-    });
-
-    diagnostics = ts
-      .getPreEmitDiagnostics(program)
-      .filter(({ code }) => !ignoredDiagnostics.includes(code));
-
-    // We will only proceed with the emit if there are no diagnostics.
-    if (diagnostics.length === 0) {
-      // we only support a single root module when bundling
-      assert(rootNames.length === 1);
-      setRootExports(program, rootNames[0]);
-      const emitResult = program.emit();
-      assert(emitResult.emitSkipped === false, "Unexpected skip of the emit.");
-      // emitResult.diagnostics is `readonly` in TS3.5+ and can't be assigned
-      // without casting.
-      diagnostics = emitResult.diagnostics;
-    }
-    if (performance) {
-      performanceProgram(program);
-    }
-  }
-
-  let bundleOutput;
-
-  if (diagnostics.length === 0) {
-    assert(state.bundleOutput);
-    bundleOutput = state.bundleOutput;
-  }
-
-  const stats = performance ? performanceEnd() : undefined;
-
-  const result: BundleResponse = {
-    bundleOutput,
-    diagnostics: fromTypeScriptDiagnostic(diagnostics),
-    stats,
-  };
-
-  log("<<< bundle end", {
-    rootNames,
-    type: CompilerRequestType[type],
-  });
-
-  return result;
-}
-
 function runtimeCompile(
   request: RuntimeCompileRequest
 ): RuntimeCompileResponse {
@@ -1657,11 +1540,6 @@ async function tsCompilerOnMessage({
       globalThis.postMessage(result);
       break;
     }
-    case CompilerRequestType.Bundle: {
-      const result = bundle(request);
-      globalThis.postMessage(result);
-      break;
-    }
     case CompilerRequestType.RuntimeCompile: {
       const result = runtimeCompile(request);
       globalThis.postMessage(result);
@@ -1689,7 +1567,7 @@ async function tsCompilerOnMessage({
 }
 
 function bootstrapTsCompilerRuntime(): void {
-  bootstrapWorkerRuntime("TS", false);
+  bootstrapWorkerRuntime("TS", false, undefined, true);
   globalThis.onmessage = tsCompilerOnMessage;
 }
 
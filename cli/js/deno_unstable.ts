@@ -18,6 +18,10 @@ export { ftruncateSync, ftruncate } from "./ops/fs/truncate.ts";
 export { ShutdownMode, shutdown } from "./net.ts";
 export { listen, listenDatagram, connect } from "./net_unstable.ts";
 export { startTls } from "./tls.ts";
+export { resolveDns } from "./ops/dns.ts";
+export { serveHttp, HttpConn, RequestEvent } from "./http.ts";
+export { createHttpClient, HttpClient } from "./web/fetch.ts";
+export { upgradeWebSocket } from "./web/websocket.ts";
 export { kill } from "./ops/process.ts";
 export {
   permissions,
@@ -17,11 +17,13 @@ import * as eventTarget from "./web/event_target.ts";
 import * as formData from "./web/form_data.ts";
 import * as fetchTypes from "./web/fetch.ts";
 import * as headers from "./web/headers.ts";
+import * as messagePort from "./web/message_port.ts";
 import * as textEncoding from "./web/text_encoding.ts";
 import * as timers from "./web/timers.ts";
 import * as url from "./web/url.ts";
 import * as urlSearchParams from "./web/url_search_params.ts";
 import * as workers from "./web/workers.ts";
+import * as webSocket from "./web/websocket.ts";
 import * as performanceUtil from "./web/performance.ts";
 import * as request from "./web/request.ts";
 import * as readableStream from "./web/streams/readable_stream.ts";
@@ -132,6 +134,9 @@ declare global {
 
     decode(bytes: Uint8Array): string;
     encode(text: string): Uint8Array;
+
+    serialize(value: unknown, transfer?: ArrayBuffer[]): number;
+    deserialize(rid: number): unknown;
   }
 
   // Only `var` variables show up in the `globalThis` type when doing a global
@@ -226,6 +231,7 @@ export const windowOrWorkerGlobalScopeProperties = {
   AbortController: nonEnumerable(abortController.AbortControllerImpl),
   AbortSignal: nonEnumerable(abortSignal.AbortSignalImpl),
   Blob: nonEnumerable(blob.DenoBlob),
+  CloseEvent: nonEnumerable(webSocket.CloseEventImpl),
   ByteLengthQueuingStrategy: nonEnumerable(
     queuingStrategy.ByteLengthQueuingStrategyImpl
   ),
@@ -240,6 +246,9 @@ export const windowOrWorkerGlobalScopeProperties = {
   URL: nonEnumerable(url.URLImpl),
   URLSearchParams: nonEnumerable(urlSearchParams.URLSearchParamsImpl),
   Headers: nonEnumerable(headers.HeadersImpl),
+  MessageChannel: nonEnumerable(messagePort.MessageChannelImpl),
+  MessageEvent: nonEnumerable(messagePort.MessageEvent),
+  MessagePort: nonEnumerable(messagePort.MessagePortImpl),
   FormData: nonEnumerable(formData.FormDataImpl),
   TextEncoder: nonEnumerable(textEncoding.TextEncoder),
   TextDecoder: nonEnumerable(textEncoding.TextDecoder),
@@ -248,6 +257,7 @@ export const windowOrWorkerGlobalScopeProperties = {
   Request: nonEnumerable(request.Request),
   Response: nonEnumerable(fetchTypes.Response),
   performance: writable(new performanceUtil.Performance()),
+  WebSocket: nonEnumerable(webSocket.WebSocketImpl),
   Worker: nonEnumerable(workers.WorkerImpl),
   WritableStream: nonEnumerable(writableStream.WritableStreamImpl),
 };
@@ -0,0 +1,171 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { Conn } from "./net.ts";
+import * as httpOps from "./ops/http.ts";
+import { read, write } from "./ops/io.ts";
+import { close } from "./ops/resources.ts";
+import { Response } from "./web/fetch.ts";
+import { Request } from "./web/request.ts";
+import { ReadableStreamImpl } from "./web/streams/readable_stream.ts";
+import { TextEncoder } from "./web/text_encoding.ts";
+
+export interface RequestEvent {
+  readonly request: Request;
+  respondWith(r: Response | Promise<Response>): Promise<void>;
+}
+
+/** Closes a resource that may already have been closed. */
+function tryClose(rid: number): void {
+  try {
+    close(rid);
+  } catch {
+    // Already closed.
+  }
+}
+
+function createRequestBody(rid: number): ReadableStreamImpl<Uint8Array> {
+  return new ReadableStreamImpl<Uint8Array>({
+    async pull(controller): Promise<void> {
+      try {
+        const buf = new Uint8Array(32 * 1024);
+        const nread = await read(rid, buf);
+        if (nread === null) {
+          controller.close();
+          tryClose(rid);
+          return;
+        }
+        controller.enqueue(buf.subarray(0, nread));
+      } catch (e) {
+        controller.error(e);
+        tryClose(rid);
+      }
+    },
+    cancel(): void {
+      tryClose(rid);
+    },
+  });
+}
+
+async function writeResponseBody(
+  rid: number,
+  body: ReadableStream<Uint8Array | string>
+): Promise<void> {
+  const encoder = new TextEncoder();
+  const reader = body.getReader();
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) {
+        break;
+      }
+      let chunk = typeof value === "string" ? encoder.encode(value) : value;
+      while (chunk.byteLength > 0) {
+        const nwritten = await write(rid, chunk);
+        chunk = chunk.subarray(nwritten);
+      }
+    }
+  } catch (e) {
+    reader.cancel(e).catch(() => {});
+    throw e;
+  } finally {
+    tryClose(rid);
+  }
+}
+
+async function respondWith(
+  request: httpOps.HttpRequest,
+  r: Response | Promise<Response>
+): Promise<void> {
+  try {
+    const response = await r;
+    const headers = Array.from(response.headers.entries());
+    const bodySource = ((response as unknown) as { _bodySource: unknown })
+      ._bodySource;
+    if (bodySource instanceof ReadableStreamImpl) {
+      const { bodyRid } = httpOps.respond(
+        {
+          rid: request.requestRid,
+          status: response.status,
+          headers,
+          streamBody: true,
+        },
+        undefined
+      );
+      await writeResponseBody(bodyRid!, bodySource);
+    } else {
+      const body =
+        bodySource == null
+          ? undefined
+          : new Uint8Array(await response.arrayBuffer());
+      httpOps.respond(
+        {
+          rid: request.requestRid,
+          status: response.status,
+          headers,
+          streamBody: false,
+        },
+        body
+      );
+    }
+  } finally {
+    // The request body is no longer needed once the response is sent.
+    if (request.bodyRid !== null) {
+      tryClose(request.bodyRid);
+    }
+  }
+}
+
+export class HttpConn implements AsyncIterable<RequestEvent> {
+  #rid: number;
+  #closed = false;
+
+  constructor(rid: number) {
+    this.#rid = rid;
+  }
+
+  get rid(): number {
+    return this.#rid;
+  }
+
+  /** Resolves with the next request, or with `null` once the connection is
+   * closed. */
+  async nextRequest(): Promise<RequestEvent | null> {
+    const next = await httpOps.nextRequest(this.#rid);
+    if (next === null) {
+      this.close();
+      return null;
+    }
+    const request = new Request(next.url, {
+      method: next.method,
+      headers: next.headers,
+      body: next.bodyRid === null ? null : createRequestBody(next.bodyRid),
+    });
+    return {
+      request,
+      respondWith: (r): Promise<void> => respondWith(next, r),
+    };
+  }
+
+  /** Gracefully shuts the connection down. Responses that are in flight are
+   * still sent. */
+  close(): void {
+    if (!this.#closed) {
+      this.#closed = true;
+      close(this.#rid);
+    }
+  }
+
+  async *[Symbol.asyncIterator](): AsyncIterableIterator<RequestEvent> {
+    while (true) {
+      const event = await this.nextRequest();
+      if (event === null) {
+        return;
+      }
+      yield event;
+    }
+  }
+}
+
+export function serveHttp(conn: Conn): HttpConn {
+  return new HttpConn(httpOps.startHttp(conn.rid));
+}
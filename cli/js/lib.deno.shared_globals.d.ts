@@ -1246,15 +1246,104 @@ interface MessageEventInit extends EventInit {
   data?: any;
   origin?: string;
   lastEventId?: string;
+  ports?: MessagePort[];
 }
 
 declare class MessageEvent extends Event {
   readonly data: any;
   readonly origin: string;
   readonly lastEventId: string;
+  /** Ports transferred along with the message. */
+  readonly ports: readonly MessagePort[];
   constructor(type: string, eventInitDict?: MessageEventInit);
 }
 
+/** Values that can be moved to another worker with `postMessage()` instead of
+ * being cloned. A transferred `ArrayBuffer` becomes detached in the sender. */
+type Transferable = ArrayBuffer | MessagePort;
+
+/** One end of a `MessageChannel`. Ports can be transferred to workers to let
+ * them communicate with each other directly. Messages are copied with the
+ * structured clone algorithm. */
+declare class MessagePort extends EventTarget {
+  onmessage: ((ev: MessageEvent) => any) | null;
+  onmessageerror: ((ev: MessageEvent) => any) | null;
+  postMessage(message: any, transfer: Transferable[]): void;
+  postMessage(message: any, options?: PostMessageOptions): void;
+  /** Starts delivering messages. Implicitly called when `onmessage` is set
+   * or a "message" listener is added. */
+  start(): void;
+  /** Disconnects the port. A port with a message listener keeps the program
+   * alive until it or its entangled port is closed. */
+  close(): void;
+}
+
+/** Creates a pair of entangled `MessagePort`s.
+ *
+ * ```ts
+ * const { port1, port2 } = new MessageChannel();
+ * worker1.postMessage({ port: "a" }, [port1]);
+ * worker2.postMessage({ port: "b" }, [port2]);
+ * // The workers receive the ports in `event.ports[0]`.
+ * ```
+ */
+declare class MessageChannel {
+  constructor();
+  readonly port1: MessagePort;
+  readonly port2: MessagePort;
+}
+
+interface CloseEventInit extends EventInit {
+  wasClean?: boolean;
+  code?: number;
+  reason?: string;
+}
+
+declare class CloseEvent extends Event {
+  /** Whether the closing handshake completed. */
+  readonly wasClean: boolean;
+  readonly code: number;
+  readonly reason: string;
+  constructor(type: string, eventInitDict?: CloseEventInit);
+}
+
+type BinaryType = "blob" | "arraybuffer";
+
+/** A WebSocket client. Connecting requires `allow-net` permission for the
+ * host.
+ *
+ * ```ts
+ * const socket = new WebSocket("ws://localhost:4500");
+ * socket.onopen = () => socket.send("hello");
+ * socket.onmessage = (e) => console.log(e.data);
+ * ```
+ */
+declare class WebSocket extends EventTarget {
+  constructor(url: string, protocols?: string | string[]);
+  static readonly CONNECTING: number;
+  static readonly OPEN: number;
+  static readonly CLOSING: number;
+  static readonly CLOSED: number;
+  readonly CONNECTING: number;
+  readonly OPEN: number;
+  readonly CLOSING: number;
+  readonly CLOSED: number;
+  readonly url: string;
+  /** The subprotocol selected by the server. */
+  readonly protocol: string;
+  readonly extensions: string;
+  readonly readyState: number;
+  /** Number of bytes passed to `send()` that were not yet sent. */
+  readonly bufferedAmount: number;
+  binaryType: BinaryType;
+  onopen: ((this: WebSocket, ev: Event) => any) | null;
+  onmessage: ((this: WebSocket, ev: MessageEvent) => any) | null;
+  onerror: ((this: WebSocket, ev: Event | ErrorEvent) => any) | null;
+  onclose: ((this: WebSocket, ev: CloseEvent) => any) | null;
+  send(data: string | ArrayBuffer | ArrayBufferView | Blob): void;
+  close(code?: number, reason?: string): void;
+}
+
 interface ErrorEventInit extends EventInit {
   message?: string;
   filename?: string;
@@ -1273,7 +1362,7 @@ declare class ErrorEvent extends Event {
 }
 
 interface PostMessageOptions {
-  transfer?: any[];
+  transfer?: Transferable[];
 }
 
 declare class Worker extends EventTarget {
@@ -1292,9 +1381,27 @@ declare class Worker extends EventTarget {
        * Set to `true` to make `Deno` namespace and all of its methods
        * available to worker thread.
        *
-       * Currently worker inherits permissions from main thread (permissions
-       * given using `--allow-*` flags).
-       * Configurable permissions are on the roadmap to be implemented.
+       * By default the worker inherits the permissions of its parent. Pass
+       * an object instead of `true` to restrict them: `namespace` controls
+       * the availability of the `Deno` namespace and `permissions` is
+       * `"inherit"`, `"none"` or an object granting individual permissions.
+       * Each of those is `"inherit"`, a boolean or, for `read`, `write` and
+       * `net`, a list of allowed paths or hosts. Omitted permissions are not
+       * granted. Asking for a permission the parent doesn't have throws a
+       * `PermissionDenied` error.
+       *
+       * ```ts
+       * const worker = new Worker(
+       *   new URL("plugin.ts", import.meta.url).href,
+       *   {
+       *     type: "module",
+       *     deno: {
+       *       namespace: true,
+       *       permissions: { read: ["./plugins/data"] },
+       *     },
+       *   }
+       * );
+       * ```
        *
        * Example:
        *
@@ -1330,10 +1437,55 @@ declare class Worker extends EventTarget {
        * hello world2
        *
        */
-      deno?: boolean;
+      deno?:
+        | boolean
+        | {
+            namespace?: boolean;
+            permissions?:
+              | "inherit"
+              | "none"
+              | {
+                  read?: "inherit" | boolean | string[];
+                  write?: "inherit" | boolean | string[];
+                  net?: "inherit" | boolean | string[];
+                  env?: "inherit" | boolean;
+                  run?: "inherit" | boolean;
+                  plugin?: "inherit" | boolean;
+                  hrtime?: "inherit" | boolean;
+                };
+          };
+      /** UNSTABLE: New API.
+       *
+       * Limits on the resources the worker may consume. When a limit is
+       * exceeded the worker is terminated and an `error` event is dispatched
+       * on the `Worker` object. Its `error` is an `Error` named
+       * `"ResourceLimitError"` whose `limit` property names the exceeded
+       * limit.
+       *
+       * ```ts
+       * const worker = new Worker(
+       *   new URL("worker.ts", import.meta.url).href,
+       *   {
+       *     type: "module",
+       *     resourceLimits: { maxHeapSizeMb: 64, maxCpuTimeMs: 1000 },
+       *   }
+       * );
+       * ```
+       */
+      resourceLimits?: {
+        /** Maximum size of the worker's heap, in megabytes. */
+        maxHeapSizeMb?: number;
+        /** Maximum CPU time the worker thread may use, in milliseconds. */
+        maxCpuTimeMs?: number;
+        /** Maximum time the worker may be alive for, in milliseconds. */
+        maxWallTimeMs?: number;
+        /** Maximum number of simultaneously open resources, including the
+         * standard streams of workers with access to the `Deno` namespace. */
+        maxResources?: number;
+      };
     }
   );
-  postMessage(message: any, transfer: ArrayBuffer[]): void;
+  postMessage(message: any, transfer: Transferable[]): void;
   postMessage(message: any, options?: PostMessageOptions): void;
   terminate(): void;
 }
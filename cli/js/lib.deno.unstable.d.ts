@@ -820,6 +820,26 @@ declare namespace Deno {
      *
      * Sends a message to the target. */
     send(p: Uint8Array, addr: Addr): Promise<number>;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables sending messages to broadcast addresses
+     * (`SO_BROADCAST`). Enabled if `broadcast` is omitted. */
+    setBroadcast(broadcast?: boolean): void;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Joins the IPv4 multicast group `address` on the interface with the
+     * address `iface` (default "0.0.0.0", any interface). */
+    joinMulticastV4(address: string, iface?: string): void;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Joins the IPv6 multicast group `address` on the interface with the
+     * index `interfaceIndex` (default 0, any interface). */
+    joinMulticastV6(address: string, interfaceIndex?: number): void;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Leaves a multicast group joined with `joinMulticastV4()` or
+     * `joinMulticastV6()`. */
+    leaveMulticast(address: string, iface?: string | number): void;
     /** UNSTABLE: new API, yet to be vetted.
      *
      * Close closes the socket. Any pending message promises will be rejected
@@ -835,6 +855,28 @@ declare namespace Deno {
     path: string;
   }
 
+  export interface ListenOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Sets `SO_REUSEPORT` on the socket, so that several listeners can bind
+     * to the same port and share incoming connections. Only supported on
+     * Unix. */
+    reusePort?: boolean;
+  }
+
+  export interface Conn {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables Nagle's algorithm (`TCP_NODELAY`). Disabled, i.e.
+     * no delay, if `noDelay` is omitted. */
+    setNoDelay(noDelay?: boolean): void;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Enables or disables TCP keep-alive. `delay` is the idle time in
+     * milliseconds before the first probe is sent. */
+    setKeepAlive(keepAlive?: boolean, delay?: number): void;
+  }
+
   /** **UNSTABLE**: new API, yet to be vetted.
    *
    * Listen announces on the local transport address.
@@ -915,6 +957,164 @@ declare namespace Deno {
     hostname?: string;
     /** Server certificate file. */
     certFile?: string;
+    /** PEM encoded certificate chain presented to servers that request a
+     * client certificate. Requires `clientKeyFile`. */
+    clientCertFile?: string;
+    /** Private key of `clientCertFile`. */
+    clientKeyFile?: string;
+    /** Name used for SNI and to verify the server certificate, if it differs
+     * from `hostname`. */
+    serverName?: string;
+    /** Application-Layer Protocol Negotiation (ALPN) protocols to offer, in
+     * order of preference. */
+    alpnProtocols?: string[];
+  }
+
+  export interface ConnectTlsOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM encoded certificate chain presented to servers that request a
+     * client certificate. Requires `clientKeyFile`. */
+    clientCertFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Private key of `clientCertFile`. */
+    clientKeyFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Name used for SNI and to verify the server certificate, if it differs
+     * from `hostname`. */
+    serverName?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Application-Layer Protocol Negotiation (ALPN) protocols to offer, in
+     * order of preference. */
+    alpnProtocols?: string[];
+  }
+
+  export interface ListenTlsOptions {
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Additional certificates, selected by the server name the client
+     * requests with SNI. `certFile` and `keyFile` are used for clients that
+     * request no or an unknown server name. */
+    certificates?: Array<{
+      hostname: string;
+      certFile: string;
+      keyFile: string;
+    }>;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Application-Layer Protocol Negotiation (ALPN) protocols supported by
+     * the server, in order of preference. */
+    alpnProtocols?: string[];
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * PEM file of the certificate authorities that client certificates must
+     * be signed by. */
+    clientCaFile?: string;
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Whether clients must present a certificate ("require", the default
+     * when `clientCaFile` is set) or may connect without one ("request"). */
+    clientAuth?: "request" | "require";
+    /** **UNSTABLE**: new API, yet to be vetted.
+     *
+     * Sets `SO_REUSEPORT` on the listening socket, so that several listeners
+     * can share the port. Only supported on Unix. */
+    reusePort?: boolean;
+  }
+
+  export interface ResolveDnsOptions {
+    /** The nameserver to query instead of the ones configured on the
+     * system. */
+    nameServer?: {
+      /** IPv4 or IPv6 address of the nameserver. */
+      ipAddr: string;
+      /** Defaults to 53. */
+      port?: number;
+    };
+  }
+
+  export type RecordType = "A" | "AAAA" | "CNAME" | "MX" | "SRV" | "TXT";
+
+  export interface MXRecord {
+    preference: number;
+    exchange: string;
+  }
+
+  export interface SRVRecord {
+    priority: number;
+    weight: number;
+    port: number;
+    target: string;
+  }
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * Resolves the DNS records of `query`. A and AAAA records resolve to IP
+   * addresses, CNAME records to names and TXT records to the list of their
+   * strings.
+   *
+   * ```ts
+   * const a = await Deno.resolveDns("example.com", "A");
+   * const mx = await Deno.resolveDns("example.com", "MX", {
+   *   nameServer: { ipAddr: "1.1.1.1" },
+   * });
+   * ```
+   *
+   * Requires `allow-net` permission for the nameservers that are queried.
+   */
+  export function resolveDns(
+    query: string,
+    recordType: "A" | "AAAA" | "CNAME",
+    options?: ResolveDnsOptions
+  ): Promise<string[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "MX",
+    options?: ResolveDnsOptions
+  ): Promise<MXRecord[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "SRV",
+    options?: ResolveDnsOptions
+  ): Promise<SRVRecord[]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: "TXT",
+    options?: ResolveDnsOptions
+  ): Promise<string[][]>;
+
+  export function resolveDns(
+    query: string,
+    recordType: RecordType,
+    options?: ResolveDnsOptions
+  ): Promise<string[] | MXRecord[] | SRVRecord[] | string[][]>;
+
+  /** **UNSTABLE**: new API, yet to be vetted.
+   *
+   * A connection returned by `Deno.connectTls()`, `Deno.startTls()` or the
+   * `accept()` method of a listener created with `Deno.listenTls()`.
+   *
+   * ```ts
+   * const conn = await Deno.connectTls({
+   *   hostname: "example.com",
+   *   port: 443,
+   *   alpnProtocols: ["h2", "http/1.1"],
+   * }) as Deno.TlsConn;
+   * console.log(conn.alpnProtocol);
+   * ```
+   */
+  export interface TlsConn extends Conn {
+    /** The protocol negotiated with ALPN, or `null` if none was. */
+    readonly alpnProtocol: string | null;
+    /** The PEM encoded certificate chain presented by the peer, leaf
+     * first. Empty if the peer presented no certificate. */
+    readonly peerCertificates: string[];
   }
 
   /** **UNSTABLE**: new API, yet to be vetted.
@@ -1192,4 +1392,117 @@ declare namespace Deno {
    * ```
    */
   export function fstat(rid: number): Promise<FileInfo>;
+
+  export interface RequestEvent {
+    readonly request: Request;
+    /** Sends the response. A response with a `ReadableStream` body is sent
+     * chunk by chunk, using chunked encoding for HTTP/1.1. */
+    respondWith(r: Response | Promise<Response>): Promise<void>;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * An HTTP connection served natively. */
+  export interface HttpConn extends AsyncIterable<RequestEvent> {
+    readonly rid: number;
+
+    /** Resolves with the next request, or with `null` once the connection
+     * is closed. */
+    nextRequest(): Promise<RequestEvent | null>;
+    /** Gracefully shuts the connection down. Responses that are in flight
+     * are still sent. */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Serves HTTP on a connection accepted from `Deno.listen()` or
+   * `Deno.listenTls()`. HTTP/1.1 (with keep-alive) and HTTP/2 are supported.
+   * The connection is taken over, so `conn` must not be used afterwards.
+   *
+   * ```ts
+   * const listener = Deno.listen({ port: 4500 });
+   * for await (const conn of listener) {
+   *   (async () => {
+   *     for await (const { request, respondWith } of Deno.serveHttp(conn)) {
+   *       respondWith(new Response("Hello World"));
+   *     }
+   *   })();
+   * }
+   * ```
+   *
+   * Requires `allow-net` permission for the listener.
+   */
+  export function serveHttp(conn: Conn): HttpConn;
+
+  export interface UpgradeWebSocketOptions {
+    /** Subprotocols supported by the server. The first one requested by the
+     * client that is in this list is selected. */
+    protocols?: string[];
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Performs the server side of the WebSocket handshake on a connection
+   * accepted from `Deno.listen()` or `Deno.listenTls()`. The connection is
+   * taken over, so `conn` must not be used afterwards. The `url` of the
+   * returned socket is the requested path.
+   *
+   * ```ts
+   * const listener = Deno.listen({ port: 4500 });
+   * for await (const conn of listener) {
+   *   const socket = await Deno.upgradeWebSocket(conn);
+   *   socket.onmessage = (e) => socket.send(e.data);
+   * }
+   * ```
+   */
+  export function upgradeWebSocket(
+    conn: Conn,
+    options?: UpgradeWebSocketOptions
+  ): Promise<WebSocket>;
+
+  export interface CreateHttpClientOptions {
+    /** A PEM encoded certificate that is trusted in addition to the default
+     * root certificates and the one passed with `--cert`. */
+    caData?: string;
+    /** URL of a proxy all requests are sent through, e.g.
+     * `"http://localhost:8080"`. Requires `allow-net` permission for the
+     * proxy. */
+    proxy?: string;
+    /** Timeout of a whole request in milliseconds, from connecting until the
+     * response body has been read. */
+    timeout?: number;
+    /** If `true` only HTTP/2 is spoken (with prior knowledge), if `false`
+     * only HTTP/1.1. By default the protocol is negotiated. */
+    http2?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * A custom HTTP client for use with `fetch()`. Connections are kept alive
+   * and reused between requests made with the same client. */
+  export class HttpClient {
+    readonly rid: number;
+    /** Closes the client. Requests that are in progress are not aborted. */
+    close(): void;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * Create a custom HttpClient to use with `fetch()`.
+   *
+   * ```ts
+   * const client = Deno.createHttpClient({ caData: await Deno.readTextFile("./ca.pem") });
+   * const res = await fetch("https://myserver.com", { client });
+   * client.close();
+   * ```
+   */
+  export function createHttpClient(
+    options?: CreateHttpClientOptions
+  ): HttpClient;
+}
+
+interface RequestInit {
+  /** **UNSTABLE**: A custom `Deno.HttpClient` to send the request with. */
+  client?: Deno.HttpClient;
 }
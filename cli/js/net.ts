@@ -12,6 +12,14 @@ export interface DatagramConn extends AsyncIterable<[Uint8Array, Addr]> {
 
   send(p: Uint8Array, addr: Addr): Promise<number>;
 
+  setBroadcast(broadcast?: boolean): void;
+
+  joinMulticastV4(address: string, iface?: string): void;
+
+  joinMulticastV6(address: string, interfaceIndex?: number): void;
+
+  leaveMulticast(address: string, iface?: string | number): void;
+
   close(): void;
 
   addr: Addr;
@@ -54,6 +62,14 @@ export class ConnImpl implements Conn {
   closeWrite(): void {
     netOps.shutdown(this.rid, netOps.ShutdownMode.Write);
   }
+
+  setNoDelay(noDelay = true): void {
+    netOps.setNoDelay(this.rid, noDelay);
+  }
+
+  setKeepAlive(keepAlive = true, delay?: number): void {
+    netOps.setKeepAlive(this.rid, keepAlive, delay);
+  }
 }
 
 export class ListenerImpl implements Listener {
@@ -117,6 +133,22 @@ export class DatagramImpl implements DatagramConn {
     return byteLength;
   }
 
+  setBroadcast(broadcast = true): void {
+    netOps.setBroadcast(this.rid, broadcast);
+  }
+
+  joinMulticastV4(address: string, iface?: string): void {
+    netOps.joinMulticastV4(this.rid, address, iface);
+  }
+
+  joinMulticastV6(address: string, interfaceIndex?: number): void {
+    netOps.joinMulticastV6(this.rid, address, interfaceIndex);
+  }
+
+  leaveMulticast(address: string, iface?: string | number): void {
+    netOps.leaveMulticast(this.rid, address, iface);
+  }
+
   close(): void {
     close(this.rid);
   }
@@ -140,12 +172,15 @@ export interface Conn extends Reader, Writer, Closer {
   remoteAddr: Addr;
   rid: number;
   closeWrite(): void;
+  setNoDelay(noDelay?: boolean): void;
+  setKeepAlive(keepAlive?: boolean, delay?: number): void;
 }
 
 export interface ListenOptions {
   port: number;
   hostname?: string;
   transport?: "tcp";
+  reusePort?: boolean;
 }
 
 export function listen(
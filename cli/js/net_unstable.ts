@@ -15,6 +15,7 @@ export interface ListenOptions {
   port: number;
   hostname?: string;
   transport?: "tcp" | "udp";
+  reusePort?: boolean;
 }
 
 export interface UnixListenOptions {
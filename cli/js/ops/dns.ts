@@ -0,0 +1,61 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { sendAsync } from "./dispatch_json.ts";
+
+export interface ResolveDnsOptions {
+  nameServer?: {
+    ipAddr: string;
+    port?: number;
+  };
+}
+
+export type RecordType = "A" | "AAAA" | "CNAME" | "MX" | "SRV" | "TXT";
+
+export interface MXRecord {
+  preference: number;
+  exchange: string;
+}
+
+export interface SRVRecord {
+  priority: number;
+  weight: number;
+  port: number;
+  target: string;
+}
+
+export function resolveDns(
+  query: string,
+  recordType: "A" | "AAAA" | "CNAME",
+  options?: ResolveDnsOptions
+): Promise<string[]>;
+export function resolveDns(
+  query: string,
+  recordType: "MX",
+  options?: ResolveDnsOptions
+): Promise<MXRecord[]>;
+export function resolveDns(
+  query: string,
+  recordType: "SRV",
+  options?: ResolveDnsOptions
+): Promise<SRVRecord[]>;
+export function resolveDns(
+  query: string,
+  recordType: "TXT",
+  options?: ResolveDnsOptions
+): Promise<string[][]>;
+export function resolveDns(
+  query: string,
+  recordType: RecordType,
+  options?: ResolveDnsOptions
+): Promise<string[] | MXRecord[] | SRVRecord[] | string[][]>;
+export function resolveDns(
+  query: string,
+  recordType: RecordType,
+  options: ResolveDnsOptions = {}
+): Promise<string[] | MXRecord[] | SRVRecord[] | string[][]> {
+  return sendAsync("op_resolve_dns", {
+    query,
+    recordType,
+    nameServer: options.nameServer,
+  });
+}
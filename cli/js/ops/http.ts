@@ -0,0 +1,33 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { sendAsync, sendSync } from "./dispatch_json.ts";
+
+export interface HttpRequest {
+  requestRid: number;
+  bodyRid: number | null;
+  method: string;
+  url: string;
+  headers: Array<[string, string]>;
+}
+
+export function startHttp(rid: number): number {
+  return sendSync("op_http_start", { rid });
+}
+
+export function nextRequest(rid: number): Promise<HttpRequest | null> {
+  return sendAsync("op_http_request_next", { rid });
+}
+
+interface RespondArgs {
+  rid: number;
+  status: number;
+  headers: Array<[string, string]>;
+  streamBody: boolean;
+}
+
+export function respond(
+  args: RespondArgs,
+  body: Uint8Array | undefined
+): { bodyRid: number | null } {
+  return sendSync("op_http_respond", args, ...(body ? [body] : []));
+}
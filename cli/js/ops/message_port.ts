@@ -0,0 +1,24 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { sendAsync, sendSync } from "./dispatch_json.ts";
+
+/** The rids of a serialized value and of the ports it transfers. */
+export interface SerializedMessage {
+  value: number;
+  ports: number[];
+}
+
+export function createEntangled(): [number, number] {
+  return sendSync("op_message_port_create_entangled");
+}
+
+export function postMessage(rid: number, message: SerializedMessage): void {
+  sendSync("op_message_port_post_message", { rid, message });
+}
+
+export async function recvMessage(
+  rid: number
+): Promise<SerializedMessage | null> {
+  const { message } = await sendAsync("op_message_port_recv_message", { rid });
+  return message;
+}
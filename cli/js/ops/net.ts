@@ -40,7 +40,7 @@ export function accept(
   return sendAsync("op_accept", { rid, transport });
 }
 
-export type ListenRequest = Addr;
+export type ListenRequest = Addr & { reusePort?: boolean };
 
 interface ListenResponse {
   rid: number;
@@ -87,3 +87,48 @@ export async function send(
   const byteLength = await sendAsync("op_datagram_send", args, zeroCopy);
   return byteLength;
 }
+
+export function setNoDelay(rid: number, noDelay: boolean): void {
+  sendSync("op_set_nodelay", { rid, noDelay });
+}
+
+export function setKeepAlive(
+  rid: number,
+  keepAlive: boolean,
+  delay?: number
+): void {
+  sendSync("op_set_keepalive", { rid, keepAlive, delay });
+}
+
+export function setBroadcast(rid: number, broadcast: boolean): void {
+  sendSync("op_set_broadcast", { rid, broadcast });
+}
+
+export function joinMulticastV4(
+  rid: number,
+  address: string,
+  iface?: string
+): void {
+  sendSync("op_join_multicast_v4", { rid, address, interface: iface });
+}
+
+export function joinMulticastV6(
+  rid: number,
+  address: string,
+  interfaceIndex?: number
+): void {
+  sendSync("op_join_multicast_v6", { rid, address, interfaceIndex });
+}
+
+export function leaveMulticast(
+  rid: number,
+  address: string,
+  iface?: string | number
+): void {
+  sendSync("op_leave_multicast", {
+    rid,
+    address,
+    interface: typeof iface === "string" ? iface : undefined,
+    interfaceIndex: typeof iface === "number" ? iface : undefined,
+  });
+}
@@ -6,9 +6,18 @@ export interface ConnectTLSRequest {
   hostname: string;
   port: number;
   certFile?: string;
+  clientCertFile?: string;
+  clientKeyFile?: string;
+  serverName?: string;
+  alpnProtocols?: string[];
 }
 
-interface EstablishTLSResponse {
+export interface TLSInfo {
+  alpnProtocol: string | null;
+  peerCertificates: string[];
+}
+
+interface EstablishTLSResponse extends TLSInfo {
   rid: number;
   localAddr: {
     hostname: string;
@@ -28,7 +37,7 @@ export function connectTls(
   return sendAsync("op_connect_tls", args);
 }
 
-interface AcceptTLSResponse {
+interface AcceptTLSResponse extends TLSInfo {
   rid: number;
   localAddr: {
     hostname: string;
@@ -52,6 +61,15 @@ export interface ListenTLSRequest {
   transport: "tcp";
   certFile: string;
   keyFile: string;
+  certificates?: Array<{
+    hostname: string;
+    certFile: string;
+    keyFile: string;
+  }>;
+  alpnProtocols?: string[];
+  clientCaFile?: string;
+  clientAuth?: "request" | "require";
+  reusePort?: boolean;
 }
 
 interface ListenTLSResponse {
@@ -71,6 +89,10 @@ export interface StartTLSRequest {
   rid: number;
   hostname: string;
   certFile?: string;
+  clientCertFile?: string;
+  clientKeyFile?: string;
+  serverName?: string;
+  alpnProtocols?: string[];
 }
 
 export function startTls(args: StartTLSRequest): Promise<EstablishTLSResponse> {
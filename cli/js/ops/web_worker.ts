@@ -1,8 +1,13 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
 import { sendSync } from "./dispatch_json.ts";
+import { SerializedMessage } from "./message_port.ts";
 
-export function postMessage(data: Uint8Array): void {
+export function postMessage(message: SerializedMessage): void {
+  sendSync("op_worker_post_message", { message });
+}
+
+export function postJsonMessage(data: Uint8Array): void {
   sendSync("op_worker_post_message", {}, data);
 }
 
@@ -0,0 +1,50 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+import { sendAsync } from "./dispatch_json.ts";
+
+export type WebSocketEvent =
+  | { type: "string"; data: string }
+  | { type: "binary"; data: number[] }
+  | { type: "ping" }
+  | { type: "pong" }
+  | { type: "close"; code: number; reason: string }
+  | { type: "error"; message: string }
+  | { type: "closed"; code: number };
+
+export function createWebSocket(
+  url: string,
+  protocols: string[]
+): Promise<{ rid: number; protocol: string }> {
+  return sendAsync("op_ws_create", { url, protocols });
+}
+
+export function acceptWebSocket(
+  rid: number,
+  protocols: string[]
+): Promise<{ rid: number; path: string; protocol: string }> {
+  return sendAsync("op_ws_accept", { rid, protocols });
+}
+
+export async function send(
+  rid: number,
+  kind: "text" | "binary" | "ping" | "pong",
+  data?: string | Uint8Array
+): Promise<void> {
+  if (typeof data === "string") {
+    await sendAsync("op_ws_send", { rid, kind, text: data });
+  } else {
+    await sendAsync("op_ws_send", { rid, kind }, ...(data ? [data] : []));
+  }
+}
+
+export async function close(
+  rid: number,
+  code?: number,
+  reason?: string
+): Promise<void> {
+  await sendAsync("op_ws_close", { rid, code, reason });
+}
+
+export function nextEvent(rid: number): Promise<WebSocketEvent> {
+  return sendAsync("op_ws_next_event", { rid });
+}
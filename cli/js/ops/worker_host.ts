@@ -1,13 +1,35 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 /* eslint-disable @typescript-eslint/no-explicit-any */
 import { sendAsync, sendSync } from "./dispatch_json.ts";
+import { SerializedMessage } from "./message_port.ts";
+
+export type PermissionArg = "inherit" | boolean | string[];
+
+export interface WorkerPermissions {
+  read?: PermissionArg;
+  write?: PermissionArg;
+  net?: PermissionArg;
+  env?: "inherit" | boolean;
+  run?: "inherit" | boolean;
+  plugin?: "inherit" | boolean;
+  hrtime?: "inherit" | boolean;
+}
+
+export interface ResourceLimits {
+  maxHeapSizeMb?: number;
+  maxCpuTimeMs?: number;
+  maxWallTimeMs?: number;
+  maxResources?: number;
+}
 
 export function createWorker(
   specifier: string,
   hasSourceCode: boolean,
   sourceCode: string,
   useDenoNamespace: boolean,
-  name?: string
+  name?: string,
+  permissions?: WorkerPermissions,
+  resourceLimits?: ResourceLimits
 ): { id: number } {
   return sendSync("op_create_worker", {
     specifier,
@@ -15,6 +37,8 @@ export function createWorker(
     sourceCode,
     name,
     useDenoNamespace,
+    permissions,
+    resourceLimits,
   });
 }
 
@@ -22,8 +46,11 @@ export function hostTerminateWorker(id: number): void {
   sendSync("op_host_terminate_worker", { id });
 }
 
-export function hostPostMessage(id: number, data: Uint8Array): void {
-  sendSync("op_host_post_message", { id }, data);
+export function hostPostMessage(
+  id: number,
+  message: SerializedMessage
+): void {
+  sendSync("op_host_post_message", { id, message });
 }
 
 export function hostGetMessage(id: number): Promise<any> {
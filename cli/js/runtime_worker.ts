@@ -23,7 +23,14 @@ import * as denoUnstableNs from "./deno_unstable.ts";
 import * as webWorkerOps from "./ops/web_worker.ts";
 import { log, assert, immutableDefine } from "./util.ts";
 import { ErrorEventImpl as ErrorEvent } from "./web/error_event.ts";
-import { MessageEvent } from "./web/workers.ts";
+import {
+  MessageEvent,
+  PostMessageOptions,
+  Transferable,
+  deserializeMessage,
+  getTransferList,
+  serializeMessage,
+} from "./web/message_port.ts";
 import { TextEncoder } from "./web/text_encoding.ts";
 import * as runtime from "./runtime.ts";
 import { internalObject, internalSymbol } from "./internals.ts";
@@ -38,15 +45,29 @@ import { setSignals } from "./signals.ts";
 
 const encoder = new TextEncoder();
 
+// Messages are structured clones, unless the worker was bootstrapped for a
+// host written in Rust, like the TS compiler, which exchanges JSON instead.
+let useJsonMessages = false;
+
 // TODO(bartlomieju): remove these funtions
 // Stuff for workers
 export const onmessage: (e: { data: any }) => void = (): void => {};
 export const onerror: (e: { data: any }) => void = (): void => {};
 
-export function postMessage(data: any): void {
-  const dataJson = JSON.stringify(data);
-  const dataIntArray = encoder.encode(dataJson);
-  webWorkerOps.postMessage(dataIntArray);
+export function postMessage(
+  data: any,
+  transferOrOptions?: Transferable[] | PostMessageOptions
+): void {
+  if (isClosing) {
+    return;
+  }
+  if (useJsonMessages) {
+    webWorkerOps.postJsonMessage(encoder.encode(JSON.stringify(data)));
+    return;
+  }
+  webWorkerOps.postMessage(
+    serializeMessage(data, getTransferList(transferOrOptions))
+  );
 }
 
 let isClosing = false;
@@ -61,11 +82,25 @@ export function close(): void {
   webWorkerOps.close();
 }
 
-export async function workerMessageRecvCallback(data: string): Promise<void> {
-  const msgEvent = new MessageEvent("message", {
-    cancelable: false,
-    data,
-  });
+/** Receives the rids of a structured clone, or the message itself if the
+ * worker exchanges JSON. */
+export async function workerMessageRecvCallback(
+  message: any
+): Promise<void> {
+  let msgEvent;
+  if (useJsonMessages) {
+    msgEvent = new MessageEvent("message", {
+      cancelable: false,
+      data: message,
+    });
+  } else {
+    const { data, ports } = deserializeMessage(message);
+    msgEvent = new MessageEvent("message", {
+      cancelable: false,
+      data,
+      ports,
+    });
+  }
 
   try {
     if (globalThis["onmessage"]) {
@@ -122,7 +157,8 @@ export const workerRuntimeGlobalProperties = {
 export function bootstrapWorkerRuntime(
   name: string,
   useDenoNamespace: boolean,
-  internalName?: string
+  internalName?: string,
+  jsonMessages = false
 ): void {
   if (hasBootstrapped) {
     throw new Error("Worker runtime already bootstrapped");
@@ -132,6 +168,7 @@ export function bootstrapWorkerRuntime(
   (globalThis as any).bootstrap = undefined;
   log("bootstrapWorkerRuntime");
   hasBootstrapped = true;
+  useJsonMessages = jsonMessages;
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeMethods);
   Object.defineProperties(globalThis, windowOrWorkerGlobalScopeProperties);
   Object.defineProperties(globalThis, workerRuntimeGlobalProperties);
@@ -1,6 +1,7 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 import * as tlsOps from "./ops/tls.ts";
 import { Listener, Conn, ConnImpl, ListenerImpl } from "./net.ts";
+import { Addr } from "./ops/net.ts";
 
 // TODO(ry) There are many configuration options to add...
 // https://docs.rs/rustls/0.16.0/rustls/struct.ClientConfig.html
@@ -9,6 +10,22 @@ interface ConnectTlsOptions {
   port: number;
   hostname?: string;
   certFile?: string;
+  clientCertFile?: string;
+  clientKeyFile?: string;
+  serverName?: string;
+  alpnProtocols?: string[];
+}
+
+export class TlsConnImpl extends ConnImpl {
+  constructor(
+    rid: number,
+    remoteAddr: Addr,
+    localAddr: Addr,
+    readonly alpnProtocol: string | null,
+    readonly peerCertificates: string[]
+  ) {
+    super(rid, remoteAddr, localAddr);
+  }
 }
 
 export async function connectTls({
@@ -16,20 +33,40 @@ export async function connectTls({
   hostname = "127.0.0.1",
   transport = "tcp",
   certFile = undefined,
+  clientCertFile = undefined,
+  clientKeyFile = undefined,
+  serverName = undefined,
+  alpnProtocols = undefined,
 }: ConnectTlsOptions): Promise<Conn> {
   const res = await tlsOps.connectTls({
     port,
     hostname,
     transport,
     certFile,
+    clientCertFile,
+    clientKeyFile,
+    serverName,
+    alpnProtocols,
   });
-  return new ConnImpl(res.rid, res.remoteAddr!, res.localAddr!);
+  return new TlsConnImpl(
+    res.rid,
+    res.remoteAddr,
+    res.localAddr,
+    res.alpnProtocol,
+    res.peerCertificates
+  );
 }
 
 class TLSListenerImpl extends ListenerImpl {
   async accept(): Promise<Conn> {
     const res = await tlsOps.acceptTLS(this.rid);
-    return new ConnImpl(res.rid, res.remoteAddr, res.localAddr);
+    return new TlsConnImpl(
+      res.rid,
+      res.remoteAddr,
+      res.localAddr,
+      res.alpnProtocol,
+      res.peerCertificates
+    );
   }
 }
 
@@ -39,6 +76,15 @@ export interface ListenTlsOptions {
   transport?: "tcp";
   certFile: string;
   keyFile: string;
+  certificates?: Array<{
+    hostname: string;
+    certFile: string;
+    keyFile: string;
+  }>;
+  alpnProtocols?: string[];
+  clientCaFile?: string;
+  clientAuth?: "request" | "require";
+  reusePort?: boolean;
 }
 
 export function listenTls({
@@ -47,6 +93,11 @@ export function listenTls({
   keyFile,
   hostname = "0.0.0.0",
   transport = "tcp",
+  certificates = undefined,
+  alpnProtocols = undefined,
+  clientCaFile = undefined,
+  clientAuth = undefined,
+  reusePort = undefined,
 }: ListenTlsOptions): Listener {
   const res = tlsOps.listenTls({
     port,
@@ -54,6 +105,11 @@ export function listenTls({
     keyFile,
     hostname,
     transport,
+    certificates,
+    alpnProtocols,
+    clientCaFile,
+    clientAuth,
+    reusePort,
   });
   return new TLSListenerImpl(res.rid, res.localAddr);
 }
@@ -61,16 +117,37 @@ export function listenTls({
 interface StartTlsOptions {
   hostname?: string;
   certFile?: string;
+  clientCertFile?: string;
+  clientKeyFile?: string;
+  serverName?: string;
+  alpnProtocols?: string[];
 }
 
 export async function startTls(
   conn: Conn,
-  { hostname = "127.0.0.1", certFile = undefined }: StartTlsOptions = {}
+  {
+    hostname = "127.0.0.1",
+    certFile = undefined,
+    clientCertFile = undefined,
+    clientKeyFile = undefined,
+    serverName = undefined,
+    alpnProtocols = undefined,
+  }: StartTlsOptions = {}
 ): Promise<Conn> {
   const res = await tlsOps.startTls({
     rid: conn.rid,
     hostname,
     certFile,
+    clientCertFile,
+    clientKeyFile,
+    serverName,
+    alpnProtocols,
   });
-  return new ConnImpl(res.rid, res.remoteAddr!, res.localAddr!);
+  return new TlsConnImpl(
+    res.rid,
+    res.remoteAddr,
+    res.localAddr,
+    res.alpnProtocol,
+    res.peerCertificates
+  );
 }
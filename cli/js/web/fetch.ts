@@ -6,11 +6,20 @@ import { TextEncoder } from "./text_encoding.ts";
 import { DenoBlob, bytesSymbol as blobBytesSymbol } from "./blob.ts";
 import { read } from "../ops/io.ts";
 import { close } from "../ops/resources.ts";
-import { fetch as opFetch, FetchResponse } from "../ops/fetch.ts";
+import {
+  fetch as opFetch,
+  fetchCancel,
+  fetchRequestWrite,
+  fetchSend,
+  FetchResponse,
+  createHttpClient as opCreateHttpClient,
+  CreateHttpClientOptions,
+} from "../ops/fetch.ts";
 import * as Body from "./body.ts";
 import { getHeaderValueParams } from "./util.ts";
 import { ReadableStreamImpl } from "./streams/readable_stream.ts";
 import { MultipartBuilder } from "./fetch/multipart.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
 
 const NULL_BODY_STATUS = [101, 204, 205, 304];
 const REDIRECT_STATUS = [301, 302, 303, 307, 308];
@@ -166,34 +175,137 @@ export class Response extends Body.Body implements domTypes.Response {
   }
 }
 
-function sendFetchReq(
+export class HttpClient {
+  #rid: number;
+
+  constructor(rid: number) {
+    this.#rid = rid;
+  }
+
+  get rid(): number {
+    return this.#rid;
+  }
+
+  close(): void {
+    close(this.#rid);
+  }
+}
+
+export function createHttpClient(
+  options: CreateHttpClientOptions = {}
+): HttpClient {
+  return new HttpClient(opCreateHttpClient(options));
+}
+
+function abortError(): DOMException {
+  return new DOMException("The request was aborted.", "AbortError");
+}
+
+/** Closes a resource that may already have been closed by a cancellation. */
+function tryClose(rid: number): void {
+  try {
+    close(rid);
+  } catch {
+    // Already closed.
+  }
+}
+
+async function writeRequestBody(
+  requestRid: number,
+  requestBodyRid: number,
+  body: ReadableStream<Uint8Array>
+): Promise<void> {
+  const reader = body.getReader();
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) {
+        break;
+      }
+      if (!(value instanceof Uint8Array)) {
+        throw new TypeError("Request body chunks must be Uint8Arrays");
+      }
+      await fetchRequestWrite(requestBodyRid, value);
+    }
+  } catch (e) {
+    reader.cancel(e).catch(() => {});
+    // Abort the request, so that a truncated body isn't sent as if it was
+    // complete.
+    try {
+      fetchCancel(requestRid);
+    } catch {
+      // The response has already arrived.
+    }
+  }
+  tryClose(requestBodyRid);
+}
+
+async function sendFetchReq(
   url: string,
   method: string | null,
   headers: Headers | null,
-  body: ArrayBufferView | undefined
+  body: ArrayBufferView | ReadableStream<Uint8Array> | undefined,
+  client: HttpClient | undefined,
+  signal: AbortSignal | null | undefined
 ): Promise<FetchResponse> {
+  if (signal?.aborted) {
+    throw abortError();
+  }
+
   let headerArray: Array<[string, string]> = [];
   if (headers) {
     headerArray = Array.from(headers.entries());
   }
 
+  const streamBody = body instanceof ReadableStreamImpl;
   const args = {
     method,
     url,
     headers: headerArray,
+    clientRid: client?.rid,
+    streamBody,
   };
 
-  return opFetch(args, body);
+  const { requestRid, requestBodyRid } = opFetch(
+    args,
+    streamBody ? undefined : (body as ArrayBufferView | undefined)
+  );
+  if (requestBodyRid !== null) {
+    writeRequestBody(
+      requestRid,
+      requestBodyRid,
+      body as ReadableStream<Uint8Array>
+    );
+  }
+
+  const onAbort = (): void => {
+    try {
+      fetchCancel(requestRid);
+    } catch {
+      // The response has already arrived.
+    }
+  };
+  signal?.addEventListener("abort", onAbort);
+  try {
+    return await fetchSend(requestRid);
+  } catch (e) {
+    if (signal?.aborted) {
+      throw abortError();
+    }
+    throw e;
+  } finally {
+    signal?.removeEventListener("abort", onAbort);
+  }
 }
 
 export async function fetch(
   input: (domTypes.Request & { _bodySource?: unknown }) | URL | string,
-  init?: domTypes.RequestInit
+  init?: domTypes.RequestInit & { client?: HttpClient }
 ): Promise<Response> {
   let url: string;
   let method: string | null = null;
   let headers: Headers | null = null;
-  let body: ArrayBufferView | undefined;
+  let body: ArrayBufferView | ReadableStream<Uint8Array> | undefined;
   let redirected = false;
   let remRedirectCount = 20; // TODO: use a better way to handle
 
@@ -240,8 +352,10 @@ export async function fetch(
           const multipartBuilder = new MultipartBuilder(init.body, boundary);
           body = multipartBuilder.getBody();
           contentType = multipartBuilder.getContentType();
+        } else if (init.body instanceof ReadableStreamImpl) {
+          // Streamed chunk by chunk instead of being buffered in memory.
+          body = init.body;
         } else {
-          // TODO: ReadableStream
           notImplemented();
         }
         if (contentType && !headers.has("content-type")) {
@@ -259,10 +373,18 @@ export async function fetch(
     }
   }
 
+  const signal = init?.signal;
   let responseBody;
   let responseInit: ResponseInit = {};
   while (remRedirectCount) {
-    const fetchResponse = await sendFetchReq(url, method, headers, body);
+    const fetchResponse = await sendFetchReq(
+      url,
+      method,
+      headers,
+      body,
+      init?.client,
+      signal
+    );
 
     if (
       NULL_BODY_STATUS.includes(fetchResponse.status) ||
@@ -273,26 +395,37 @@ export async function fetch(
       close(fetchResponse.bodyRid);
       responseBody = null;
     } else {
+      const bodyRid = fetchResponse.bodyRid;
+      const onAbort = (): void => {
+        try {
+          fetchCancel(bodyRid);
+        } catch {
+          // The body was already read or cancelled.
+        }
+      };
+      signal?.addEventListener("abort", onAbort);
       responseBody = new ReadableStreamImpl({
         async pull(controller: ReadableStreamDefaultController): Promise<void> {
           try {
             const b = new Uint8Array(1024 * 32);
-            const result = await read(fetchResponse.bodyRid, b);
+            const result = await read(bodyRid, b);
             if (result === null) {
+              signal?.removeEventListener("abort", onAbort);
               controller.close();
-              return close(fetchResponse.bodyRid);
+              return close(bodyRid);
             }
 
             controller.enqueue(b.subarray(0, result));
           } catch (e) {
-            controller.error(e);
-            controller.close();
-            close(fetchResponse.bodyRid);
+            signal?.removeEventListener("abort", onAbort);
+            controller.error(signal?.aborted ? abortError() : e);
+            tryClose(bodyRid);
           }
         },
         cancel(): void {
           // When reader.cancel() is called
-          close(fetchResponse.bodyRid);
+          signal?.removeEventListener("abort", onAbort);
+          tryClose(bodyRid);
         },
       });
     }
@@ -347,6 +480,22 @@ export async function fetch(
               url.split("//")[1].split("/")[0] +
               redirectUrl; // TODO: handle relative redirection more gracefully
           }
+          if (body instanceof ReadableStreamImpl) {
+            // A streamed body can't be sent again.
+            const status = fetchResponse.status;
+            if (
+              status === 303 ||
+              ((status === 301 || status === 302) && method === "POST")
+            ) {
+              method = "GET";
+              body = undefined;
+              headers?.delete("content-type");
+            } else {
+              throw new TypeError(
+                "Cannot follow a redirect of a request with a streamed body"
+              );
+            }
+          }
           url = redirectUrl;
           redirected = true;
           remRedirectCount--;
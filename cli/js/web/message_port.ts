@@ -0,0 +1,254 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+import * as messagePortOps from "../ops/message_port.ts";
+import { SerializedMessage } from "../ops/message_port.ts";
+import { close } from "../ops/resources.ts";
+import { core } from "../core.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { EventImpl as Event } from "./event.ts";
+import { EventTargetImpl as EventTarget } from "./event_target.ts";
+
+const portRid = Symbol("portRid");
+const detach = Symbol("detach");
+
+export interface MessageEventInit extends EventInit {
+  data?: any;
+  origin?: string;
+  lastEventId?: string;
+  ports?: MessagePort[];
+}
+
+export class MessageEvent extends Event {
+  readonly data: any;
+  readonly origin: string;
+  readonly lastEventId: string;
+  readonly ports: readonly MessagePort[];
+
+  constructor(type: string, eventInitDict?: MessageEventInit) {
+    super(type, {
+      bubbles: eventInitDict?.bubbles ?? false,
+      cancelable: eventInitDict?.cancelable ?? false,
+      composed: eventInitDict?.composed ?? false,
+    });
+
+    this.data = eventInitDict?.data ?? null;
+    this.origin = eventInitDict?.origin ?? "";
+    this.lastEventId = eventInitDict?.lastEventId ?? "";
+    this.ports = Object.freeze([...(eventInitDict?.ports ?? [])]);
+  }
+}
+
+export type Transferable = ArrayBuffer | MessagePort;
+
+export interface PostMessageOptions {
+  transfer?: Transferable[];
+}
+
+/** Normalizes the second argument of the various `postMessage()` methods. */
+export function getTransferList(
+  transferOrOptions?: Transferable[] | PostMessageOptions
+): Transferable[] {
+  if (transferOrOptions == null) {
+    return [];
+  }
+  if (Array.isArray(transferOrOptions)) {
+    return transferOrOptions;
+  }
+  return transferOrOptions.transfer ?? [];
+}
+
+/** Serializes a message with the structured clone algorithm.
+ *
+ * `ArrayBuffer`s in the transfer list are moved without a copy and become
+ * detached. `MessagePort`s in the transfer list are delivered through the
+ * `ports` property of the receiving `MessageEvent`.
+ *
+ * The result must be passed to one of the ops posting a message right away,
+ * which takes its resources out of the resource table. */
+export function serializeMessage(
+  message: any,
+  transferList: Transferable[],
+  sourcePort?: MessagePort
+): SerializedMessage {
+  const arrayBuffers: ArrayBuffer[] = [];
+  const ports: MessagePortImpl[] = [];
+  for (const item of transferList) {
+    if (item instanceof MessagePortImpl) {
+      if (
+        item === sourcePort ||
+        ports.includes(item) ||
+        item[portRid]() === null
+      ) {
+        throw new DOMException(
+          "Invalid MessagePort in transfer list",
+          "DataCloneError"
+        );
+      }
+      ports.push(item);
+    } else if (item instanceof ArrayBuffer) {
+      if (arrayBuffers.includes(item)) {
+        throw new DOMException(
+          "ArrayBuffer appears more than once in transfer list",
+          "DataCloneError"
+        );
+      }
+      arrayBuffers.push(item);
+    } else {
+      throw new DOMException(
+        "Value in transfer list is not transferable",
+        "DataCloneError"
+      );
+    }
+  }
+
+  let value;
+  try {
+    value = core.serialize(message, arrayBuffers);
+  } catch (e) {
+    throw new DOMException(e.message, "DataCloneError");
+  }
+  // Ports are only detached once the message was cloned successfully, so
+  // that a DataCloneError leaves them usable.
+  return { value, ports: ports.map((port) => port[detach]()) };
+}
+
+export function deserializeMessage(
+  message: SerializedMessage
+): { data: any; ports: MessagePort[] } {
+  const ports = message.ports.map((rid) => new MessagePortImpl(rid));
+  let data;
+  try {
+    data = core.deserialize(message.value);
+  } catch (e) {
+    ports.forEach((port) => port.close());
+    throw e;
+  }
+  return { data, ports };
+}
+
+/** Frees the resources of a received message that is not dispatched. */
+export function discardMessage(message: SerializedMessage): void {
+  close(message.value);
+  message.ports.forEach((rid) => close(rid));
+}
+
+export class MessagePortImpl extends EventTarget implements MessagePort {
+  #rid: number | null;
+  #started = false;
+
+  #onmessage: ((ev: MessageEvent) => any) | null = null;
+  onmessageerror: ((ev: MessageEvent) => any) | null = null;
+
+  get onmessage(): ((ev: MessageEvent) => any) | null {
+    return this.#onmessage;
+  }
+
+  /** Setting `onmessage` implicitly starts the port, like in browsers. */
+  set onmessage(value: ((ev: MessageEvent) => any) | null) {
+    this.#onmessage = value;
+    this.start();
+  }
+
+  constructor(rid: number) {
+    super();
+    this.#rid = rid;
+  }
+
+  postMessage(
+    message: any,
+    transferOrOptions?: Transferable[] | PostMessageOptions
+  ): void {
+    if (this.#rid === null) {
+      return;
+    }
+    const transferList = getTransferList(transferOrOptions);
+    const data = serializeMessage(message, transferList, this);
+    messagePortOps.postMessage(this.#rid, data);
+  }
+
+  start(): void {
+    if (this.#started || this.#rid === null) {
+      return;
+    }
+    this.#started = true;
+    this.#poll(this.#rid);
+  }
+
+  close(): void {
+    if (this.#rid !== null) {
+      close(this.#rid);
+      this.#rid = null;
+    }
+  }
+
+  [portRid](): number | null {
+    return this.#rid;
+  }
+
+  /** Detaches the port so that its resource can be sent to another isolate.
+   * Returns the rid of the resource. */
+  [detach](): number {
+    const rid = this.#rid!;
+    this.#rid = null;
+    return rid;
+  }
+
+  #poll = async (rid: number): Promise<void> => {
+    while (this.#rid === rid) {
+      const message = await messagePortOps.recvMessage(rid);
+      if (message === null) {
+        // The port was closed or transferred, or its entangled port is gone.
+        if (this.#rid === rid) {
+          this.close();
+        }
+        return;
+      }
+      this.#dispatchMessage(message);
+    }
+  };
+
+  #dispatchMessage = (serialized: SerializedMessage): void => {
+    let message;
+    try {
+      message = deserializeMessage(serialized);
+    } catch (e) {
+      const event = new MessageEvent("messageerror", { cancelable: false });
+      if (this.onmessageerror) {
+        this.onmessageerror(event);
+      }
+      this.dispatchEvent(event);
+      return;
+    }
+    const event = new MessageEvent("message", {
+      cancelable: false,
+      data: message.data,
+      ports: message.ports,
+    });
+    if (this.#onmessage) {
+      this.#onmessage(event);
+    }
+    this.dispatchEvent(event);
+  };
+
+  addEventListener(
+    type: string,
+    listener: EventListenerOrEventListenerObject | null,
+    options?: boolean | AddEventListenerOptions
+  ): void {
+    super.addEventListener(type, listener, options);
+    if (type === "message") {
+      this.start();
+    }
+  }
+}
+
+export class MessageChannelImpl implements MessageChannel {
+  readonly port1: MessagePort;
+  readonly port2: MessagePort;
+
+  constructor() {
+    const [rid1, rid2] = messagePortOps.createEntangled();
+    this.port1 = new MessagePortImpl(rid1);
+    this.port2 = new MessagePortImpl(rid2);
+  }
+}
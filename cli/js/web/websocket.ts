@@ -0,0 +1,334 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+/* eslint-disable @typescript-eslint/no-explicit-any */
+
+import * as wsOps from "../ops/websocket.ts";
+import { close } from "../ops/resources.ts";
+import { DenoBlob } from "./blob.ts";
+import { DOMExceptionImpl as DOMException } from "./dom_exception.ts";
+import { ErrorEventImpl as ErrorEvent } from "./error_event.ts";
+import { EventImpl as Event } from "./event.ts";
+import { EventTargetImpl as EventTarget } from "./event_target.ts";
+import { MessageEvent } from "./message_port.ts";
+import { TextEncoder } from "./text_encoding.ts";
+import { URLImpl as URL } from "./url.ts";
+
+const CONNECTING = 0;
+const OPEN = 1;
+const CLOSING = 2;
+const CLOSED = 3;
+
+export type BinaryType = "blob" | "arraybuffer";
+
+export interface CloseEventInit extends EventInit {
+  wasClean?: boolean;
+  code?: number;
+  reason?: string;
+}
+
+export class CloseEventImpl extends Event {
+  readonly wasClean: boolean;
+  readonly code: number;
+  readonly reason: string;
+
+  constructor(type: string, eventInitDict?: CloseEventInit) {
+    super(type, {
+      bubbles: eventInitDict?.bubbles ?? false,
+      cancelable: eventInitDict?.cancelable ?? false,
+      composed: eventInitDict?.composed ?? false,
+    });
+
+    this.wasClean = eventInitDict?.wasClean ?? false;
+    this.code = eventInitDict?.code ?? 0;
+    this.reason = eventInitDict?.reason ?? "";
+  }
+}
+
+/** Passed to the constructor instead of a URL to wrap a connection that was
+ * already upgraded by `Deno.upgradeWebSocket()`. */
+const accepted = Symbol("accepted");
+
+interface AcceptedWebSocket {
+  [accepted]: { rid: number; url: string; protocol: string };
+}
+
+export class WebSocketImpl extends EventTarget {
+  static readonly CONNECTING = CONNECTING;
+  static readonly OPEN = OPEN;
+  static readonly CLOSING = CLOSING;
+  static readonly CLOSED = CLOSED;
+  readonly CONNECTING = CONNECTING;
+  readonly OPEN = OPEN;
+  readonly CLOSING = CLOSING;
+  readonly CLOSED = CLOSED;
+
+  #rid: number | null = null;
+  #url: string;
+  #protocol = "";
+  #readyState = CONNECTING;
+  #binaryType: BinaryType = "blob";
+  #bufferedAmount = 0;
+
+  onopen: ((ev: Event) => any) | null = null;
+  onmessage: ((ev: MessageEvent) => any) | null = null;
+  onerror: ((ev: Event) => any) | null = null;
+  onclose: ((ev: CloseEventImpl) => any) | null = null;
+
+  constructor(
+    url: string | AcceptedWebSocket,
+    protocols: string | string[] = []
+  ) {
+    super();
+    if (typeof url === "object" && accepted in url) {
+      const { rid, url: acceptedUrl, protocol } = url[accepted];
+      this.#url = acceptedUrl;
+      this.#rid = rid;
+      this.#protocol = protocol;
+      this.#readyState = OPEN;
+      this.#poll(rid);
+      return;
+    }
+
+    let parsed;
+    try {
+      parsed = new URL(String(url));
+    } catch (e) {
+      throw new DOMException(e.message, "SyntaxError");
+    }
+    if (parsed.protocol !== "ws:" && parsed.protocol !== "wss:") {
+      throw new DOMException(
+        "Only ws: and wss: schemes are allowed in a WebSocket URL",
+        "SyntaxError"
+      );
+    }
+    if (parsed.hash !== "") {
+      throw new DOMException(
+        "Fragments are not allowed in a WebSocket URL",
+        "SyntaxError"
+      );
+    }
+    this.#url = parsed.href;
+
+    const protocolList =
+      typeof protocols === "string" ? [protocols] : protocols;
+    if (new Set(protocolList).size !== protocolList.length) {
+      throw new DOMException(
+        "Can't supply multiple times the same protocol",
+        "SyntaxError"
+      );
+    }
+
+    wsOps.createWebSocket(this.#url, protocolList).then(
+      ({ rid, protocol }) => {
+        if (this.#readyState !== CONNECTING) {
+          // `close()` was called while connecting.
+          close(rid);
+          return;
+        }
+        this.#rid = rid;
+        this.#protocol = protocol;
+        this.#readyState = OPEN;
+        this.#dispatch(new Event("open"), this.onopen);
+        this.#poll(rid);
+      },
+      (e) => {
+        this.#fail(e.message);
+      }
+    );
+  }
+
+  get url(): string {
+    return this.#url;
+  }
+
+  get protocol(): string {
+    return this.#protocol;
+  }
+
+  get extensions(): string {
+    return "";
+  }
+
+  get readyState(): number {
+    return this.#readyState;
+  }
+
+  get bufferedAmount(): number {
+    return this.#bufferedAmount;
+  }
+
+  get binaryType(): BinaryType {
+    return this.#binaryType;
+  }
+
+  set binaryType(value: BinaryType) {
+    if (value === "blob" || value === "arraybuffer") {
+      this.#binaryType = value;
+    }
+  }
+
+  send(data: string | ArrayBuffer | ArrayBufferView | Blob): void {
+    if (this.#readyState === CONNECTING) {
+      throw new DOMException("WebSocket is not open", "InvalidStateError");
+    }
+    if (this.#readyState !== OPEN) {
+      return;
+    }
+    const rid = this.#rid!;
+    const sendBytes = (bytes: Uint8Array): void => {
+      this.#bufferedAmount += bytes.byteLength;
+      wsOps.send(rid, "binary", bytes).then(
+        () => {
+          this.#bufferedAmount -= bytes.byteLength;
+        },
+        () => {}
+      );
+    };
+    if (typeof data === "string") {
+      const length = new TextEncoder().encode(data).byteLength;
+      this.#bufferedAmount += length;
+      wsOps.send(rid, "text", data).then(
+        () => {
+          this.#bufferedAmount -= length;
+        },
+        () => {}
+      );
+    } else if (data instanceof ArrayBuffer) {
+      sendBytes(new Uint8Array(data));
+    } else if (ArrayBuffer.isView(data)) {
+      sendBytes(new Uint8Array(data.buffer, data.byteOffset, data.byteLength));
+    } else if (data instanceof DenoBlob) {
+      data.arrayBuffer().then((buf) => sendBytes(new Uint8Array(buf)));
+    } else {
+      throw new TypeError("Invalid data type");
+    }
+  }
+
+  close(code?: number, reason?: string): void {
+    if (code !== undefined && code !== 1000 && (code < 3000 || code > 4999)) {
+      throw new DOMException(
+        "The close code must be either 1000 or in the range of 3000 to 4999",
+        "InvalidAccessError"
+      );
+    }
+    if (reason !== undefined && new TextEncoder().encode(reason).length > 123) {
+      throw new DOMException(
+        "The close reason may not be longer than 123 bytes",
+        "SyntaxError"
+      );
+    }
+
+    if (this.#readyState === CONNECTING) {
+      this.#fail("WebSocket was closed before the connection was established");
+    } else if (this.#readyState === OPEN) {
+      this.#readyState = CLOSING;
+      wsOps.close(this.#rid!, code, reason).catch((e) => {
+        this.#fail(e.message);
+      });
+    }
+  }
+
+  #poll = async (rid: number): Promise<void> => {
+    while (this.#rid === rid) {
+      let event: wsOps.WebSocketEvent;
+      try {
+        event = await wsOps.nextEvent(rid);
+      } catch (e) {
+        // The resource was closed.
+        return;
+      }
+      switch (event.type) {
+        case "string":
+          this.#dispatch(
+            new MessageEvent("message", {
+              data: event.data,
+              origin: this.#url,
+            }),
+            this.onmessage
+          );
+          break;
+        case "binary": {
+          const bytes = new Uint8Array(event.data);
+          const data =
+            this.#binaryType === "arraybuffer"
+              ? bytes.buffer
+              : new DenoBlob([bytes]);
+          this.#dispatch(
+            new MessageEvent("message", { data, origin: this.#url }),
+            this.onmessage
+          );
+          break;
+        }
+        case "ping":
+        case "pong":
+          break;
+        case "close":
+          this.#finish(true, event.code, event.reason);
+          return;
+        case "closed":
+          this.#finish(false, event.code, "");
+          return;
+        case "error":
+          this.#fail(event.message);
+          return;
+      }
+    }
+  };
+
+  /** Fails the connection: an "error" event followed by an abnormal "close"
+   * event. */
+  #fail = (message: string): void => {
+    if (this.#readyState === CLOSED) {
+      return;
+    }
+    const errorEvent = new ErrorEvent("error", { message });
+    this.#dispatch(errorEvent, this.onerror);
+    this.#finish(false, 1006, "");
+  };
+
+  #finish = (wasClean: boolean, code: number, reason: string): void => {
+    if (this.#readyState === CLOSED) {
+      return;
+    }
+    this.#readyState = CLOSED;
+    if (this.#rid !== null) {
+      close(this.#rid);
+      this.#rid = null;
+    }
+    this.#dispatch(
+      new CloseEventImpl("close", { wasClean, code, reason }),
+      this.onclose
+    );
+  };
+
+  #dispatch = <E extends Event>(
+    event: E,
+    handler: ((ev: E) => any) | null
+  ): void => {
+    if (handler) {
+      handler.call(this, event);
+    }
+    this.dispatchEvent(event);
+  };
+}
+
+Object.defineProperty(WebSocketImpl, "name", {
+  value: "WebSocket",
+  configurable: true,
+});
+
+export interface UpgradeWebSocketOptions {
+  protocols?: string[];
+}
+
+/** Performs the server side of the WebSocket handshake on `conn`, which must
+ * not have been read from or written to yet. */
+export async function upgradeWebSocket(
+  conn: { rid: number },
+  options: UpgradeWebSocketOptions = {}
+): Promise<WebSocketImpl> {
+  const { rid, path, protocol } = await wsOps.acceptWebSocket(
+    conn.rid,
+    options.protocols ?? []
+  );
+  return new WebSocketImpl({ [accepted]: { rid, url: path, protocol } });
+}
@@ -5,55 +5,35 @@ import {
   hostTerminateWorker,
   hostPostMessage,
   hostGetMessage,
+  ResourceLimits,
+  WorkerPermissions,
 } from "../ops/worker_host.ts";
+import { SerializedMessage } from "../ops/message_port.ts";
 import { log } from "../util.ts";
-import { TextDecoder, TextEncoder } from "./text_encoding.ts";
+import { TextDecoder } from "./text_encoding.ts";
 /*
 import { blobURLMap } from "./web/url.ts";
 */
 import { ErrorEventImpl as ErrorEvent } from "./error_event.ts";
-import { EventImpl as Event } from "./event.ts";
 import { EventTargetImpl as EventTarget } from "./event_target.ts";
+import {
+  MessageEvent,
+  PostMessageOptions,
+  Transferable,
+  deserializeMessage,
+  discardMessage,
+  getTransferList,
+  serializeMessage,
+} from "./message_port.ts";
 
-const encoder = new TextEncoder();
 const decoder = new TextDecoder();
 
-export interface MessageEventInit extends EventInit {
-  data?: any;
-  origin?: string;
-  lastEventId?: string;
-}
-
-export class MessageEvent extends Event {
-  readonly data: any;
-  readonly origin: string;
-  readonly lastEventId: string;
-
-  constructor(type: string, eventInitDict?: MessageEventInit) {
-    super(type, {
-      bubbles: eventInitDict?.bubbles ?? false,
-      cancelable: eventInitDict?.cancelable ?? false,
-      composed: eventInitDict?.composed ?? false,
-    });
-
-    this.data = eventInitDict?.data ?? null;
-    this.origin = eventInitDict?.origin ?? "";
-    this.lastEventId = eventInitDict?.lastEventId ?? "";
-  }
-}
-
-function encodeMessage(data: any): Uint8Array {
-  const dataJson = JSON.stringify(data);
-  return encoder.encode(dataJson);
-}
-
-function decodeMessage(dataIntArray: Uint8Array): any {
-  const dataJson = decoder.decode(dataIntArray);
-  return JSON.parse(dataJson);
-}
+export { MessageEvent };
 
 interface WorkerHostError {
   message: string;
+  name?: string;
+  limit?: string;
   fileName?: string;
   lineNumber?: number;
   columnNumber?: number;
@@ -61,7 +41,7 @@ interface WorkerHostError {
 
 interface WorkerHostMessage {
   type: "terminalError" | "error" | "msg";
-  data?: any;
+  message?: SerializedMessage;
   error?: WorkerHostError;
 }
 
@@ -69,14 +49,23 @@ export interface Worker {
   onerror?: (e: ErrorEvent) => void;
   onmessage?: (e: MessageEvent) => void;
   onmessageerror?: (e: MessageEvent) => void;
-  postMessage(data: any): void;
+  postMessage(
+    data: any,
+    transferOrOptions?: Transferable[] | PostMessageOptions
+  ): void;
   terminate(): void;
 }
 
 export interface WorkerOptions {
   type?: "classic" | "module";
   name?: string;
-  deno?: boolean;
+  deno?:
+    | boolean
+    | {
+        namespace?: boolean;
+        permissions?: "inherit" | "none" | WorkerPermissions;
+      };
+  resourceLimits?: ResourceLimits;
 }
 
 export class WorkerImpl extends EventTarget implements Worker {
@@ -118,27 +107,40 @@ export class WorkerImpl extends EventTarget implements Worker {
     }
     */
 
-    const useDenoNamespace = options ? !!options.deno : false;
+    let useDenoNamespace = false;
+    let permissions: WorkerPermissions | undefined;
+    if (typeof options?.deno === "object") {
+      useDenoNamespace = !!options.deno.namespace;
+      const requested = options.deno.permissions ?? "inherit";
+      if (requested === "none") {
+        permissions = {};
+      } else if (requested !== "inherit") {
+        permissions = requested;
+      }
+    } else {
+      useDenoNamespace = !!options?.deno;
+    }
 
     const { id } = createWorker(
       specifier,
       hasSourceCode,
       sourceCode,
       useDenoNamespace,
-      options?.name
+      options?.name,
+      permissions,
+      options?.resourceLimits
     );
     this.#id = id;
     this.#poll();
   }
 
-  #handleMessage = (msgData: any): void => {
-    let data;
+  #handleMessage = (serialized: SerializedMessage): void => {
+    let message;
     try {
-      data = decodeMessage(new Uint8Array(msgData));
+      message = deserializeMessage(serialized);
     } catch (e) {
       const msgErrorEvent = new MessageEvent("messageerror", {
         cancelable: false,
-        data,
       });
       if (this.onmessageerror) {
         this.onmessageerror(msgErrorEvent);
@@ -148,7 +150,8 @@ export class WorkerImpl extends EventTarget implements Worker {
 
     const msgEvent = new MessageEvent("message", {
       cancelable: false,
-      data,
+      data: message.data,
+      ports: message.ports,
     });
 
     if (this.onmessage) {
@@ -159,13 +162,19 @@ export class WorkerImpl extends EventTarget implements Worker {
   };
 
   #handleError = (e: WorkerHostError): boolean => {
+    let error = null;
+    if (e.name === "ResourceLimitError") {
+      error = new Error(e.message);
+      error.name = e.name;
+      Object.assign(error, { limit: e.limit });
+    }
     const event = new ErrorEvent("error", {
       cancelable: true,
       message: e.message,
       lineno: e.lineNumber ? e.lineNumber + 1 : undefined,
       colno: e.columnNumber ? e.columnNumber + 1 : undefined,
       filename: e.fileName,
-      error: null,
+      error,
     });
 
     let handled = false;
@@ -187,6 +196,9 @@ export class WorkerImpl extends EventTarget implements Worker {
 
       // If terminate was called then we ignore all messages
       if (this.#terminated) {
+        if (event.type === "msg") {
+          discardMessage(event.message!);
+        }
         return;
       }
 
@@ -201,7 +213,7 @@ export class WorkerImpl extends EventTarget implements Worker {
       }
 
       if (type === "msg") {
-        this.#handleMessage(event.data);
+        this.#handleMessage(event.message!);
         continue;
       }
 
@@ -222,18 +234,16 @@ export class WorkerImpl extends EventTarget implements Worker {
     }
   };
 
-  postMessage(message: any, transferOrOptions?: any): void {
-    if (transferOrOptions) {
-      throw new Error(
-        "Not yet implemented: `transfer` and `options` are not supported."
-      );
-    }
-
+  postMessage(
+    message: any,
+    transferOrOptions?: Transferable[] | PostMessageOptions
+  ): void {
     if (this.#terminated) {
       return;
     }
 
-    hostPostMessage(this.#id, encodeMessage(message));
+    const data = serializeMessage(message, getTransferList(transferOrOptions));
+    hostPostMessage(this.#id, data);
   }
 
   terminate(): void {
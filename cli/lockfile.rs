@@ -43,6 +43,8 @@ impl Lockfile {
 
   /// Lazily reads the filename, checks the given module is included.
   /// Returns Ok(true) if check passed
+  ///
+  /// `url` is the final URL of the module, after following all redirects.
   pub fn check(&mut self, url: &Url, code: Vec<u8>) -> Result<bool> {
     let url_str = url.to_string();
     if url_str.starts_with("file:") {
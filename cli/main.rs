@@ -22,6 +22,9 @@ extern crate serde_derive;
 extern crate tokio;
 extern crate url;
 
+mod auth_tokens;
+mod bundler;
+mod cache_gc;
 mod checksum;
 pub mod colors;
 pub mod deno_dir;
@@ -40,6 +43,7 @@ mod global_timer;
 pub mod http_cache;
 mod http_util;
 mod import_map;
+mod info;
 mod inspector;
 pub mod installer;
 mod js;
@@ -51,8 +55,12 @@ pub mod msg;
 pub mod op_error;
 pub mod ops;
 pub mod permissions;
+mod profiler;
+mod progress;
 mod repl;
 pub mod resolve_addr;
+mod resolver;
+mod resource_limits;
 pub mod signal;
 pub mod source_maps;
 mod startup_data;
@@ -72,7 +80,6 @@ pub use dprint_plugin_typescript::swc_ecma_parser;
 
 use crate::doc::parser::DocFileLoader;
 use crate::file_fetcher::SourceFile;
-use crate::file_fetcher::SourceFileFetcher;
 use crate::fs as deno_fs;
 use crate::global_state::GlobalState;
 use crate::msg::MediaType;
@@ -205,7 +212,6 @@ async fn print_file_info(
       TargetLib::Main,
       Permissions::allow_all(),
       false,
-      global_state.maybe_import_map.clone(),
     )
     .await?;
   global_state
@@ -283,8 +289,11 @@ fn get_types(unstable: bool) -> String {
 async fn info_command(
   flags: Flags,
   file: Option<String>,
+  json: bool,
+  dot: bool,
+  why: Option<String>,
 ) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
   // If it was just "deno info" print location of caches and exit
   if file.is_none() {
     print_cache_info(&global_state);
@@ -292,6 +301,31 @@ async fn info_command(
   }
 
   let main_module = ModuleSpecifier::resolve_url_or_path(&file.unwrap())?;
+  if json || dot || why.is_some() {
+    let graph = info::build_module_graph(&global_state, &main_module).await?;
+    if json {
+      let info = info::graph_to_json(&global_state, &main_module, &graph);
+      let info = serde_json::to_string_pretty(&info)? + "\n";
+      return write_to_stdout_ignore_sigpipe(info.as_bytes())
+        .map_err(ErrBox::from);
+    }
+    if dot {
+      return write_to_stdout_ignore_sigpipe(
+        info::graph_to_dot(&main_module, &graph).as_bytes(),
+      )
+      .map_err(ErrBox::from);
+    }
+    let target = ModuleSpecifier::resolve_url_or_path(&why.unwrap())?;
+    let paths = info::import_paths(&graph, &main_module, &target);
+    if paths.is_empty() {
+      println!("{} is not imported by {}", target, main_module);
+    }
+    for path in paths {
+      println!("{}", path.join(" -> "));
+    }
+    return Ok(());
+  }
+
   let mut worker = MainWorker::create(global_state, main_module.clone())?;
   worker.preload_module(&main_module).await?;
   print_file_info(&worker, main_module.clone()).await
@@ -308,7 +342,7 @@ async fn install_command(
   // Firstly fetch and compile module, this step ensures that module exists.
   let mut fetch_flags = flags.clone();
   fetch_flags.reload = true;
-  let global_state = GlobalState::new(fetch_flags)?;
+  let global_state = GlobalState::new(fetch_flags).await?;
   let main_module = ModuleSpecifier::resolve_url_or_path(&module_url)?;
   let mut worker = MainWorker::create(global_state, main_module.clone())?;
   worker.preload_module(&main_module).await?;
@@ -321,7 +355,7 @@ async fn lint_command(
   files: Vec<String>,
   list_rules: bool,
 ) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
 
   // TODO(bartlomieju): refactor, it's non-sense to create
   // state just to perform unstable check...
@@ -330,7 +364,6 @@ async fn lint_command(
     global_state,
     None,
     ModuleSpecifier::resolve_url("file:///dummy.ts").unwrap(),
-    None,
     true,
   )?;
 
@@ -347,7 +380,7 @@ async fn lint_command(
 async fn cache_command(flags: Flags, files: Vec<String>) -> Result<(), ErrBox> {
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./__$deno$fetch.ts").unwrap();
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
   let mut worker =
     MainWorker::create(global_state.clone(), main_module.clone())?;
 
@@ -361,6 +394,39 @@ async fn cache_command(flags: Flags, files: Vec<String>) -> Result<(), ErrBox> {
   Ok(())
 }
 
+async fn cache_clean_command(
+  flags: Flags,
+  max_size: Option<u64>,
+  max_age: Option<u64>,
+  dry_run: bool,
+) -> Result<(), ErrBox> {
+  let global_state = GlobalState::new(flags).await?;
+  let options = cache_gc::GcOptions {
+    max_size,
+    max_age: max_age.and_then(cache_gc::GcOptions::max_age_from_days),
+    dry_run,
+  };
+  let report = cache_gc::collect_garbage(&global_state, &options)?;
+  if dry_run {
+    for file in &report.files {
+      println!("{}", file.display());
+    }
+    println!(
+      "Would remove {} files, reclaiming {}",
+      report.files.len(),
+      human_size(report.reclaimed_bytes as f64)
+    );
+  } else {
+    println!(
+      "{} {} files, reclaimed {}",
+      colors::green("Removed"),
+      report.files.len(),
+      human_size(report.reclaimed_bytes as f64)
+    );
+  }
+  Ok(())
+}
+
 async fn eval_command(
   flags: Flags,
   code: String,
@@ -370,7 +436,7 @@ async fn eval_command(
   // Force TypeScript compile.
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./__$deno$eval.ts").unwrap();
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
   let mut worker = MainWorker::create(global_state, main_module.clone())?;
   let main_module_url = main_module.as_url().to_owned();
   // Create a dummy source file.
@@ -384,6 +450,7 @@ async fn eval_command(
   let source_file = SourceFile {
     filename: main_module_url.to_file_path().unwrap(),
     url: main_module_url,
+    redirects: vec![],
     types_header: None,
     media_type: if as_typescript {
       MediaType::TypeScript
@@ -412,6 +479,7 @@ async fn bundle_command(
   flags: Flags,
   source_file: String,
   out_file: Option<PathBuf>,
+  minify: bool,
 ) -> Result<(), ErrBox> {
   let mut module_specifier =
     ModuleSpecifier::resolve_url_or_path(&source_file)?;
@@ -425,7 +493,7 @@ async fn bundle_command(
   }
 
   debug!(">>>>> bundle START");
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
 
   info!(
     "{} {}",
@@ -433,10 +501,32 @@ async fn bundle_command(
     module_specifier.to_string()
   );
 
-  let output = global_state
-    .ts_compiler
-    .bundle(global_state.clone(), module_specifier)
-    .await?;
+  let graph =
+    info::build_module_graph(&global_state, &module_specifier).await?;
+  // Type check the program first, so that type errors are reported the same
+  // way as with `deno run`. With `--no-check` the bundler strips the types
+  // itself, so nothing is emitted to the cache.
+  if !global_state.flags.no_check {
+    global_state
+      .compile_module_graph(
+        &module_specifier,
+        &graph,
+        TargetLib::Main,
+        Permissions::allow_all(),
+      )
+      .await?;
+  }
+  let options = bundler::BundleOptions {
+    minify,
+    jsx: global_state.ts_compiler.config.jsx.clone(),
+  };
+  let output = bundler::bundle(
+    &graph,
+    &module_specifier,
+    &global_state.resolver,
+    &options,
+  )?;
+  let output = output.code_with_inline_source_map();
 
   debug!(">>>>> bundle END");
 
@@ -495,17 +585,28 @@ async fn doc_command(
   json: bool,
   maybe_filter: Option<String>,
 ) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags.clone())?;
+  let global_state = GlobalState::new(flags.clone()).await?;
   let source_file = source_file.unwrap_or_else(|| "--builtin".to_string());
 
-  impl DocFileLoader for SourceFileFetcher {
+  impl DocFileLoader for GlobalState {
+    fn resolve(
+      &self,
+      specifier: &str,
+      referrer: &str,
+    ) -> Result<ModuleSpecifier, OpError> {
+      self
+        .resolver
+        .resolve(specifier, referrer)
+        .map_err(OpError::from)
+    }
+
     fn load_source_code(
       &self,
       specifier: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, OpError>>>> {
       let specifier =
         ModuleSpecifier::resolve_url_or_path(specifier).expect("Bad specifier");
-      let fetcher = self.clone();
+      let fetcher = self.file_fetcher.clone();
 
       async move {
         let source_file = fetcher
@@ -518,7 +619,7 @@ async fn doc_command(
     }
   }
 
-  let loader = Box::new(global_state.file_fetcher.clone());
+  let loader = Box::new(global_state.clone());
   let doc_parser = doc::DocParser::new(loader);
 
   let parse_result = if source_file == "--builtin" {
@@ -566,7 +667,7 @@ async fn doc_command(
 async fn run_repl(flags: Flags) -> Result<(), ErrBox> {
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./__$deno$repl.ts").unwrap();
-  let global_state = GlobalState::new(flags)?;
+  let global_state = GlobalState::new(flags).await?;
   let mut worker = MainWorker::create(global_state, main_module)?;
   loop {
     (&mut *worker).await?;
@@ -574,7 +675,7 @@ async fn run_repl(flags: Flags) -> Result<(), ErrBox> {
 }
 
 async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags.clone())?;
+  let global_state = GlobalState::new(flags.clone()).await?;
   let main_module = if script != "-" {
     ModuleSpecifier::resolve_url_or_path(&script).unwrap()
   } else {
@@ -590,6 +691,7 @@ async fn run_command(flags: Flags, script: String) -> Result<(), ErrBox> {
     let source_file = SourceFile {
       filename: main_module_url.to_file_path().unwrap(),
       url: main_module_url,
+      redirects: vec![],
       types_header: None,
       media_type: MediaType::TypeScript,
       source_code: source,
@@ -621,7 +723,7 @@ async fn test_command(
   allow_none: bool,
   filter: Option<String>,
 ) -> Result<(), ErrBox> {
-  let global_state = GlobalState::new(flags.clone())?;
+  let global_state = GlobalState::new(flags.clone()).await?;
   let cwd = std::env::current_dir().expect("No current directory");
   let include = include.unwrap_or_else(|| vec![".".to_string()]);
   let test_modules = test_runner::prepare_test_modules_urls(include, &cwd)?;
@@ -647,6 +749,7 @@ async fn test_command(
   let source_file = SourceFile {
     filename: test_file_url.to_file_path().unwrap(),
     url: test_file_url,
+    redirects: vec![],
     types_header: None,
     media_type: MediaType::TypeScript,
     source_code: test_file.clone().into_bytes(),
@@ -690,7 +793,8 @@ pub fn main() {
     DenoSubcommand::Bundle {
       source_file,
       out_file,
-    } => bundle_command(flags, source_file, out_file).boxed_local(),
+      minify,
+    } => bundle_command(flags, source_file, out_file, minify).boxed_local(),
     DenoSubcommand::Doc {
       source_file,
       json,
@@ -704,10 +808,20 @@ pub fn main() {
     DenoSubcommand::Cache { files } => {
       cache_command(flags, files).boxed_local()
     }
+    DenoSubcommand::CacheClean {
+      max_size,
+      max_age,
+      dry_run,
+    } => cache_clean_command(flags, max_size, max_age, dry_run).boxed_local(),
     DenoSubcommand::Fmt { check, files } => {
       fmt::format(files, check).boxed_local()
     }
-    DenoSubcommand::Info { file } => info_command(flags, file).boxed_local(),
+    DenoSubcommand::Info {
+      file,
+      json,
+      dot,
+      why,
+    } => info_command(flags, file, json, dot, why).boxed_local(),
     DenoSubcommand::Install {
       module_url,
       args,
@@ -749,7 +863,7 @@ pub fn main() {
       force,
       dry_run,
       version,
-    } => upgrade_command(dry_run, force, version).boxed_local(),
+    } => upgrade_command(flags, dry_run, force, version).boxed_local(),
     _ => unreachable!(),
   };
 
@@ -4,10 +4,10 @@ use crate::doc::Location;
 use crate::file_fetcher::map_file_extension;
 use crate::file_fetcher::SourceFile;
 use crate::file_fetcher::SourceFileFetcher;
-use crate::import_map::ImportMap;
 use crate::msg::MediaType;
 use crate::op_error::OpError;
 use crate::permissions::Permissions;
+use crate::resolver::Resolver;
 use crate::tsc::pre_process_file;
 use crate::tsc::ImportDesc;
 use crate::tsc::TsReferenceDesc;
@@ -26,6 +26,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::pin::Pin;
+use url::Url;
 
 // TODO(bartlomieju): it'd be great if this function returned
 // more structured data and possibly format the same as TS diagnostics.
@@ -95,7 +96,7 @@ fn validate_no_file_from_remote(
 // https://github.com/denoland/deno/issues/6133
 fn resolve_imports_and_references(
   referrer: ModuleSpecifier,
-  maybe_import_map: Option<&ImportMap>,
+  resolver: &Resolver,
   import_descs: Vec<ImportDesc>,
   ref_descs: Vec<TsReferenceDesc>,
 ) -> Result<(Vec<ImportDescriptor>, Vec<ReferenceDescriptor>), ErrBox> {
@@ -103,27 +104,12 @@ fn resolve_imports_and_references(
   let mut references = vec![];
 
   for import_desc in import_descs {
-    let maybe_resolved = if let Some(import_map) = maybe_import_map.as_ref() {
-      import_map.resolve(&import_desc.specifier, &referrer.to_string())?
-    } else {
-      None
-    };
-
-    let resolved_specifier = if let Some(resolved) = maybe_resolved {
-      resolved
-    } else {
-      ModuleSpecifier::resolve_import(
-        &import_desc.specifier,
-        &referrer.to_string(),
-      )?
-    };
+    let resolved_specifier =
+      resolver.resolve(&import_desc.specifier, &referrer.to_string())?;
 
     let resolved_type_directive =
       if let Some(types_specifier) = import_desc.deno_types.as_ref() {
-        Some(ModuleSpecifier::resolve_import(
-          &types_specifier,
-          &referrer.to_string(),
-        )?)
+        Some(resolver.resolve(&types_specifier, &referrer.to_string())?)
       } else {
         None
       };
@@ -227,7 +213,9 @@ pub struct ReferenceDescriptor {
 pub struct ModuleGraphFile {
   pub specifier: String,
   pub url: String,
-  pub redirect: Option<String>,
+  /// Every hop of the redirect of `specifier`, ending with the URL of the
+  /// module it leads to. Empty unless `specifier` was redirected.
+  pub redirect: Vec<String>,
   pub filename: String,
   pub version_hash: String,
   pub imports: Vec<ImportDescriptor>,
@@ -239,15 +227,23 @@ pub struct ModuleGraphFile {
   pub source_code: String,
 }
 
-type SourceFileFuture =
-  Pin<Box<dyn Future<Output = Result<(ModuleSpecifier, SourceFile), ErrBox>>>>;
+type SourceFileFuture = Pin<
+  Box<
+    dyn Future<
+      Output = Result<(ModuleSpecifier, SourceFile), (ModuleSpecifier, ErrBox)>,
+    >,
+  >,
+>;
 
 pub struct ModuleGraphLoader {
   permissions: Permissions,
   file_fetcher: SourceFileFetcher,
-  maybe_import_map: Option<ImportMap>,
+  resolver: Resolver,
   pending_downloads: FuturesUnordered<SourceFileFuture>,
   has_downloaded: HashSet<ModuleSpecifier>,
+  /// The module that first imported a module, to describe how a module that
+  /// failed to load was reached.
+  referrers: HashMap<ModuleSpecifier, ModuleSpecifier>,
   graph: ModuleGraph,
   is_dyn_import: bool,
   analyze_dynamic_imports: bool,
@@ -256,7 +252,7 @@ pub struct ModuleGraphLoader {
 impl ModuleGraphLoader {
   pub fn new(
     file_fetcher: SourceFileFetcher,
-    maybe_import_map: Option<ImportMap>,
+    resolver: Resolver,
     permissions: Permissions,
     is_dyn_import: bool,
     analyze_dynamic_imports: bool,
@@ -264,9 +260,10 @@ impl ModuleGraphLoader {
     Self {
       file_fetcher,
       permissions,
-      maybe_import_map,
+      resolver,
       pending_downloads: FuturesUnordered::new(),
       has_downloaded: HashSet::new(),
+      referrers: HashMap::new(),
       graph: ModuleGraph::new(),
       is_dyn_import,
       analyze_dynamic_imports,
@@ -277,6 +274,8 @@ impl ModuleGraphLoader {
   /// dependencies to the graph.
   ///
   /// It resolves when all dependent modules have been fetched and analyzed.
+  /// A module that fails to load doesn't stop the others, all failures are
+  /// reported together with the chain of imports that reached them.
   ///
   /// This method can be called multiple times.
   pub async fn add_to_graph(
@@ -286,16 +285,84 @@ impl ModuleGraphLoader {
   ) -> Result<(), ErrBox> {
     self.download_module(specifier.clone(), maybe_referrer, None)?;
 
-    loop {
-      let (specifier, source_file) =
-        self.pending_downloads.next().await.unwrap()?;
-      self.visit_module(&specifier, source_file)?;
-      if self.pending_downloads.is_empty() {
+    let mut failures = vec![];
+    while let Some(result) = self.pending_downloads.next().await {
+      match result {
+        // The other downloads are still awaited when a module can't be
+        // analyzed, so that none are left pending.
+        Ok((specifier, source_file)) => {
+          if let Err(err) = self.visit_module(&specifier, source_file) {
+            failures.push((specifier, err));
+          }
+        }
+        Err(failure) => failures.push(failure),
+      }
+    }
+
+    if failures.len() <= 1 {
+      return match failures.pop() {
+        Some((specifier, err)) => {
+          Err(self.err_with_import_chain(&specifier, err))
+        }
+        None => Ok(()),
+      };
+    }
+
+    failures.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+    let errors: Vec<OpError> = failures
+      .into_iter()
+      .map(|(specifier, err)| {
+        OpError::from(self.err_with_import_chain(&specifier, err))
+      })
+      .collect();
+    let messages: Vec<String> =
+      errors.iter().map(|e| e.msg.replace("\n", "\n  ")).collect();
+    Err(
+      OpError {
+        kind: errors[0].kind,
+        msg: format!(
+          "Failed to load {} modules:\n  {}",
+          errors.len(),
+          messages.join("\n\n  ")
+        ),
+      }
+      .into(),
+    )
+  }
+
+  /// Returns the modules that lead from the root to `specifier`, following
+  /// the first import of each module.
+  fn import_chain(&self, specifier: &ModuleSpecifier) -> Vec<String> {
+    let mut chain = vec![specifier.to_string()];
+    let mut current = specifier;
+    while let Some(referrer) = self.referrers.get(current) {
+      if chain.contains(&referrer.to_string()) {
         break;
       }
+      chain.push(referrer.to_string());
+      current = referrer;
     }
+    chain.reverse();
+    chain
+  }
 
-    Ok(())
+  /// Adds the import chain to `err` when the module wasn't imported by the
+  /// root directly, in which case the import location says it all.
+  fn err_with_import_chain(
+    &self,
+    specifier: &ModuleSpecifier,
+    err: ErrBox,
+  ) -> ErrBox {
+    let chain = self.import_chain(specifier);
+    if chain.len() <= 2 {
+      return err;
+    }
+    let err = OpError::from(err);
+    OpError {
+      kind: err.kind,
+      msg: format!("{}\nImport chain: {}", err.msg, chain.join(" -> ")),
+    }
+    .into()
   }
 
   /// This method is used to create a graph from in-memory files stored in
@@ -346,7 +413,7 @@ impl ModuleGraphLoader {
     )?;
     let (imports, references) = resolve_imports_and_references(
       module_specifier.clone(),
-      self.maybe_import_map.as_ref(),
+      &self.resolver,
       raw_imports,
       raw_references,
     )?;
@@ -370,7 +437,7 @@ impl ModuleGraphLoader {
       ModuleGraphFile {
         specifier: specifier.to_string(),
         url: specifier.to_string(),
-        redirect: None,
+        redirect: vec![],
         version_hash: "".to_string(),
         media_type: map_file_extension(&PathBuf::from(specifier.clone())),
         filename: specifier,
@@ -412,6 +479,11 @@ impl ModuleGraphLoader {
     }
 
     self.has_downloaded.insert(module_specifier.clone());
+    if let Some(referrer) = &maybe_referrer {
+      self
+        .referrers
+        .insert(module_specifier.clone(), referrer.clone());
+    }
     let spec = module_specifier;
     let file_fetcher = self.file_fetcher.clone();
     let perms = self.permissions.clone();
@@ -421,7 +493,9 @@ impl ModuleGraphLoader {
       let source_file = file_fetcher
         .fetch_source_file(&spec_, maybe_referrer, perms)
         .await
-        .map_err(|e| err_with_location(e, maybe_location.as_ref()))?;
+        .map_err(|e| {
+          (spec_.clone(), err_with_location(e, maybe_location.as_ref()))
+        })?;
 
       Ok((spec_.clone(), source_file))
     }
@@ -445,17 +519,25 @@ impl ModuleGraphLoader {
     // IMPORTANT: source_file.url might be different than requested
     // module_specifier because of HTTP redirects. In such
     // situation we add an "empty" ModuleGraphFile with 'redirect'
-    // field set that will be later used in TS worker when building
-    // map of available source file. It will perform substitution
-    // for proper URL point to redirect target.
-    if module_specifier.as_url() != &source_file.url {
+    // field set for the specifier and every intermediate hop, that will be
+    // later used in TS worker when building map of available source file.
+    // It will perform substitution for proper URL point to redirect target.
+    let mut hops: Vec<String> =
+      source_file.redirects.iter().map(Url::to_string).collect();
+    if module_specifier.as_url() != &source_file.url
+      && hops.first() != Some(&module_specifier.to_string())
+    {
+      hops.insert(0, module_specifier.to_string());
+    }
+    hops.push(source_file.url.to_string());
+    for (i, hop) in hops.iter().enumerate().take(hops.len() - 1) {
       // TODO(bartlomieju): refactor, this is a band-aid
       self.graph.insert(
-        module_specifier.to_string(),
+        hop.to_string(),
         ModuleGraphFile {
-          specifier: module_specifier.to_string(),
-          url: module_specifier.to_string(),
-          redirect: Some(source_file.url.to_string()),
+          specifier: hop.to_string(),
+          url: hop.to_string(),
+          redirect: hops[i + 1..].to_vec(),
           filename: source_file.filename.to_str().unwrap().to_string(),
           version_hash: checksum::gen(&[
             &source_file.source_code,
@@ -510,7 +592,7 @@ impl ModuleGraphLoader {
       )?;
       let (imports_, references) = resolve_imports_and_references(
         module_specifier.clone(),
-        self.maybe_import_map.as_ref(),
+        &self.resolver,
         raw_imports,
         raw_refs,
       )?;
@@ -561,7 +643,7 @@ impl ModuleGraphLoader {
       ModuleGraphFile {
         specifier: module_specifier.to_string(),
         url: module_specifier.to_string(),
-        redirect: None,
+        redirect: vec![],
         version_hash,
         filename: source_file.filename.to_str().unwrap().to_string(),
         media_type: source_file.media_type,
@@ -585,10 +667,10 @@ mod tests {
   async fn build_graph(
     module_specifier: &ModuleSpecifier,
   ) -> Result<ModuleGraph, ErrBox> {
-    let global_state = GlobalState::new(Default::default()).unwrap();
+    let global_state = GlobalState::new(Default::default()).await.unwrap();
     let mut graph_loader = ModuleGraphLoader::new(
       global_state.file_fetcher.clone(),
-      None,
+      global_state.resolver.clone(),
       Permissions::allow_all(),
       false,
       false,
@@ -861,6 +943,38 @@ mod tests {
 
     drop(http_server_guard);
   }
+
+  #[tokio::test]
+  async fn source_graph_redirect_chain() {
+    let http_server_guard = test_util::http_server();
+
+    // 4548 redirects to 4546, which redirects to 4545.
+    let module_specifier = ModuleSpecifier::resolve_url_or_path(
+      "http://localhost:4548/cli/tests/subdir/redirects/redirect1.js",
+    )
+    .unwrap();
+    let graph = build_graph(&module_specifier)
+      .await
+      .expect("Failed to build graph");
+
+    let target =
+      "http://localhost:4545/cli/tests/subdir/redirects/redirect1.js";
+    assert_eq!(
+      graph[module_specifier.as_str()].redirect,
+      vec![
+        "http://localhost:4546/cli/tests/subdir/redirects/redirect1.js",
+        target
+      ]
+    );
+    assert_eq!(
+      graph["http://localhost:4546/cli/tests/subdir/redirects/redirect1.js"]
+        .redirect,
+      vec![target]
+    );
+    assert!(graph[target].redirect.is_empty());
+
+    drop(http_server_guard);
+  }
 }
 
 // TODO(bartlomieju): use baseline tests from TSC to ensure
@@ -22,6 +22,7 @@ use std::env::VarError;
 use std::error::Error;
 use std::fmt;
 use std::io;
+use tokio_tungstenite::tungstenite;
 
 // Warning! The values in this enum are duplicated in js/errors.ts
 // Update carefully!
@@ -381,6 +382,30 @@ impl From<&notify::Error> for OpError {
   }
 }
 
+impl From<tungstenite::Error> for OpError {
+  fn from(error: tungstenite::Error) -> Self {
+    OpError::from(&error)
+  }
+}
+
+impl From<&tungstenite::Error> for OpError {
+  fn from(error: &tungstenite::Error) -> Self {
+    use tungstenite::Error::*;
+    let kind = match error {
+      Io(e) => return e.into(),
+      ConnectionClosed | AlreadyClosed => ErrorKind::ConnectionReset,
+      Url(_) => ErrorKind::TypeError,
+      Http(_) | HttpFormat(_) => ErrorKind::Http,
+      _ => ErrorKind::Other,
+    };
+
+    Self {
+      kind,
+      msg: error.to_string(),
+    }
+  }
+}
+
 impl From<ErrBox> for OpError {
   fn from(error: ErrBox) -> Self {
     #[cfg(unix)]
@@ -417,6 +442,7 @@ impl From<ErrBox> for OpError {
       })
       .or_else(|| error.downcast_ref::<dlopen::Error>().map(|e| e.into()))
       .or_else(|| error.downcast_ref::<notify::Error>().map(|e| e.into()))
+      .or_else(|| error.downcast_ref::<tungstenite::Error>().map(|e| e.into()))
       .or_else(|| unix_error_kind(&error))
       .unwrap_or_else(|| {
         panic!("Can't downcast {:?} to OpError", error);
@@ -0,0 +1,198 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::CoreIsolate;
+use deno_core::CoreIsolateState;
+use deno_core::ZeroCopyBuf;
+use futures::future::FutureExt;
+use std::net::IpAddr;
+use trust_dns_resolver::config::NameServerConfigGroup;
+use trust_dns_resolver::config::ResolverConfig;
+use trust_dns_resolver::config::ResolverOpts;
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::lookup::Lookup;
+use trust_dns_resolver::proto::rr::RData;
+use trust_dns_resolver::proto::rr::RecordType;
+use trust_dns_resolver::proto::xfer::DnsRequestOptions;
+use trust_dns_resolver::system_conf;
+use trust_dns_resolver::TokioAsyncResolver;
+
+pub fn init(i: &mut CoreIsolate, s: &State) {
+  i.register_op("op_resolve_dns", s.stateful_json_op2(op_resolve_dns));
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NameServerArgs {
+  ip_addr: String,
+  #[serde(default = "default_dns_port")]
+  port: u16,
+}
+
+fn default_dns_port() -> u16 {
+  53
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveDnsArgs {
+  query: String,
+  record_type: String,
+  name_server: Option<NameServerArgs>,
+}
+
+fn parse_record_type(record_type: &str) -> Result<RecordType, OpError> {
+  match record_type {
+    "A" => Ok(RecordType::A),
+    "AAAA" => Ok(RecordType::AAAA),
+    "CNAME" => Ok(RecordType::CNAME),
+    "MX" => Ok(RecordType::MX),
+    "SRV" => Ok(RecordType::SRV),
+    "TXT" => Ok(RecordType::TXT),
+    _ => Err(OpError::type_error(format!(
+      "Unsupported record type: {}",
+      record_type
+    ))),
+  }
+}
+
+/// Resolves DNS records of `query`, either with the nameservers of the
+/// system or with the one passed in `nameServer`. The nameservers that are
+/// contacted must be allowed by `--allow-net`.
+fn op_resolve_dns(
+  _isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.resolveDns");
+  let args: ResolveDnsArgs = serde_json::from_value(args)?;
+  let record_type = parse_record_type(&args.record_type)?;
+
+  let (config, opts) = match &args.name_server {
+    Some(name_server) => {
+      let ip: IpAddr = name_server.ip_addr.parse().map_err(|_| {
+        OpError::type_error(format!(
+          "Invalid nameserver address: {}",
+          name_server.ip_addr
+        ))
+      })?;
+      let group =
+        NameServerConfigGroup::from_ips_clear(&[ip], name_server.port);
+      (
+        ResolverConfig::from_parts(None, vec![], group),
+        ResolverOpts::default(),
+      )
+    }
+    None => system_conf::read_system_conf()?,
+  };
+
+  for name_server in config.name_servers() {
+    let addr = name_server.socket_addr;
+    state.check_net(&addr.ip().to_string(), addr.port())?;
+  }
+
+  let op = async move {
+    let resolver = TokioAsyncResolver::tokio(config, opts)
+      .await
+      .map_err(resolve_error)?;
+    let lookup = resolver
+      .lookup(args.query, record_type, DnsRequestOptions::default())
+      .await
+      .map_err(resolve_error)?;
+    Ok(json!(format_lookup(&lookup, record_type)))
+  };
+
+  Ok(JsonOp::Async(op.boxed_local()))
+}
+
+fn resolve_error(err: ResolveError) -> OpError {
+  match err.kind() {
+    ResolveErrorKind::NoRecordsFound { .. } => {
+      OpError::not_found(format!("No records found: {}", err))
+    }
+    ResolveErrorKind::Io(e) => OpError::from(e),
+    _ => OpError::other(err.to_string()),
+  }
+}
+
+/// Returns the records of `lookup` that have the requested type. A lookup
+/// of an aliased name also returns the CNAME records that were followed.
+fn format_lookup(lookup: &Lookup, record_type: RecordType) -> Vec<Value> {
+  lookup
+    .iter()
+    .filter(|rdata| rdata.to_record_type() == record_type)
+    .filter_map(format_rdata)
+    .collect()
+}
+
+fn format_rdata(rdata: &RData) -> Option<Value> {
+  let value = match rdata {
+    RData::A(ip) => json!(ip.to_string()),
+    RData::AAAA(ip) => json!(ip.to_string()),
+    RData::CNAME(name) => json!(name.to_string()),
+    RData::MX(mx) => json!({
+      "preference": mx.preference(),
+      "exchange": mx.exchange().to_string(),
+    }),
+    RData::SRV(srv) => json!({
+      "priority": srv.priority(),
+      "weight": srv.weight(),
+      "port": srv.port(),
+      "target": srv.target().to_string(),
+    }),
+    RData::TXT(txt) => json!(txt
+      .txt_data()
+      .iter()
+      .map(|data| String::from_utf8_lossy(data).into_owned())
+      .collect::<Vec<_>>()),
+    _ => return None,
+  };
+  Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use trust_dns_resolver::proto::rr::rdata::{MX, SRV, TXT};
+  use trust_dns_resolver::proto::rr::Name;
+
+  #[test]
+  fn record_types() {
+    assert_eq!(parse_record_type("AAAA").unwrap(), RecordType::AAAA);
+    assert_eq!(parse_record_type("SRV").unwrap(), RecordType::SRV);
+    assert!(parse_record_type("aaaa").is_err());
+    assert!(parse_record_type("NS").is_err());
+  }
+
+  #[test]
+  fn format_records() {
+    let name = Name::from_ascii("mail.example.com.").unwrap();
+    assert_eq!(
+      format_rdata(&RData::A("127.0.0.1".parse().unwrap())),
+      Some(json!("127.0.0.1"))
+    );
+    assert_eq!(
+      format_rdata(&RData::MX(MX::new(10, name.clone()))),
+      Some(json!({ "preference": 10, "exchange": "mail.example.com." }))
+    );
+    assert_eq!(
+      format_rdata(&RData::SRV(SRV::new(0, 5, 5060, name))),
+      Some(json!({
+        "priority": 0,
+        "weight": 5,
+        "port": 5060,
+        "target": "mail.example.com.",
+      }))
+    );
+    assert_eq!(
+      format_rdata(&RData::TXT(TXT::new(vec![
+        "v=spf1".to_string(),
+        "-all".to_string()
+      ]))),
+      Some(json!(["v=spf1", "-all"]))
+    );
+  }
+}
@@ -1,27 +1,55 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use super::io::{StreamResource, StreamResourceHolder};
-use crate::http_util::{create_http_client, HttpBody};
+use crate::http_util::HttpClientOptions;
+use crate::http_util::{create_http_client_with_options, HttpBody};
 use crate::op_error::OpError;
 use crate::state::State;
+use bytes::Bytes;
 use deno_core::CoreIsolate;
 use deno_core::CoreIsolateState;
 use deno_core::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::future::poll_fn;
 use futures::future::FutureExt;
+use futures::ready;
 use http::header::HeaderName;
 use http::header::HeaderValue;
 use http::Method;
+use reqwest::Response;
 use std::convert::From;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
 
 pub fn init(i: &mut CoreIsolate, s: &State) {
   i.register_op("op_fetch", s.stateful_json_op2(op_fetch));
+  i.register_op("op_fetch_send", s.stateful_json_op2(op_fetch_send));
+  i.register_op(
+    "op_fetch_request_write",
+    s.stateful_json_op2(op_fetch_request_write),
+  );
+  i.register_op("op_fetch_cancel", s.stateful_json_op2(op_fetch_cancel));
+  i.register_op(
+    "op_create_http_client",
+    s.stateful_json_op2(op_create_http_client),
+  );
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct FetchArgs {
   method: Option<String>,
   url: String,
   headers: Vec<(String, String)>,
+  client_rid: Option<u32>,
+  /// The body is streamed with `op_fetch_request_write` instead of being
+  /// passed as a buffer.
+  #[serde(default)]
+  stream_body: bool,
 }
 
 pub fn op_fetch(
@@ -33,8 +61,16 @@ pub fn op_fetch(
   let args: FetchArgs = serde_json::from_value(args)?;
   let url = args.url;
 
-  let client =
-    create_http_client(state.borrow().global_state.flags.ca_file.clone())?;
+  let client = match args.client_rid {
+    Some(rid) => {
+      let resource_table = isolate_state.resource_table.borrow();
+      let resource = resource_table
+        .get::<HttpClientResource>(rid)
+        .ok_or_else(OpError::bad_resource_id)?;
+      resource.client.clone()
+    }
+    None => state.http_client()?,
+  };
 
   let method = match args.method {
     Some(method_str) => Method::from_bytes(method_str.as_bytes())
@@ -57,23 +93,113 @@ pub fn op_fetch(
 
   let mut request = client.request(method, url_);
 
+  for (key, value) in args.headers {
+    let name = HeaderName::from_bytes(key.as_bytes())
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    let v = HeaderValue::from_str(&value)
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    request = request.header(name, v);
+  }
+
+  let mut request_body_rid = None;
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
   match data.len() {
+    0 if args.stream_body => {
+      let (sender, receiver) = mpsc::channel::<Result<Bytes, io::Error>>(1);
+      request = request.body(reqwest::Body::wrap_stream(receiver));
+      request_body_rid = Some(resource_table.add(
+        "fetchRequestBody",
+        Box::new(FetchRequestBodyResource { sender }),
+      ));
+    }
     0 => {}
     1 => request = request.body(Vec::from(&*data[0])),
-    _ => panic!("Invalid number of arguments"),
+    _ => {
+      return Err(OpError::type_error(
+        "Invalid number of arguments".to_string(),
+      ))
+    }
   }
+  debug!("Before fetch {}", url);
 
-  for (key, value) in args.headers {
-    let name = HeaderName::from_bytes(key.as_bytes()).unwrap();
-    let v = HeaderValue::from_str(&value).unwrap();
-    request = request.header(name, v);
+  let request_rid = resource_table.add(
+    "fetchRequest",
+    Box::new(FetchRequestResource {
+      future: request.send().boxed(),
+      request_body_rid,
+      waker: None,
+    }),
+  );
+
+  Ok(JsonOp::Sync(json!({
+    "requestRid": request_rid,
+    "requestBodyRid": request_body_rid,
+  })))
+}
+
+/// A request created by `op_fetch` whose response hasn't arrived yet.
+/// Removing it from the resource table aborts the request.
+struct FetchRequestResource {
+  future: Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send>>,
+  request_body_rid: Option<u32>,
+  /// Waker of the pending `op_fetch_send`, woken when the request is
+  /// cancelled.
+  waker: Option<Waker>,
+}
+
+impl Drop for FetchRequestResource {
+  fn drop(&mut self) {
+    if let Some(waker) = self.waker.take() {
+      waker.wake();
+    }
   }
-  debug!("Before fetch {}", url);
+}
+
+/// The writable end of a streamed request body. Closing the resource ends
+/// the body.
+struct FetchRequestBodyResource {
+  sender: mpsc::Sender<Result<Bytes, io::Error>>,
+}
+
+#[derive(Deserialize)]
+struct FetchRidArgs {
+  rid: u32,
+}
+
+/// Waits for the response of a request created by `op_fetch`.
+fn op_fetch_send(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _data: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: FetchRidArgs = serde_json::from_value(args)?;
+  let rid = args.rid;
 
   let resource_table = isolate_state.resource_table.clone();
-  let future = async move {
-    let res = request.send().await?;
-    debug!("Fetch response {}", url);
+  let future = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let resource = match resource_table.get_mut::<FetchRequestResource>(rid) {
+      Some(resource) => resource,
+      None => {
+        return Poll::Ready(Err(OpError::from(io::Error::new(
+          io::ErrorKind::Interrupted,
+          "Request was aborted",
+        ))))
+      }
+    };
+    let result = match resource.future.as_mut().poll(cx) {
+      Poll::Ready(result) => result,
+      Poll::Pending => {
+        resource.waker = Some(cx.waker().clone());
+        return Poll::Pending;
+      }
+    };
+    resource.waker = None;
+    resource_table.close(rid);
+    let res = result?;
+
+    debug!("Fetch response {}", res.url());
     let status = res.status();
     let mut res_headers = Vec::new();
     for (key, val) in res.headers().iter() {
@@ -81,7 +207,6 @@ pub fn op_fetch(
     }
 
     let body = HttpBody::from(res);
-    let mut resource_table = resource_table.borrow_mut();
     let rid = resource_table.add(
       "httpBody",
       Box::new(StreamResourceHolder::new(StreamResource::HttpBody(
@@ -89,15 +214,133 @@ pub fn op_fetch(
       ))),
     );
 
-    let json_res = json!({
+    Poll::Ready(Ok(json!({
       "bodyRid": rid,
       "status": status.as_u16(),
       "statusText": status.canonical_reason().unwrap_or(""),
       "headers": res_headers
-    });
+    })))
+  });
 
-    Ok(json_res)
-  };
+  Ok(JsonOp::Async(future.boxed_local()))
+}
+
+/// Writes one chunk of a streamed request body. Resolves once the request
+/// is ready for more data, which makes uploads subject to back pressure.
+fn op_fetch_request_write(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  data: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: FetchRidArgs = serde_json::from_value(args)?;
+  let rid = args.rid;
+  if data.len() != 1 {
+    return Err(OpError::type_error(
+      "Invalid number of arguments".to_string(),
+    ));
+  }
+  let mut chunk = Some(Bytes::from(Vec::from(&*data[0])));
+
+  let resource_table = isolate_state.resource_table.clone();
+  let future = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let resource = resource_table
+      .get_mut::<FetchRequestBodyResource>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    // The receiver is gone if the request failed or was aborted.
+    let closed = || {
+      OpError::from(io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "Request body stream is closed",
+      ))
+    };
+    ready!(resource.sender.poll_ready(cx)).map_err(|_| closed())?;
+    resource
+      .sender
+      .start_send(Ok(chunk.take().unwrap()))
+      .map_err(|_| closed())?;
+    Poll::Ready(Ok(json!({})))
+  });
 
   Ok(JsonOp::Async(future.boxed_local()))
 }
+
+/// Aborts a fetch. `rid` is either the request created by `op_fetch`, in
+/// which case the request and its streamed body are dropped, or the
+/// response body, in which case pending reads of it fail.
+fn op_fetch_cancel(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _data: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: FetchRidArgs = serde_json::from_value(args)?;
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  if resource_table
+    .get::<FetchRequestResource>(args.rid)
+    .is_some()
+  {
+    let request = resource_table
+      .remove::<FetchRequestResource>(args.rid)
+      .unwrap();
+    if let Some(request_body_rid) = request.request_body_rid {
+      resource_table.close(request_body_rid);
+    }
+  } else {
+    match resource_table.get::<StreamResourceHolder>(args.rid) {
+      Some(StreamResourceHolder {
+        resource: StreamResource::HttpBody(_),
+        ..
+      }) => {
+        resource_table.close(args.rid);
+      }
+      _ => return Err(OpError::bad_resource_id()),
+    }
+  }
+  Ok(JsonOp::Sync(json!({})))
+}
+
+struct HttpClientResource {
+  client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateHttpClientArgs {
+  ca_data: Option<String>,
+  proxy: Option<String>,
+  /// Timeout in milliseconds.
+  timeout: Option<u64>,
+  http2: Option<bool>,
+}
+
+fn op_create_http_client(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.createHttpClient");
+  let args: CreateHttpClientArgs = serde_json::from_value(args)?;
+
+  if let Some(proxy) = &args.proxy {
+    let proxy_url = url::Url::parse(proxy).map_err(OpError::from)?;
+    state.check_net_url(&proxy_url)?;
+  }
+
+  // `--cert`, `--proxy`, the proxy environment variables and
+  // `--unsafely-ignore-certificate-errors` also apply to custom clients.
+  let client = create_http_client_with_options(HttpClientOptions {
+    ca_data: args.ca_data,
+    proxy: args.proxy,
+    timeout: args.timeout.map(Duration::from_millis),
+    http2: args.http2,
+    ..state.borrow().global_state.http_client_options.clone()
+  })?;
+
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  let rid =
+    resource_table.add("httpClient", Box::new(HttpClientResource { client }));
+  Ok(JsonOp::Sync(json!(rid)))
+}
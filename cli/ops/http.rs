@@ -0,0 +1,464 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use super::io::{StreamResource, StreamResourceHolder};
+use crate::op_error::OpError;
+use crate::state::State;
+use bytes::Bytes;
+use deno_core::CoreIsolate;
+use deno_core::CoreIsolateState;
+use deno_core::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::channel::oneshot;
+use futures::future::poll_fn;
+use futures::future::Either;
+use futures::future::FutureExt;
+use futures::ready;
+use futures::stream::StreamExt;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use http::header::HOST;
+use http::request::Parts;
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use std::cmp::min;
+use std::convert::Infallible;
+use std::io;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::rustls::Session;
+
+pub fn init(i: &mut CoreIsolate, s: &State) {
+  i.register_op("op_http_start", s.stateful_json_op2(op_http_start));
+  i.register_op(
+    "op_http_request_next",
+    s.stateful_json_op2(op_http_request_next),
+  );
+  i.register_op("op_http_respond", s.stateful_json_op2(op_http_respond));
+}
+
+/// A request received by a connection, waiting to be passed to JS.
+struct RequestEvent {
+  request: Request<Body>,
+  response_tx: oneshot::Sender<Response<Body>>,
+}
+
+/// An HTTP connection served by hyper. Closing the resource gracefully shuts
+/// the connection down: responses that are in flight are completed, but no
+/// further requests are read.
+struct HttpConnResource {
+  receiver: mpsc::UnboundedReceiver<RequestEvent>,
+  scheme: &'static str,
+  _shutdown_tx: oneshot::Sender<()>,
+  /// Waker of a pending `op_http_request_next`, woken when the connection
+  /// is closed so that the op doesn't hang forever.
+  waker: Option<Waker>,
+}
+
+impl Drop for HttpConnResource {
+  fn drop(&mut self) {
+    if let Some(waker) = self.waker.take() {
+      waker.wake();
+    }
+  }
+}
+
+/// A request that hasn't been responded to yet.
+struct HttpRequestResource {
+  response_tx: oneshot::Sender<Response<Body>>,
+}
+
+/// Body of a request received by the HTTP server.
+pub struct HttpRequestBody {
+  body: Body,
+  chunk: Option<Bytes>,
+}
+
+impl AsyncRead for HttpRequestBody {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &mut [u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let inner = self.get_mut();
+    loop {
+      if let Some(chunk) = inner.chunk.as_mut() {
+        let n = min(buf.len(), chunk.len());
+        buf[..n].copy_from_slice(&chunk.split_to(n));
+        if chunk.is_empty() {
+          inner.chunk = None;
+        }
+        return Poll::Ready(Ok(n));
+      }
+      match ready!(Pin::new(&mut inner.body).poll_data(cx)) {
+        Some(Ok(chunk)) => {
+          if !chunk.is_empty() {
+            inner.chunk = Some(chunk);
+          }
+        }
+        Some(Err(e)) => {
+          return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e)))
+        }
+        None => return Poll::Ready(Ok(0)),
+      }
+    }
+  }
+}
+
+/// Body of a response sent by the HTTP server, streamed chunk by chunk.
+/// Closing the resource ends the body.
+pub struct HttpResponseBody {
+  sender: hyper::body::Sender,
+}
+
+impl AsyncWrite for HttpResponseBody {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &[u8],
+  ) -> Poll<Result<usize, io::Error>> {
+    let inner = self.get_mut();
+    let closed =
+      || io::Error::new(io::ErrorKind::BrokenPipe, "Response body is closed");
+    ready!(inner.sender.poll_ready(cx)).map_err(|_| closed())?;
+    inner
+      .sender
+      .try_send_data(Bytes::copy_from_slice(buf))
+      .map_err(|_| closed())?;
+    Poll::Ready(Ok(buf.len()))
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_shutdown(
+    self: Pin<&mut Self>,
+    _cx: &mut Context,
+  ) -> Poll<Result<(), io::Error>> {
+    Poll::Ready(Ok(()))
+  }
+}
+
+/// Serves HTTP/1.1 (with keep-alive and chunked encoding) or HTTP/2 on
+/// `stream`. The connection is driven by a task of its own, so that it makes
+/// progress independently of the ops polled by JS. Received requests are sent
+/// through the returned channel.
+fn serve_connection<S>(
+  stream: S,
+  http2_only: bool,
+) -> (mpsc::UnboundedReceiver<RequestEvent>, oneshot::Sender<()>)
+where
+  S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+  let (sender, receiver) = mpsc::unbounded::<RequestEvent>();
+  let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+  let service = service_fn(move |request: Request<Body>| {
+    let sender = sender.clone();
+    async move {
+      let (response_tx, response_rx) = oneshot::channel();
+      // Fails if the connection resource has been closed, in which case
+      // `response_tx` is dropped right away.
+      let _ = sender.unbounded_send(RequestEvent {
+        request,
+        response_tx,
+      });
+      let response = response_rx.await.unwrap_or_else(|_| {
+        Response::builder()
+          .status(StatusCode::SERVICE_UNAVAILABLE)
+          .body(Body::empty())
+          .unwrap()
+      });
+      Ok::<_, Infallible>(response)
+    }
+  });
+
+  let mut http = Http::new();
+  http.http2_only(http2_only);
+  let conn = http.serve_connection(stream, service);
+  tokio::spawn(async move {
+    futures::pin_mut!(conn);
+    let result = match futures::future::select(conn, shutdown_rx).await {
+      Either::Left((result, _)) => result,
+      Either::Right((_, mut conn)) => {
+        conn.as_mut().graceful_shutdown();
+        conn.await
+      }
+    };
+    if let Err(err) = result {
+      debug!("HTTP connection error: {}", err);
+    }
+  });
+
+  (receiver, shutdown_tx)
+}
+
+#[derive(Deserialize)]
+struct HttpRidArgs {
+  rid: u32,
+}
+
+/// Takes over a connection accepted from a `TcpListener` or a
+/// `TlsListenerResource` and starts serving HTTP on it.
+fn op_http_start(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.serveHttp");
+  let args: HttpRidArgs = serde_json::from_value(args)?;
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  let resource_holder = resource_table
+    .get_mut::<StreamResourceHolder>(args.rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  let is_connection = match resource_holder.resource {
+    StreamResource::TcpStream(Some(_)) => true,
+    StreamResource::ServerTlsStream(_) => true,
+    _ => false,
+  };
+  if !is_connection {
+    return Err(OpError::bad_resource_id());
+  }
+  let resource = std::mem::replace(
+    &mut resource_holder.resource,
+    StreamResource::TcpStream(None),
+  );
+  resource_table.close(args.rid);
+
+  let (receiver, shutdown_tx, scheme) = match resource {
+    StreamResource::TcpStream(Some(tcp_stream)) => {
+      let (receiver, shutdown_tx) = serve_connection(tcp_stream, false);
+      (receiver, shutdown_tx, "http")
+    }
+    StreamResource::ServerTlsStream(tls_stream) => {
+      let http2_only =
+        tls_stream.get_ref().1.get_alpn_protocol() == Some(&b"h2"[..]);
+      let (receiver, shutdown_tx) = serve_connection(*tls_stream, http2_only);
+      (receiver, shutdown_tx, "https")
+    }
+    _ => unreachable!(),
+  };
+
+  let rid = resource_table.add(
+    "httpConn",
+    Box::new(HttpConnResource {
+      receiver,
+      scheme,
+      _shutdown_tx: shutdown_tx,
+      waker: None,
+    }),
+  );
+  Ok(JsonOp::Sync(json!(rid)))
+}
+
+/// Returns the absolute URL of a request. HTTP/1.1 requests usually only
+/// carry a path, in which case the authority is taken from the `Host` header.
+fn request_url(scheme: &str, parts: &Parts) -> String {
+  if parts.uri.scheme().is_some() {
+    return parts.uri.to_string();
+  }
+  let host = parts
+    .uri
+    .authority()
+    .map(|authority| authority.as_str())
+    .or_else(|| parts.headers.get(HOST).and_then(|h| h.to_str().ok()))
+    .unwrap_or("localhost");
+  let path = parts
+    .uri
+    .path_and_query()
+    .map(|path| path.as_str())
+    .unwrap_or("/");
+  format!("{}://{}{}", scheme, host, path)
+}
+
+/// Resolves with the next request of a connection, or with `null` once the
+/// connection is closed.
+fn op_http_request_next(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: HttpRidArgs = serde_json::from_value(args)?;
+  let rid = args.rid;
+  let resource_table = isolate_state.resource_table.clone();
+
+  let f = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let conn = match resource_table.get_mut::<HttpConnResource>(rid) {
+      Some(conn) => conn,
+      None => return Poll::Ready(Ok(json!(null))),
+    };
+    let event = match conn.receiver.poll_next_unpin(cx) {
+      Poll::Ready(Some(event)) => event,
+      Poll::Ready(None) => return Poll::Ready(Ok(json!(null))),
+      Poll::Pending => {
+        conn.waker = Some(cx.waker().clone());
+        return Poll::Pending;
+      }
+    };
+    conn.waker = None;
+    let scheme = conn.scheme;
+
+    let (parts, body) = event.request.into_parts();
+    let url = request_url(scheme, &parts);
+    let headers: Vec<(String, String)> = parts
+      .headers
+      .iter()
+      .map(|(key, value)| {
+        (
+          key.to_string(),
+          String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        )
+      })
+      .collect();
+
+    let body_rid = if body.is_end_stream() {
+      None
+    } else {
+      Some(resource_table.add(
+        "httpRequestBody",
+        Box::new(StreamResourceHolder::new(StreamResource::HttpRequestBody(
+          Box::new(HttpRequestBody { body, chunk: None }),
+        ))),
+      ))
+    };
+    let request_rid = resource_table.add(
+      "httpRequest",
+      Box::new(HttpRequestResource {
+        response_tx: event.response_tx,
+      }),
+    );
+
+    Poll::Ready(Ok(json!({
+      "requestRid": request_rid,
+      "bodyRid": body_rid,
+      "method": parts.method.as_str(),
+      "url": url,
+      "headers": headers,
+    })))
+  });
+
+  Ok(JsonOp::Async(f.boxed_local()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpRespondArgs {
+  rid: u32,
+  status: u16,
+  headers: Vec<(String, String)>,
+  /// The body is written to the returned resource instead of being passed
+  /// as a buffer.
+  #[serde(default)]
+  stream_body: bool,
+}
+
+fn op_http_respond(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: HttpRespondArgs = serde_json::from_value(args)?;
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  let request = resource_table
+    .remove::<HttpRequestResource>(args.rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+
+  let mut builder = Response::builder().status(args.status);
+  for (key, value) in args.headers {
+    let name = HeaderName::from_bytes(key.as_bytes())
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    let v = HeaderValue::from_str(&value)
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    builder = builder.header(name, v);
+  }
+
+  let mut body_sender = None;
+  let body = match zero_copy.len() {
+    0 if args.stream_body => {
+      let (sender, body) = Body::channel();
+      body_sender = Some(sender);
+      body
+    }
+    0 => Body::empty(),
+    1 => Body::from(Vec::from(&*zero_copy[0])),
+    _ => {
+      return Err(OpError::type_error(
+        "Invalid number of arguments".to_string(),
+      ))
+    }
+  };
+  let response = builder
+    .body(body)
+    .map_err(|e| OpError::type_error(e.to_string()))?;
+
+  if request.response_tx.send(response).is_err() {
+    return Err(OpError::from(io::Error::new(
+      io::ErrorKind::BrokenPipe,
+      "Connection closed before the response was sent",
+    )));
+  }
+
+  let body_rid = body_sender.map(|sender| {
+    resource_table.add(
+      "httpResponseBody",
+      Box::new(StreamResourceHolder::new(StreamResource::HttpResponseBody(
+        Box::new(HttpResponseBody { sender }),
+      ))),
+    )
+  });
+  Ok(JsonOp::Sync(json!({ "bodyRid": body_rid })))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::io::AsyncReadExt;
+
+  #[test]
+  fn request_url_from_host_header() {
+    let (parts, _) = Request::get("/foo?bar=1")
+      .header(HOST, "example.com:8000")
+      .body(())
+      .unwrap()
+      .into_parts();
+    assert_eq!(
+      request_url("http", &parts),
+      "http://example.com:8000/foo?bar=1"
+    );
+
+    let (parts, _) = Request::get("https://deno.land/std/")
+      .body(())
+      .unwrap()
+      .into_parts();
+    assert_eq!(request_url("https", &parts), "https://deno.land/std/");
+  }
+
+  #[tokio::test]
+  async fn read_request_body() {
+    let (mut sender, body) = Body::channel();
+    let mut reader = HttpRequestBody { body, chunk: None };
+    tokio::spawn(async move {
+      sender.send_data(Bytes::from("hello ")).await.unwrap();
+      sender.send_data(Bytes::from("")).await.unwrap();
+      sender.send_data(Bytes::from("world")).await.unwrap();
+    });
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf).await.unwrap();
+    assert_eq!(buf, "hello world");
+  }
+}
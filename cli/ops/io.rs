@@ -1,4 +1,5 @@
 use super::dispatch_minimal::MinimalOp;
+use super::http::{HttpRequestBody, HttpResponseBody};
 use crate::http_util::HttpBody;
 use crate::op_error::OpError;
 use crate::state::State;
@@ -170,6 +171,8 @@ pub enum StreamResource {
   ServerTlsStream(Box<ServerTlsStream<TcpStream>>),
   ClientTlsStream(Box<ClientTlsStream<TcpStream>>),
   HttpBody(Box<HttpBody>),
+  HttpRequestBody(Box<HttpRequestBody>),
+  HttpResponseBody(Box<HttpResponseBody>),
   ChildStdin(tokio::process::ChildStdin),
   ChildStdout(tokio::process::ChildStdout),
   ChildStderr(tokio::process::ChildStderr),
@@ -210,6 +213,7 @@ impl DenoAsyncRead for StreamResource {
       ChildStdout(f) => f,
       ChildStderr(f) => f,
       HttpBody(f) => f,
+      HttpRequestBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
     let v = ready!(Pin::new(f).poll_read(cx, buf))?;
@@ -312,6 +316,7 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      HttpResponseBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
 
@@ -330,6 +335,7 @@ impl DenoAsyncWrite for StreamResource {
       ClientTlsStream(f) => f,
       ServerTlsStream(f) => f,
       ChildStdin(f) => f,
+      HttpResponseBody(f) => f,
       _ => return Err(OpError::bad_resource_id()).into(),
     };
 
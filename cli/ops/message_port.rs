@@ -0,0 +1,247 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use crate::op_error::OpError;
+use crate::state::State;
+use deno_core::CoreIsolate;
+use deno_core::CoreIsolateState;
+use deno_core::ResourceTable;
+use deno_core::SerializedValue;
+use deno_core::ZeroCopyBuf;
+use futures::channel::mpsc;
+use futures::future::poll_fn;
+use futures::future::FutureExt;
+use futures::stream::StreamExt;
+use std::task::Poll;
+use std::task::Waker;
+
+pub fn init(i: &mut CoreIsolate, s: &State) {
+  i.register_op(
+    "op_message_port_create_entangled",
+    s.stateful_json_op2(op_message_port_create_entangled),
+  );
+  i.register_op(
+    "op_message_port_post_message",
+    s.stateful_json_op2(op_message_port_post_message),
+  );
+  i.register_op(
+    "op_message_port_recv_message",
+    s.stateful_json_op2(op_message_port_recv_message),
+  );
+}
+
+/// A message sent through a `MessageChannel` or to a worker: a value
+/// serialized by `Deno.core.serialize()` and the `MessagePort`s it transfers.
+pub struct Message {
+  pub value: SerializedValue,
+  pub ports: Vec<MessagePort>,
+}
+
+/// The rids of a message in the resource table of the sending or receiving
+/// isolate, see `serializeMessage()` and `deserializeMessage()`.
+#[derive(Deserialize)]
+pub struct MessageArgs {
+  value: u32,
+  ports: Vec<u32>,
+}
+
+impl Message {
+  /// Moves a serialized value and the ports it transfers out of the resource
+  /// table of the sending isolate.
+  pub fn take(
+    resource_table: &mut ResourceTable,
+    args: MessageArgs,
+  ) -> Result<Message, OpError> {
+    let value = resource_table
+      .remove::<SerializedValue>(args.value)
+      .ok_or_else(OpError::bad_resource_id)?;
+    // Check every port before moving any of them, so that an invalid
+    // transfer list leaves all ports usable.
+    for (i, rid) in args.ports.iter().enumerate() {
+      if resource_table.get::<MessagePortResource>(*rid).is_none()
+        || args.ports[..i].contains(rid)
+      {
+        return Err(OpError::bad_resource_id());
+      }
+    }
+    let ports = args
+      .ports
+      .iter()
+      .map(|rid| {
+        let mut resource =
+          resource_table.remove::<MessagePortResource>(*rid).unwrap();
+        resource.take_port()
+      })
+      .collect();
+    Ok(Message {
+      value: *value,
+      ports,
+    })
+  }
+
+  /// Adds a received message to the resource table of the receiving isolate
+  /// and returns the rids of its value and ports.
+  pub fn add_to(self, resource_table: &mut ResourceTable) -> Value {
+    let value = resource_table.add("serializedValue", Box::new(self.value));
+    let ports: Vec<u32> = self
+      .ports
+      .into_iter()
+      .map(|port| add_port(resource_table, port))
+      .collect();
+    json!({ "value": value, "ports": ports })
+  }
+}
+
+/// One end of a `MessageChannel`.
+pub struct MessagePort {
+  sender: mpsc::UnboundedSender<Message>,
+  receiver: mpsc::UnboundedReceiver<Message>,
+}
+
+impl MessagePort {
+  pub fn create_entangled() -> (MessagePort, MessagePort) {
+    let (sender1, receiver1) = mpsc::unbounded::<Message>();
+    let (sender2, receiver2) = mpsc::unbounded::<Message>();
+    let port1 = MessagePort {
+      sender: sender1,
+      receiver: receiver2,
+    };
+    let port2 = MessagePort {
+      sender: sender2,
+      receiver: receiver1,
+    };
+    (port1, port2)
+  }
+}
+
+struct MessagePortResource {
+  port: MessagePort,
+  /// Waker of a pending `op_message_port_recv_message`, woken when the port
+  /// is closed or transferred so that the op doesn't hang forever.
+  waker: Option<Waker>,
+}
+
+impl MessagePortResource {
+  /// Moves the port out of the resource so that it can be sent to another
+  /// isolate.
+  fn take_port(&mut self) -> MessagePort {
+    let (placeholder, _) = MessagePort::create_entangled();
+    std::mem::replace(&mut self.port, placeholder)
+  }
+}
+
+impl Drop for MessagePortResource {
+  fn drop(&mut self) {
+    if let Some(waker) = self.waker.take() {
+      waker.wake();
+    }
+  }
+}
+
+fn add_port(resource_table: &mut ResourceTable, port: MessagePort) -> u32 {
+  resource_table.add(
+    "messagePort",
+    Box::new(MessagePortResource { port, waker: None }),
+  )
+}
+
+fn op_message_port_create_entangled(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  _args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let (port1, port2) = MessagePort::create_entangled();
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  let rid1 = add_port(&mut resource_table, port1);
+  let rid2 = add_port(&mut resource_table, port2);
+  Ok(JsonOp::Sync(json!([rid1, rid2])))
+}
+
+#[derive(Deserialize)]
+struct MessagePortArgs {
+  rid: u32,
+}
+
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  rid: u32,
+  message: MessageArgs,
+}
+
+fn op_message_port_post_message(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: PostMessageArgs = serde_json::from_value(args)?;
+  let mut resource_table = isolate_state.resource_table.borrow_mut();
+  let msg = Message::take(&mut resource_table, args.message)?;
+  let resource = resource_table
+    .get::<MessagePortResource>(args.rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  // Messages sent after the other port was closed are silently dropped.
+  let _ = resource.port.sender.unbounded_send(msg);
+  Ok(JsonOp::Sync(json!({})))
+}
+
+/// Resolves with the next message, or with `null` once the port is closed,
+/// transferred, or its entangled port is gone.
+fn op_message_port_recv_message(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: MessagePortArgs = serde_json::from_value(args)?;
+  let rid = args.rid;
+  let resource_table = isolate_state.resource_table.clone();
+  let f = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let resource = match resource_table.get_mut::<MessagePortResource>(rid) {
+      Some(resource) => resource,
+      None => return Poll::Ready(Ok(json!({ "message": null }))),
+    };
+    match resource.port.receiver.poll_next_unpin(cx) {
+      Poll::Ready(Some(msg)) => {
+        resource.waker = None;
+        let msg = msg.add_to(&mut resource_table);
+        Poll::Ready(Ok(json!({ "message": msg })))
+      }
+      Poll::Ready(None) => {
+        resource.waker = None;
+        Poll::Ready(Ok(json!({ "message": null })))
+      }
+      Poll::Pending => {
+        resource.waker = Some(cx.waker().clone());
+        Poll::Pending
+      }
+    }
+  });
+  Ok(JsonOp::Async(f.boxed_local()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entangled_ports() {
+    let (port1, mut port2) = MessagePort::create_entangled();
+    let value = SerializedValue {
+      data: vec![1, 2, 3].into_boxed_slice(),
+      array_buffers: vec![],
+    };
+    port1
+      .sender
+      .unbounded_send(Message {
+        value,
+        ports: vec![],
+      })
+      .unwrap();
+    let msg = port2.receiver.try_next().unwrap().unwrap();
+    assert_eq!(*msg.value.data, [1, 2, 3]);
+    drop(port1);
+    assert!(port2.receiver.try_next().unwrap().is_none());
+  }
+}
@@ -9,11 +9,14 @@ pub use dispatch_minimal::minimal_op;
 pub use dispatch_minimal::MinimalOp;
 
 pub mod compiler;
+pub mod dns;
 pub mod errors;
 pub mod fetch;
 pub mod fs;
 pub mod fs_events;
+pub mod http;
 pub mod io;
+pub mod message_port;
 pub mod net;
 #[cfg(unix)]
 mod net_unix;
@@ -31,4 +34,5 @@ pub mod timers;
 pub mod tls;
 pub mod tty;
 pub mod web_worker;
+pub mod websocket;
 pub mod worker_host;
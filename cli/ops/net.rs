@@ -11,10 +11,14 @@ use deno_core::ZeroCopyBuf;
 use futures::future::poll_fn;
 use futures::future::FutureExt;
 use std::convert::From;
+use std::io;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
 use std::net::Shutdown;
 use std::net::SocketAddr;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 use tokio::net::UdpSocket;
@@ -32,6 +36,21 @@ pub fn init(i: &mut CoreIsolate, s: &State) {
     s.stateful_json_op2(op_datagram_receive),
   );
   i.register_op("op_datagram_send", s.stateful_json_op2(op_datagram_send));
+  i.register_op("op_set_nodelay", s.stateful_json_op2(op_set_nodelay));
+  i.register_op("op_set_keepalive", s.stateful_json_op2(op_set_keepalive));
+  i.register_op("op_set_broadcast", s.stateful_json_op2(op_set_broadcast));
+  i.register_op(
+    "op_join_multicast_v4",
+    s.stateful_json_op2(op_join_multicast_v4),
+  );
+  i.register_op(
+    "op_join_multicast_v6",
+    s.stateful_json_op2(op_join_multicast_v6),
+  );
+  i.register_op(
+    "op_leave_multicast",
+    s.stateful_json_op2(op_leave_multicast),
+  );
 }
 
 #[derive(Deserialize)]
@@ -388,6 +407,222 @@ fn op_shutdown(
   Ok(JsonOp::Sync(json!({})))
 }
 
+/// Calls `f` with the TCP stream of a TCP or TLS connection.
+fn with_tcp_stream<F>(
+  isolate_state: &mut CoreIsolateState,
+  rid: u32,
+  f: F,
+) -> Result<(), OpError>
+where
+  F: FnOnce(&TcpStream) -> io::Result<()>,
+{
+  let resource_table = isolate_state.resource_table.borrow();
+  let resource_holder = resource_table
+    .get::<StreamResourceHolder>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  let stream = match &resource_holder.resource {
+    StreamResource::TcpStream(Some(stream)) => stream,
+    StreamResource::ClientTlsStream(stream) => stream.get_ref().0,
+    StreamResource::ServerTlsStream(stream) => stream.get_ref().0,
+    _ => return Err(OpError::bad_resource_id()),
+  };
+  f(stream).map_err(OpError::from)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetNoDelayArgs {
+  rid: u32,
+  no_delay: bool,
+}
+
+fn op_set_nodelay(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.Conn.setNoDelay");
+  let args: SetNoDelayArgs = serde_json::from_value(args)?;
+  with_tcp_stream(isolate_state, args.rid, |stream| {
+    stream.set_nodelay(args.no_delay)
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetKeepAliveArgs {
+  rid: u32,
+  keep_alive: bool,
+  /// Idle time before the first keepalive probe is sent, in milliseconds.
+  delay: Option<u64>,
+}
+
+fn op_set_keepalive(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.Conn.setKeepAlive");
+  let args: SetKeepAliveArgs = serde_json::from_value(args)?;
+  let keepalive = if args.keep_alive {
+    // Two hours is the default of most operating systems.
+    Some(Duration::from_millis(
+      args.delay.unwrap_or(2 * 60 * 60 * 1000),
+    ))
+  } else {
+    None
+  };
+  with_tcp_stream(isolate_state, args.rid, |stream| {
+    stream.set_keepalive(keepalive)
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+/// Calls `f` with the socket of a UDP resource.
+fn with_udp_socket<F>(
+  isolate_state: &mut CoreIsolateState,
+  rid: u32,
+  f: F,
+) -> Result<(), OpError>
+where
+  F: FnOnce(&UdpSocket) -> io::Result<()>,
+{
+  let resource_table = isolate_state.resource_table.borrow();
+  let resource = resource_table
+    .get::<UdpSocketResource>(rid)
+    .ok_or_else(OpError::bad_resource_id)?;
+  f(&resource.socket).map_err(OpError::from)
+}
+
+#[derive(Deserialize)]
+struct SetBroadcastArgs {
+  rid: u32,
+  broadcast: bool,
+}
+
+fn op_set_broadcast(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.DatagramConn.setBroadcast");
+  let args: SetBroadcastArgs = serde_json::from_value(args)?;
+  with_udp_socket(isolate_state, args.rid, |socket| {
+    socket.set_broadcast(args.broadcast)
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MulticastArgs {
+  rid: u32,
+  address: String,
+  /// Address of the IPv4 interface. Any interface if unset.
+  interface: Option<String>,
+  /// Index of the IPv6 interface. Any interface if unset.
+  interface_index: Option<u32>,
+}
+
+impl MulticastArgs {
+  fn address(&self) -> Result<IpAddr, OpError> {
+    let address: IpAddr = self.address.parse().map_err(|_| {
+      OpError::type_error(format!("Invalid IP address: {}", self.address))
+    })?;
+    if !address.is_multicast() {
+      return Err(OpError::type_error(format!(
+        "{} is not a multicast address",
+        address
+      )));
+    }
+    Ok(address)
+  }
+
+  fn interface_v4(&self) -> Result<Ipv4Addr, OpError> {
+    match &self.interface {
+      Some(interface) => interface.parse().map_err(|_| {
+        OpError::type_error(format!("Invalid IPv4 address: {}", interface))
+      }),
+      None => Ok(Ipv4Addr::UNSPECIFIED),
+    }
+  }
+}
+
+fn op_join_multicast_v4(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.DatagramConn.joinMulticastV4");
+  let args: MulticastArgs = serde_json::from_value(args)?;
+  let address = match args.address()? {
+    IpAddr::V4(address) => address,
+    IpAddr::V6(_) => {
+      return Err(OpError::type_error(
+        "Use joinMulticastV6 to join an IPv6 group".to_string(),
+      ))
+    }
+  };
+  let interface = args.interface_v4()?;
+  with_udp_socket(isolate_state, args.rid, |socket| {
+    socket.join_multicast_v4(address, interface)
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+fn op_join_multicast_v6(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.DatagramConn.joinMulticastV6");
+  let args: MulticastArgs = serde_json::from_value(args)?;
+  let address = match args.address()? {
+    IpAddr::V6(address) => address,
+    IpAddr::V4(_) => {
+      return Err(OpError::type_error(
+        "Use joinMulticastV4 to join an IPv4 group".to_string(),
+      ))
+    }
+  };
+  let interface_index = args.interface_index.unwrap_or(0);
+  with_udp_socket(isolate_state, args.rid, |socket| {
+    socket.join_multicast_v6(&address, interface_index)
+  })?;
+  Ok(JsonOp::Sync(json!({})))
+}
+
+fn op_leave_multicast(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.DatagramConn.leaveMulticast");
+  let args: MulticastArgs = serde_json::from_value(args)?;
+  match args.address()? {
+    IpAddr::V4(address) => {
+      let interface = args.interface_v4()?;
+      with_udp_socket(isolate_state, args.rid, |socket| {
+        socket.leave_multicast_v4(address, interface)
+      })?;
+    }
+    IpAddr::V6(address) => {
+      let interface_index = args.interface_index.unwrap_or(0);
+      with_udp_socket(isolate_state, args.rid, |socket| {
+        socket.leave_multicast_v6(&address, interface_index)
+      })?;
+    }
+  }
+  Ok(JsonOp::Sync(json!({})))
+}
+
 #[allow(dead_code)]
 struct TcpListenerResource {
   listener: TcpListener,
@@ -442,9 +677,12 @@ struct UdpSocketResource {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct IpListenArgs {
   hostname: String,
   port: u16,
+  #[serde(default)]
+  reuse_port: bool,
 }
 
 #[derive(Deserialize)]
@@ -462,11 +700,55 @@ struct ListenArgs {
   transport_args: ArgsEnum,
 }
 
+/// Binds a socket with `SO_REUSEPORT` set, which lets several sockets, for
+/// example of different processes, be bound to the same address.
+#[cfg(unix)]
+fn bind_reuse_port(
+  addr: SocketAddr,
+  socket_type: socket2::Type,
+) -> Result<socket2::Socket, OpError> {
+  let domain = if addr.is_ipv4() {
+    socket2::Domain::ipv4()
+  } else {
+    socket2::Domain::ipv6()
+  };
+  let socket = socket2::Socket::new(domain, socket_type, None)?;
+  socket.set_reuse_address(true)?;
+  socket.set_reuse_port(true)?;
+  socket.bind(&addr.into())?;
+  Ok(socket)
+}
+
+#[cfg(not(unix))]
+fn bind_reuse_port(
+  _addr: SocketAddr,
+  _socket_type: socket2::Type,
+) -> Result<socket2::Socket, OpError> {
+  Err(OpError::other(
+    "reusePort is not supported on this platform".to_string(),
+  ))
+}
+
+/// Binds a TCP listener, also used by `op_listen_tls`.
+pub fn bind_tcp_listener(
+  addr: SocketAddr,
+  reuse_port: bool,
+) -> Result<std::net::TcpListener, OpError> {
+  if reuse_port {
+    let socket = bind_reuse_port(addr, socket2::Type::stream())?;
+    socket.listen(128)?;
+    Ok(socket.into_tcp_listener())
+  } else {
+    Ok(std::net::TcpListener::bind(&addr)?)
+  }
+}
+
 fn listen_tcp(
   resource_table: &mut ResourceTable,
   addr: SocketAddr,
+  reuse_port: bool,
 ) -> Result<(u32, SocketAddr), OpError> {
-  let std_listener = std::net::TcpListener::bind(&addr)?;
+  let std_listener = bind_tcp_listener(addr, reuse_port)?;
   let listener = TcpListener::from_std(std_listener)?;
   let local_addr = listener.local_addr()?;
   let listener_resource = TcpListenerResource {
@@ -482,8 +764,13 @@ fn listen_tcp(
 fn listen_udp(
   resource_table: &mut ResourceTable,
   addr: SocketAddr,
+  reuse_port: bool,
 ) -> Result<(u32, SocketAddr), OpError> {
-  let std_socket = std::net::UdpSocket::bind(&addr)?;
+  let std_socket = if reuse_port {
+    bind_reuse_port(addr, socket2::Type::dgram())?.into_udp_socket()
+  } else {
+    std::net::UdpSocket::bind(&addr)?
+  };
   let socket = UdpSocket::from_std(std_socket)?;
   let local_addr = socket.local_addr()?;
   let socket_resource = UdpSocketResource { socket };
@@ -507,12 +794,15 @@ fn op_listen(
       if transport == "udp" {
         state.check_unstable("Deno.listenDatagram");
       }
+      if args.reuse_port {
+        state.check_unstable("Deno.listen");
+      }
       state.check_net(&args.hostname, args.port)?;
       let addr = resolve_addr(&args.hostname, args.port)?;
       let (rid, local_addr) = if transport == "tcp" {
-        listen_tcp(&mut resource_table, addr)?
+        listen_tcp(&mut resource_table, addr, args.reuse_port)?
       } else {
-        listen_udp(&mut resource_table, addr)?
+        listen_udp(&mut resource_table, addr, args.reuse_port)?
       };
       debug!(
         "New listener {} {}:{}",
@@ -102,11 +102,12 @@ struct Exit {
 }
 
 fn op_exit(
-  _s: &State,
+  state: &State,
   args: Value,
   _zero_copy: &mut [ZeroCopyBuf],
 ) -> Result<JsonOp, OpError> {
   let args: Exit = serde_json::from_value(args)?;
+  state.stop_cpu_profiler();
   std::process::exit(args.code)
 }
 
@@ -1,6 +1,7 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use super::io::{StreamResource, StreamResourceHolder};
+use super::net::bind_tcp_listener;
 use crate::op_error::OpError;
 use crate::resolve_addr::resolve_addr;
 use crate::state::State;
@@ -9,6 +10,10 @@ use deno_core::CoreIsolateState;
 use deno_core::ZeroCopyBuf;
 use futures::future::poll_fn;
 use futures::future::FutureExt;
+use rustls::{
+  ServerCertVerified, ServerCertVerifier, TLSError, WebPKIVerifier,
+};
+use std::collections::HashMap;
 use std::convert::From;
 use std::fs::File;
 use std::io::BufReader;
@@ -23,7 +28,9 @@ use tokio_rustls::{rustls::ClientConfig, TlsConnector};
 use tokio_rustls::{
   rustls::{
     internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
-    Certificate, NoClientAuth, PrivateKey, ServerConfig,
+    sign, AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient,
+    Certificate, ClientHello, NoClientAuth, PrivateKey, ResolvesServerCert,
+    RootCertStore, ServerConfig, Session,
   },
   TlsAcceptor,
 };
@@ -36,21 +43,103 @@ pub fn init(i: &mut CoreIsolate, s: &State) {
   i.register_op("op_accept_tls", s.stateful_json_op2(op_accept_tls));
 }
 
+/// Options shared by `op_connect_tls` and `op_start_tls`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClientTlsOptions {
+  cert_file: Option<String>,
+  client_cert_file: Option<String>,
+  client_key_file: Option<String>,
+  /// Name used for SNI and to verify the server certificate instead of the
+  /// hostname that is connected to.
+  server_name: Option<String>,
+  #[serde(default)]
+  alpn_protocols: Vec<String>,
+}
+
+impl ClientTlsOptions {
+  fn is_unstable(&self) -> bool {
+    self.client_cert_file.is_some()
+      || self.client_key_file.is_some()
+      || self.server_name.is_some()
+      || !self.alpn_protocols.is_empty()
+  }
+
+  fn check_read(&self, state: &State) -> Result<(), OpError> {
+    let files = vec![
+      &self.cert_file,
+      &self.client_cert_file,
+      &self.client_key_file,
+    ];
+    for path in files.into_iter().flatten() {
+      state.check_read(Path::new(path))?;
+    }
+    Ok(())
+  }
+
+  fn create_config(&self, state: &State) -> Result<ClientConfig, OpError> {
+    let ignored_hosts = state
+      .borrow()
+      .global_state
+      .flags
+      .unsafely_ignore_certificate_errors
+      .clone();
+    let mut config =
+      create_client_config(self.cert_file.as_deref(), &ignored_hosts)?;
+    match (&self.client_cert_file, &self.client_key_file) {
+      (Some(cert_file), Some(key_file)) => {
+        config
+          .set_single_client_cert(
+            load_certs(cert_file)?,
+            load_keys(key_file)?.remove(0),
+          )
+          .map_err(|e| OpError::other(e.to_string()))?;
+      }
+      (None, None) => {}
+      _ => {
+        return Err(OpError::type_error(
+          "clientCertFile and clientKeyFile must be passed together"
+            .to_string(),
+        ))
+      }
+    }
+    config.set_protocols(&alpn_protocols_to_bytes(&self.alpn_protocols));
+    Ok(config)
+  }
+
+  /// Returns the name the server certificate is verified against.
+  fn dns_name(&self, hostname: &str) -> String {
+    match &self.server_name {
+      Some(server_name) => server_name.clone(),
+      None if hostname.is_empty() => "localhost".to_string(),
+      None => hostname.to_string(),
+    }
+  }
+}
+
+fn parse_dns_name(domain: &str) -> Result<DNSNameRef, OpError> {
+  DNSNameRef::try_from_ascii_str(domain).map_err(|_| {
+    OpError::type_error(format!("Invalid server name: {}", domain))
+  })
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ConnectTLSArgs {
   transport: String,
   hostname: String,
   port: u16,
-  cert_file: Option<String>,
+  #[serde(flatten)]
+  options: ClientTlsOptions,
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct StartTLSArgs {
   rid: u32,
-  cert_file: Option<String>,
   hostname: String,
+  #[serde(flatten)]
+  options: ClientTlsOptions,
 }
 
 pub fn op_start_tls(
@@ -62,18 +151,17 @@ pub fn op_start_tls(
   state.check_unstable("Deno.startTls");
   let args: StartTLSArgs = serde_json::from_value(args)?;
   let rid = args.rid as u32;
-  let cert_file = args.cert_file.clone();
   let resource_table = isolate_state.resource_table.clone();
 
-  let mut domain = args.hostname;
-  if domain.is_empty() {
-    domain.push_str("localhost");
-  }
+  let domain = args.options.dns_name(&args.hostname);
 
-  state.check_net(&domain, 0)?;
-  if let Some(path) = cert_file.clone() {
-    state.check_read(Path::new(&path))?;
+  if args.hostname.is_empty() {
+    state.check_net("localhost", 0)?;
+  } else {
+    state.check_net(&args.hostname, 0)?;
   }
+  args.options.check_read(state)?;
+  let config = args.options.create_config(state)?;
 
   let op = async move {
     let mut resource_holder = {
@@ -90,20 +178,11 @@ pub fn op_start_tls(
       let tcp_stream = tcp_stream.take().unwrap();
       let local_addr = tcp_stream.local_addr()?;
       let remote_addr = tcp_stream.peer_addr()?;
-      let mut config = ClientConfig::new();
-      config
-        .root_store
-        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
-      if let Some(path) = cert_file {
-        let key_file = File::open(path)?;
-        let reader = &mut BufReader::new(key_file);
-        config.root_store.add_pem_file(reader).unwrap();
-      }
-
       let tls_connector = TlsConnector::from(Arc::new(config));
-      let dnsname =
-        DNSNameRef::try_from_ascii_str(&domain).expect("Invalid DNS lookup");
+      let dnsname = parse_dns_name(&domain)?;
       let tls_stream = tls_connector.connect(dnsname, tcp_stream).await?;
+      let (alpn_protocol, peer_certificates) =
+        session_info(tls_stream.get_ref().1);
 
       let mut resource_table_ = resource_table.borrow_mut();
       let rid = resource_table_.add(
@@ -123,7 +202,9 @@ pub fn op_start_tls(
             "hostname": remote_addr.ip().to_string(),
             "port": remote_addr.port(),
             "transport": "tcp",
-          }
+          },
+          "alpnProtocol": alpn_protocol,
+          "peerCertificates": peer_certificates,
       }))
     } else {
       Err(OpError::bad_resource_id())
@@ -139,36 +220,26 @@ pub fn op_connect_tls(
   _zero_copy: &mut [ZeroCopyBuf],
 ) -> Result<JsonOp, OpError> {
   let args: ConnectTLSArgs = serde_json::from_value(args)?;
-  let cert_file = args.cert_file.clone();
+  if args.options.is_unstable() {
+    state.check_unstable("Deno.connectTls");
+  }
   let resource_table = isolate_state.resource_table.clone();
   state.check_net(&args.hostname, args.port)?;
-  if let Some(path) = cert_file.clone() {
-    state.check_read(Path::new(&path))?;
-  }
+  args.options.check_read(state)?;
+  let config = args.options.create_config(state)?;
 
-  let mut domain = args.hostname.clone();
-  if domain.is_empty() {
-    domain.push_str("localhost");
-  }
+  let domain = args.options.dns_name(&args.hostname);
 
   let op = async move {
     let addr = resolve_addr(&args.hostname, args.port)?;
     let tcp_stream = TcpStream::connect(&addr).await?;
     let local_addr = tcp_stream.local_addr()?;
     let remote_addr = tcp_stream.peer_addr()?;
-    let mut config = ClientConfig::new();
-    config
-      .root_store
-      .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
-    if let Some(path) = cert_file {
-      let key_file = File::open(path)?;
-      let reader = &mut BufReader::new(key_file);
-      config.root_store.add_pem_file(reader).unwrap();
-    }
     let tls_connector = TlsConnector::from(Arc::new(config));
-    let dnsname =
-      DNSNameRef::try_from_ascii_str(&domain).expect("Invalid DNS lookup");
+    let dnsname = parse_dns_name(&domain)?;
     let tls_stream = tls_connector.connect(dnsname, tcp_stream).await?;
+    let (alpn_protocol, peer_certificates) =
+      session_info(tls_stream.get_ref().1);
     let mut resource_table_ = resource_table.borrow_mut();
     let rid = resource_table_.add(
       "clientTlsStream",
@@ -187,13 +258,76 @@ pub fn op_connect_tls(
           "hostname": remote_addr.ip().to_string(),
           "port": remote_addr.port(),
           "transport": args.transport,
-        }
+        },
+        "alpnProtocol": alpn_protocol,
+        "peerCertificates": peer_certificates,
     }))
   };
 
   Ok(JsonOp::Async(op.boxed_local()))
 }
 
+/// Verifies server certificates like rustls does by default, except for the
+/// hosts passed to `--unsafely-ignore-certificate-errors`.
+struct IgnoreCertificateErrorsVerifier {
+  hosts: Vec<String>,
+  webpki: WebPKIVerifier,
+}
+
+impl ServerCertVerifier for IgnoreCertificateErrorsVerifier {
+  fn verify_server_cert(
+    &self,
+    roots: &RootCertStore,
+    presented_certs: &[Certificate],
+    dns_name: DNSNameRef,
+    ocsp_response: &[u8],
+  ) -> Result<ServerCertVerified, TLSError> {
+    let name: &str = dns_name.into();
+    if self
+      .hosts
+      .iter()
+      .any(|host| host.eq_ignore_ascii_case(name))
+    {
+      return Ok(ServerCertVerified::assertion());
+    }
+    self.webpki.verify_server_cert(
+      roots,
+      presented_certs,
+      dns_name,
+      ocsp_response,
+    )
+  }
+}
+
+/// Creates the configuration of a TLS client that trusts the Mozilla root
+/// certificates, like the HTTP client does, and the certificates in
+/// `ca_file`. Certificates of `ignored_hosts` are not verified at all.
+pub fn create_client_config(
+  ca_file: Option<&str>,
+  ignored_hosts: &[String],
+) -> Result<ClientConfig, OpError> {
+  let mut config = ClientConfig::new();
+  config
+    .root_store
+    .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+  if let Some(path) = ca_file {
+    let ca_file = File::open(path)?;
+    let reader = &mut BufReader::new(ca_file);
+    config.root_store.add_pem_file(reader).map_err(|_| {
+      OpError::other("Unable to decode certificate".to_string())
+    })?;
+  }
+  if !ignored_hosts.is_empty() {
+    config.dangerous().set_certificate_verifier(Arc::new(
+      IgnoreCertificateErrorsVerifier {
+        hosts: ignored_hosts.to_vec(),
+        webpki: WebPKIVerifier::new(),
+      },
+    ));
+  }
+  Ok(config)
+}
+
 fn load_certs(path: &str) -> Result<Vec<Certificate>, OpError> {
   let cert_file = File::open(path)?;
   let reader = &mut BufReader::new(cert_file);
@@ -248,6 +382,117 @@ fn load_keys(path: &str) -> Result<Vec<PrivateKey>, OpError> {
   Ok(keys)
 }
 
+/// Loads a certificate chain and the first private key of `key_file`.
+fn load_certified_key(
+  cert_file: &str,
+  key_file: &str,
+) -> Result<sign::CertifiedKey, OpError> {
+  let certs = load_certs(cert_file)?;
+  let key = load_keys(key_file)?.remove(0);
+  let signing_key = sign::any_supported_type(&key)
+    .map_err(|_| OpError::other("Unsupported private key type".to_string()))?;
+  Ok(sign::CertifiedKey::new(certs, Arc::new(signing_key)))
+}
+
+fn alpn_protocols_to_bytes(protocols: &[String]) -> Vec<Vec<u8>> {
+  protocols.iter().map(|p| p.as_bytes().to_vec()).collect()
+}
+
+fn cert_to_pem(cert: &Certificate) -> String {
+  let encoded = base64::encode(&cert.0);
+  let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+  for line in encoded.as_bytes().chunks(64) {
+    pem.push_str(std::str::from_utf8(line).unwrap());
+    pem.push('\n');
+  }
+  pem.push_str("-----END CERTIFICATE-----\n");
+  pem
+}
+
+/// Returns the ALPN protocol negotiated on a TLS connection and the
+/// certificate chain presented by the peer, PEM encoded.
+fn session_info<S: Session>(session: &S) -> (Option<String>, Vec<String>) {
+  let alpn_protocol = session
+    .get_alpn_protocol()
+    .map(|p| String::from_utf8_lossy(p).into_owned());
+  let peer_certificates = session
+    .get_peer_certificates()
+    .unwrap_or_default()
+    .iter()
+    .map(cert_to_pem)
+    .collect();
+  (alpn_protocol, peer_certificates)
+}
+
+/// Selects the certificate for the server name a client requested with SNI,
+/// falling back to the default certificate.
+struct SniCertResolver {
+  default: sign::CertifiedKey,
+  by_name: HashMap<String, sign::CertifiedKey>,
+}
+
+impl ResolvesServerCert for SniCertResolver {
+  fn resolve(&self, client_hello: ClientHello) -> Option<sign::CertifiedKey> {
+    let certified_key = client_hello
+      .server_name()
+      .and_then(|name| {
+        let name: &str = name.into();
+        self.by_name.get(&name.to_ascii_lowercase())
+      })
+      .unwrap_or(&self.default);
+    Some(certified_key.clone())
+  }
+}
+
+fn create_server_config(args: &ListenTlsArgs) -> Result<ServerConfig, OpError> {
+  let mut config = match &args.client_ca_file {
+    Some(path) => {
+      let ca_file = File::open(path)?;
+      let reader = &mut BufReader::new(ca_file);
+      let mut roots = RootCertStore::empty();
+      roots.add_pem_file(reader).map_err(|_| {
+        OpError::other("Unable to decode certificate".to_string())
+      })?;
+      match args.client_auth.as_deref() {
+        None | Some("require") => {
+          ServerConfig::new(AllowAnyAuthenticatedClient::new(roots))
+        }
+        Some("request") => {
+          ServerConfig::new(AllowAnyAnonymousOrAuthenticatedClient::new(roots))
+        }
+        Some(mode) => {
+          return Err(OpError::type_error(format!(
+            "Invalid clientAuth mode: {}",
+            mode
+          )))
+        }
+      }
+    }
+    None => {
+      if args.client_auth.is_some() {
+        return Err(OpError::type_error(
+          "clientAuth requires clientCaFile".to_string(),
+        ));
+      }
+      ServerConfig::new(NoClientAuth::new())
+    }
+  };
+
+  let mut by_name = HashMap::new();
+  for cert in &args.certificates {
+    by_name.insert(
+      cert.hostname.to_ascii_lowercase(),
+      load_certified_key(&cert.cert_file, &cert.key_file)?,
+    );
+  }
+  config.cert_resolver = Arc::new(SniCertResolver {
+    default: load_certified_key(&args.cert_file, &args.key_file)?,
+    by_name,
+  });
+  config.set_protocols(&alpn_protocols_to_bytes(&args.alpn_protocols));
+  Ok(config)
+}
+
 #[allow(dead_code)]
 pub struct TlsListenerResource {
   listener: TcpListener,
@@ -296,6 +541,14 @@ impl TlsListenerResource {
   }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SniCertificateArgs {
+  hostname: String,
+  cert_file: String,
+  key_file: String,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ListenTlsArgs {
@@ -304,6 +557,16 @@ struct ListenTlsArgs {
   port: u16,
   cert_file: String,
   key_file: String,
+  /// Additional certificates, selected by the server name requested by the
+  /// client.
+  #[serde(default)]
+  certificates: Vec<SniCertificateArgs>,
+  #[serde(default)]
+  alpn_protocols: Vec<String>,
+  client_ca_file: Option<String>,
+  client_auth: Option<String>,
+  #[serde(default)]
+  reuse_port: bool,
 }
 
 fn op_listen_tls(
@@ -314,21 +577,29 @@ fn op_listen_tls(
 ) -> Result<JsonOp, OpError> {
   let args: ListenTlsArgs = serde_json::from_value(args)?;
   assert_eq!(args.transport, "tcp");
-
-  let cert_file = args.cert_file;
-  let key_file = args.key_file;
+  if !args.certificates.is_empty()
+    || !args.alpn_protocols.is_empty()
+    || args.client_ca_file.is_some()
+    || args.reuse_port
+  {
+    state.check_unstable("Deno.listenTls");
+  }
 
   state.check_net(&args.hostname, args.port)?;
-  state.check_read(Path::new(&cert_file))?;
-  state.check_read(Path::new(&key_file))?;
+  state.check_read(Path::new(&args.cert_file))?;
+  state.check_read(Path::new(&args.key_file))?;
+  for cert in &args.certificates {
+    state.check_read(Path::new(&cert.cert_file))?;
+    state.check_read(Path::new(&cert.key_file))?;
+  }
+  if let Some(path) = &args.client_ca_file {
+    state.check_read(Path::new(path))?;
+  }
 
-  let mut config = ServerConfig::new(NoClientAuth::new());
-  config
-    .set_single_cert(load_certs(&cert_file)?, load_keys(&key_file)?.remove(0))
-    .expect("invalid key or certificate");
+  let config = create_server_config(&args)?;
   let tls_acceptor = TlsAcceptor::from(Arc::new(config));
   let addr = resolve_addr(&args.hostname, args.port)?;
-  let std_listener = std::net::TcpListener::bind(&addr)?;
+  let std_listener = bind_tcp_listener(addr, args.reuse_port)?;
   let listener = TcpListener::from_std(std_listener)?;
   let local_addr = listener.local_addr()?;
   let tls_listener_resource = TlsListenerResource {
@@ -401,6 +672,8 @@ fn op_accept_tls(
       resource.tls_acceptor.clone()
     };
     let tls_stream = tls_acceptor.accept(tcp_stream).await?;
+    let (alpn_protocol, peer_certificates) =
+      session_info(tls_stream.get_ref().1);
     let rid = {
       let mut resource_table = resource_table.borrow_mut();
       resource_table.add(
@@ -421,9 +694,41 @@ fn op_accept_tls(
         "transport": "tcp",
         "hostname": remote_addr.ip().to_string(),
         "port": remote_addr.port()
-      }
+      },
+      "alpnProtocol": alpn_protocol,
+      "peerCertificates": peer_certificates,
     }))
   };
 
   Ok(JsonOp::Async(op.boxed_local()))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tls_fixture(name: &str) -> String {
+    let p = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+      .join("tests/tls")
+      .join(name);
+    p.to_str().unwrap().to_string()
+  }
+
+  #[test]
+  fn cert_to_pem_roundtrip() {
+    let loaded = load_certs(&tls_fixture("localhost.crt")).unwrap();
+    let pem = cert_to_pem(&loaded[0]);
+    assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+    assert!(pem.lines().all(|line| line.len() <= 64));
+    let reparsed = certs(&mut pem.as_bytes()).unwrap();
+    assert_eq!(reparsed, loaded);
+  }
+
+  #[test]
+  fn load_certified_key_invalid_key() {
+    let cert_file = tls_fixture("localhost.crt");
+    // A certificate is not a key.
+    let err = load_certified_key(&cert_file, &cert_file).err().unwrap();
+    assert_eq!(err.msg, "No keys found in key file");
+  }
+}
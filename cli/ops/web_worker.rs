@@ -1,10 +1,13 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
-use super::dispatch_json::{JsonOp, Value};
+use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::op_error::OpError;
 use crate::ops::json_op;
+use crate::ops::message_port::Message;
+use crate::ops::message_port::MessageArgs;
 use crate::state::State;
 use crate::web_worker::WebWorkerHandle;
 use crate::worker::WorkerEvent;
+use crate::worker::WorkerMessage;
 use deno_core::CoreIsolate;
 use deno_core::CoreIsolateState;
 use deno_core::ZeroCopyBuf;
@@ -21,15 +24,18 @@ pub fn web_worker_op<D>(
 ) -> Result<JsonOp, OpError>
 where
   D: Fn(
+    &mut CoreIsolateState,
     &mpsc::Sender<WorkerEvent>,
     Value,
     &mut [ZeroCopyBuf],
   ) -> Result<JsonOp, OpError>,
 {
-  move |_isolate_state: &mut CoreIsolateState,
+  move |isolate_state: &mut CoreIsolateState,
         args: Value,
         zero_copy: &mut [ZeroCopyBuf]|
-        -> Result<JsonOp, OpError> { dispatcher(&sender, args, zero_copy) }
+        -> Result<JsonOp, OpError> {
+    dispatcher(isolate_state, &sender, args, zero_copy)
+  }
 }
 
 pub fn web_worker_op2<D>(
@@ -80,17 +86,33 @@ pub fn init(
   );
 }
 
-/// Post message to host as guest worker
+#[derive(Deserialize)]
+struct PostMessageArgs {
+  message: Option<MessageArgs>,
+}
+
+/// Post message to host as guest worker. The message is either a structured
+/// clone or, if no message args are given, JSON in the zero copy buffer.
 fn op_worker_post_message(
+  isolate_state: &mut CoreIsolateState,
   sender: &mpsc::Sender<WorkerEvent>,
-  _args: Value,
+  args: Value,
   data: &mut [ZeroCopyBuf],
 ) -> Result<JsonOp, OpError> {
-  assert_eq!(data.len(), 1, "Invalid number of arguments");
-  let d = Vec::from(&*data[0]).into_boxed_slice();
+  let args: PostMessageArgs = serde_json::from_value(args)?;
+  let msg = match args.message {
+    Some(message) => {
+      let mut resource_table = isolate_state.resource_table.borrow_mut();
+      WorkerMessage::Cloned(Message::take(&mut resource_table, message)?)
+    }
+    None => match data {
+      [buf] => WorkerMessage::Json(Vec::from(&**buf).into_boxed_slice()),
+      _ => return Err(OpError::type_error("Missing message".to_string())),
+    },
+  };
   let mut sender = sender.clone();
   sender
-    .try_send(WorkerEvent::Message(d))
+    .try_send(WorkerEvent::Message(msg))
     .expect("Failed to post message to host");
   Ok(JsonOp::Sync(json!({})))
 }
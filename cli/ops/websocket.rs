@@ -0,0 +1,358 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use super::dispatch_json::{Deserialize, JsonOp, Value};
+use super::io::{StreamResource, StreamResourceHolder};
+use super::tls::create_client_config;
+use crate::op_error::OpError;
+use crate::resolve_addr::resolve_addr;
+use crate::state::State;
+use deno_core::CoreIsolate;
+use deno_core::CoreIsolateState;
+use deno_core::ZeroCopyBuf;
+use futures::future::poll_fn;
+use futures::future::FutureExt;
+use futures::ready;
+use futures::sink::Sink;
+use futures::stream::StreamExt;
+use http::header::HeaderValue;
+use http::header::SEC_WEBSOCKET_PROTOCOL;
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::server::{
+  ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use webpki::DNSNameRef;
+
+pub fn init(i: &mut CoreIsolate, s: &State) {
+  i.register_op("op_ws_create", s.stateful_json_op2(op_ws_create));
+  i.register_op("op_ws_accept", s.stateful_json_op2(op_ws_accept));
+  i.register_op("op_ws_send", s.stateful_json_op2(op_ws_send));
+  i.register_op("op_ws_close", s.stateful_json_op2(op_ws_close));
+  i.register_op("op_ws_next_event", s.stateful_json_op2(op_ws_next_event));
+}
+
+trait WsStream: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> WsStream for T {}
+
+/// A WebSocket connection, either opened with `op_ws_create` or accepted with
+/// `op_ws_accept`. Fragmented messages are reassembled, pings are answered
+/// and close frames are echoed by tungstenite.
+struct WsStreamResource {
+  stream: WebSocketStream<Box<dyn WsStream>>,
+}
+
+#[derive(Deserialize)]
+struct CreateArgs {
+  url: String,
+  protocols: Vec<String>,
+}
+
+fn op_ws_create(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: CreateArgs = serde_json::from_value(args)?;
+  let url = url::Url::parse(&args.url).map_err(OpError::from)?;
+
+  // Check scheme before asking for net permission
+  let scheme = url.scheme();
+  if scheme != "ws" && scheme != "wss" {
+    return Err(OpError::type_error(format!(
+      "scheme '{}' not supported",
+      scheme
+    )));
+  }
+
+  state.check_net_url(&url)?;
+
+  // `--cert` applies to WebSockets like it does to `fetch()`.
+  let tls_config = if scheme == "wss" {
+    let state_ = state.borrow();
+    let flags = &state_.global_state.flags;
+    Some(create_client_config(
+      flags.ca_file.as_deref(),
+      &flags.unsafely_ignore_certificate_errors,
+    )?)
+  } else {
+    None
+  };
+
+  let mut request =
+    url.as_str().into_client_request().map_err(OpError::from)?;
+  if !args.protocols.is_empty() {
+    let protocols = HeaderValue::from_str(&args.protocols.join(", "))
+      .map_err(|e| OpError::type_error(e.to_string()))?;
+    request
+      .headers_mut()
+      .insert(SEC_WEBSOCKET_PROTOCOL, protocols);
+  }
+
+  let resource_table = isolate_state.resource_table.clone();
+  let op = async move {
+    let host = url.host_str().unwrap_or("").to_string();
+    let port = url.port_or_known_default().unwrap();
+    let addr = resolve_addr(&host, port)?;
+    let tcp_stream = TcpStream::connect(&addr).await?;
+    let stream: Box<dyn WsStream> = match tls_config {
+      Some(config) => {
+        let tls_connector = TlsConnector::from(Arc::new(config));
+        let dnsname = DNSNameRef::try_from_ascii_str(&host).map_err(|_| {
+          OpError::type_error(format!("Invalid host: {}", host))
+        })?;
+        Box::new(tls_connector.connect(dnsname, tcp_stream).await?)
+      }
+      None => Box::new(tcp_stream),
+    };
+
+    let (stream, response) =
+      tokio_tungstenite::client_async(request, stream).await?;
+    let protocol = response
+      .headers()
+      .get(SEC_WEBSOCKET_PROTOCOL)
+      .and_then(|h| h.to_str().ok())
+      .unwrap_or("")
+      .to_string();
+
+    let rid = resource_table
+      .borrow_mut()
+      .add("webSocketStream", Box::new(WsStreamResource { stream }));
+    Ok(json!({
+      "rid": rid,
+      "protocol": protocol,
+    }))
+  };
+
+  Ok(JsonOp::Async(op.boxed_local()))
+}
+
+#[derive(Deserialize)]
+struct AcceptArgs {
+  rid: u32,
+  protocols: Vec<String>,
+}
+
+/// Performs the server side of the WebSocket handshake on a connection
+/// accepted from a `TcpListener` or a `TlsListenerResource`. The first
+/// subprotocol requested by the client that is contained in `protocols` is
+/// selected.
+fn op_ws_accept(
+  isolate_state: &mut CoreIsolateState,
+  state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  state.check_unstable("Deno.upgradeWebSocket");
+  let args: AcceptArgs = serde_json::from_value(args)?;
+
+  let stream: Box<dyn WsStream> = {
+    let mut resource_table = isolate_state.resource_table.borrow_mut();
+    let resource_holder = resource_table
+      .get_mut::<StreamResourceHolder>(args.rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let is_connection = match resource_holder.resource {
+      StreamResource::TcpStream(Some(_)) => true,
+      StreamResource::ServerTlsStream(_) => true,
+      _ => false,
+    };
+    if !is_connection {
+      return Err(OpError::bad_resource_id());
+    }
+    let resource = std::mem::replace(
+      &mut resource_holder.resource,
+      StreamResource::TcpStream(None),
+    );
+    resource_table.close(args.rid);
+    match resource {
+      StreamResource::TcpStream(Some(tcp_stream)) => Box::new(tcp_stream),
+      StreamResource::ServerTlsStream(tls_stream) => tls_stream,
+      _ => unreachable!(),
+    }
+  };
+
+  let resource_table = isolate_state.resource_table.clone();
+  let op = async move {
+    let mut path = String::new();
+    let mut selected_protocol = None;
+    let callback = |request: &Request, mut response: Response| {
+      path = request.uri().to_string();
+      let requested = request
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+      selected_protocol = requested
+        .split(',')
+        .map(str::trim)
+        .find(|p| args.protocols.iter().any(|supported| supported == *p))
+        .map(String::from);
+      if let Some(protocol) = &selected_protocol {
+        response.headers_mut().insert(
+          SEC_WEBSOCKET_PROTOCOL,
+          HeaderValue::from_str(protocol).unwrap(),
+        );
+      }
+      Ok::<_, ErrorResponse>(response)
+    };
+    let stream = tokio_tungstenite::accept_hdr_async(stream, callback).await?;
+
+    let rid = resource_table
+      .borrow_mut()
+      .add("webSocketStream", Box::new(WsStreamResource { stream }));
+    Ok(json!({
+      "rid": rid,
+      "path": path,
+      "protocol": selected_protocol.unwrap_or_default(),
+    }))
+  };
+
+  Ok(JsonOp::Async(op.boxed_local()))
+}
+
+/// Sends `message` once the WebSocket is ready to accept it and flushes it.
+fn send_message(
+  isolate_state: &mut CoreIsolateState,
+  rid: u32,
+  message: Message,
+) -> JsonOp {
+  let resource_table = isolate_state.resource_table.clone();
+  let mut message = Some(message);
+  let f = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let resource = resource_table
+      .get_mut::<WsStreamResource>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let mut stream = Pin::new(&mut resource.stream);
+    if message.is_some() {
+      ready!(stream.as_mut().poll_ready(cx))?;
+      stream.as_mut().start_send(message.take().unwrap())?;
+    }
+    ready!(stream.poll_flush(cx))?;
+    Poll::Ready(Ok(json!({})))
+  });
+  JsonOp::Async(f.boxed_local())
+}
+
+#[derive(Deserialize)]
+struct SendArgs {
+  rid: u32,
+  kind: String,
+  text: Option<String>,
+}
+
+fn op_ws_send(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: SendArgs = serde_json::from_value(args)?;
+  let data = || match zero_copy.len() {
+    0 => Vec::new(),
+    _ => Vec::from(&*zero_copy[0]),
+  };
+  let message = match args.kind.as_str() {
+    "text" => Message::Text(args.text.unwrap_or_default()),
+    "binary" => Message::Binary(data()),
+    "ping" => Message::Ping(data()),
+    "pong" => Message::Pong(data()),
+    kind => {
+      return Err(OpError::type_error(format!(
+        "Invalid message kind {}",
+        kind
+      )))
+    }
+  };
+  Ok(send_message(isolate_state, args.rid, message))
+}
+
+#[derive(Deserialize)]
+struct CloseArgs {
+  rid: u32,
+  code: Option<u16>,
+  reason: Option<String>,
+}
+
+/// Starts the closing handshake. The resource stays open until the peer
+/// acknowledges it, which `op_ws_next_event` reports as a "close" event.
+fn op_ws_close(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: CloseArgs = serde_json::from_value(args)?;
+  let reason = args.reason;
+  let frame = args.code.map(|code| CloseFrame {
+    code: CloseCode::from(code),
+    reason: Cow::from(reason.unwrap_or_default()),
+  });
+  Ok(send_message(isolate_state, args.rid, Message::Close(frame)))
+}
+
+#[derive(Deserialize)]
+struct NextEventArgs {
+  rid: u32,
+}
+
+fn op_ws_next_event(
+  isolate_state: &mut CoreIsolateState,
+  _state: &State,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<JsonOp, OpError> {
+  let args: NextEventArgs = serde_json::from_value(args)?;
+  let rid = args.rid;
+  let resource_table = isolate_state.resource_table.clone();
+  let f = poll_fn(move |cx| {
+    let mut resource_table = resource_table.borrow_mut();
+    let resource = resource_table
+      .get_mut::<WsStreamResource>(rid)
+      .ok_or_else(OpError::bad_resource_id)?;
+    let event = match ready!(resource.stream.poll_next_unpin(cx)) {
+      Some(Ok(Message::Text(text))) => json!({
+        "type": "string",
+        "data": text,
+      }),
+      Some(Ok(Message::Binary(data))) => json!({
+        "type": "binary",
+        "data": data,
+      }),
+      Some(Ok(Message::Ping(_))) => json!({ "type": "ping" }),
+      Some(Ok(Message::Pong(_))) => json!({ "type": "pong" }),
+      Some(Ok(Message::Close(Some(frame)))) => json!({
+        "type": "close",
+        "code": Into::<u16>::into(frame.code),
+        "reason": frame.reason.to_string(),
+      }),
+      // 1005: no status code was present.
+      Some(Ok(Message::Close(None))) => json!({
+        "type": "close",
+        "code": 1005,
+        "reason": "",
+      }),
+      Some(Err(err)) => json!({
+        "type": "error",
+        "message": err.to_string(),
+      }),
+      // 1006: the connection was closed abnormally, without a close frame.
+      None => json!({
+        "type": "closed",
+        "code": 1006,
+      }),
+    };
+    Poll::Ready(Ok(event))
+  });
+  Ok(JsonOp::Async(f.boxed_local()))
+}
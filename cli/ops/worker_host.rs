@@ -1,23 +1,36 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use super::dispatch_json::{Deserialize, JsonOp, Value};
 use crate::fmt_errors::JSError;
+use crate::fs::resolve_from_cwd;
 use crate::global_state::GlobalState;
 use crate::op_error::OpError;
 use crate::ops::io::get_stdio;
+use crate::ops::message_port::Message;
+use crate::ops::message_port::MessageArgs;
+use crate::permissions::PermissionState;
 use crate::permissions::Permissions;
+use crate::resource_limits::ResourceLimitError;
+use crate::resource_limits::ResourceLimits;
 use crate::startup_data;
 use crate::state::State;
 use crate::tokio_util::create_basic_runtime;
 use crate::web_worker::WebWorker;
 use crate::web_worker::WebWorkerHandle;
 use crate::worker::WorkerEvent;
+use crate::worker::WorkerMessage;
 use deno_core::CoreIsolate;
+use deno_core::CoreIsolateState;
 use deno_core::ErrBox;
 use deno_core::ModuleSpecifier;
+use deno_core::ResourceTable;
 use deno_core::ZeroCopyBuf;
 use futures::future::FutureExt;
+use std::collections::HashSet;
 use std::convert::From;
+use std::hash::Hash;
+use std::path::Path;
 use std::thread::JoinHandle;
+use std::time::Duration;
 
 pub fn init(i: &mut CoreIsolate, s: &State) {
   i.register_op("op_create_worker", s.stateful_json_op(op_create_worker));
@@ -27,11 +40,11 @@ pub fn init(i: &mut CoreIsolate, s: &State) {
   );
   i.register_op(
     "op_host_post_message",
-    s.stateful_json_op(op_host_post_message),
+    s.stateful_json_op2(op_host_post_message),
   );
   i.register_op(
     "op_host_get_message",
-    s.stateful_json_op(op_host_get_message),
+    s.stateful_json_op2(op_host_get_message),
   );
 }
 
@@ -42,6 +55,7 @@ fn create_web_worker(
   permissions: Permissions,
   specifier: ModuleSpecifier,
   has_deno_namespace: bool,
+  resource_limits: ResourceLimits,
 ) -> Result<WebWorker, ErrBox> {
   let state =
     State::new_for_worker(global_state, Some(permissions), specifier)?;
@@ -51,6 +65,7 @@ fn create_web_worker(
     startup_data::deno_isolate_init(),
     state,
     has_deno_namespace,
+    resource_limits,
   );
 
   if has_deno_namespace {
@@ -83,6 +98,7 @@ fn run_worker_thread(
   specifier: ModuleSpecifier,
   has_deno_namespace: bool,
   maybe_source_code: Option<String>,
+  resource_limits: ResourceLimits,
 ) -> Result<(JoinHandle<()>, WebWorkerHandle), ErrBox> {
   let (handle_sender, handle_receiver) =
     std::sync::mpsc::sync_channel::<Result<WebWorkerHandle, ErrBox>>(1);
@@ -101,6 +117,7 @@ fn run_worker_thread(
       permissions,
       specifier.clone(),
       has_deno_namespace,
+      resource_limits,
     );
 
     if let Err(err) = result {
@@ -140,6 +157,11 @@ fn run_worker_thread(
     };
 
     if let Err(e) = result {
+      // Report the exceeded limit rather than the termination it caused.
+      let e = match worker.thread_safe_handle().take_limit_error() {
+        Some(limit_error) => limit_error.into(),
+        None => e,
+      };
       let mut sender = worker.internal_channels.sender.clone();
       sender
         .try_send(WorkerEvent::TerminalError(e))
@@ -168,6 +190,153 @@ struct CreateWorkerArgs {
   has_source_code: bool,
   source_code: String,
   use_deno_namespace: bool,
+  permissions: Option<WorkerPermissionsArgs>,
+  resource_limits: Option<ResourceLimitsArgs>,
+}
+
+/// Permissions requested for a worker through `deno.permissions`. Omitted
+/// permissions are not granted.
+#[derive(Deserialize)]
+struct WorkerPermissionsArgs {
+  read: Option<PermissionArg>,
+  write: Option<PermissionArg>,
+  net: Option<PermissionArg>,
+  env: Option<PermissionArg>,
+  run: Option<PermissionArg>,
+  plugin: Option<PermissionArg>,
+  hrtime: Option<PermissionArg>,
+}
+
+/// A single worker permission: `"inherit"`, a boolean, or for `read`,
+/// `write` and `net` a list of allowed paths or hosts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PermissionArg {
+  Bool(bool),
+  List(Vec<String>),
+  Keyword(String),
+}
+
+fn invalid_permission_arg(name: &str) -> OpError {
+  OpError::type_error(format!(
+    "Expected \"inherit\" or a boolean{} for the \"{}\" permission of a worker",
+    if ["read", "write", "net"].contains(&name) {
+      " or a list"
+    } else {
+      ""
+    },
+    name
+  ))
+}
+
+fn fork_permission(
+  name: &str,
+  arg: Option<PermissionArg>,
+  parent: PermissionState,
+) -> Result<bool, OpError> {
+  match arg {
+    None => Ok(false),
+    Some(PermissionArg::Bool(value)) => Ok(value),
+    Some(PermissionArg::Keyword(k)) if k == "inherit" => Ok(parent.is_allow()),
+    _ => Err(invalid_permission_arg(name)),
+  }
+}
+
+fn fork_allowlist_permission<T: Clone + Eq + Hash>(
+  name: &str,
+  arg: Option<PermissionArg>,
+  parent: PermissionState,
+  parent_allowlist: &HashSet<T>,
+  parse_entry: impl Fn(&str) -> Result<T, OpError>,
+) -> Result<(bool, HashSet<T>), OpError> {
+  match arg {
+    Some(PermissionArg::List(entries)) => {
+      let allowlist = entries
+        .iter()
+        .map(|entry| parse_entry(entry))
+        .collect::<Result<_, _>>()?;
+      Ok((false, allowlist))
+    }
+    Some(PermissionArg::Keyword(k)) if k == "inherit" => {
+      Ok((parent.is_allow(), parent_allowlist.clone()))
+    }
+    arg => Ok((fork_permission(name, arg, parent)?, HashSet::new())),
+  }
+}
+
+/// Builds the permissions of a worker from those of its parent. Fails if
+/// the worker asks for anything its parent wasn't granted.
+fn fork_worker_permissions(
+  parent: &Permissions,
+  args: WorkerPermissionsArgs,
+) -> Result<Permissions, OpError> {
+  let resolve_path =
+    |path: &str| resolve_from_cwd(Path::new(path)).map_err(OpError::from);
+  let (allow_read, read_allowlist) = fork_allowlist_permission(
+    "read",
+    args.read,
+    parent.allow_read,
+    &parent.read_allowlist,
+    resolve_path,
+  )?;
+  let (allow_write, write_allowlist) = fork_allowlist_permission(
+    "write",
+    args.write,
+    parent.allow_write,
+    &parent.write_allowlist,
+    resolve_path,
+  )?;
+  let (allow_net, net_allowlist) = fork_allowlist_permission(
+    "net",
+    args.net,
+    parent.allow_net,
+    &parent.net_allowlist,
+    |host| Ok(host.to_string()),
+  )?;
+  parent.fork(
+    allow_read,
+    read_allowlist,
+    allow_write,
+    write_allowlist,
+    allow_net,
+    net_allowlist,
+    fork_permission("env", args.env, parent.allow_env)?,
+    fork_permission("run", args.run, parent.allow_run)?,
+    fork_permission("plugin", args.plugin, parent.allow_plugin)?,
+    fork_permission("hrtime", args.hrtime, parent.allow_hrtime)?,
+  )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceLimitsArgs {
+  max_heap_size_mb: Option<u64>,
+  max_cpu_time_ms: Option<u64>,
+  max_wall_time_ms: Option<u64>,
+  max_resources: Option<usize>,
+}
+
+impl ResourceLimitsArgs {
+  fn into_limits(self) -> Result<ResourceLimits, OpError> {
+    let max_heap_size = match self.max_heap_size_mb {
+      Some(mb) => match ResourceLimits::heap_size_from_mb(mb) {
+        Some(max_heap_size) => Some(max_heap_size),
+        None => {
+          return Err(OpError::type_error(format!(
+            "maxHeapSizeMb is too large: {}",
+            mb
+          )))
+        }
+      },
+      None => None,
+    };
+    Ok(ResourceLimits {
+      max_heap_size,
+      max_cpu_time: self.max_cpu_time_ms.map(Duration::from_millis),
+      max_wall_time: self.max_wall_time_ms.map(Duration::from_millis),
+      max_resources: self.max_resources,
+    })
+  }
 }
 
 /// Create worker as the host
@@ -189,10 +358,25 @@ fn op_create_worker(
   if use_deno_namespace {
     state.check_unstable("Worker.deno");
   }
+  if args.permissions.is_some() {
+    state.check_unstable("Worker.deno.permissions");
+  }
+  let resource_limits = match args.resource_limits {
+    Some(resource_limits) => {
+      state.check_unstable("Worker.resourceLimits");
+      resource_limits.into_limits()?
+    }
+    None => ResourceLimits::default(),
+  };
   let parent_state = state.clone();
   let mut state = state.borrow_mut();
   let global_state = state.global_state.clone();
-  let permissions = state.permissions.clone();
+  let permissions = match args.permissions {
+    Some(permissions_args) => {
+      fork_worker_permissions(&state.permissions, permissions_args)?
+    }
+    None => state.permissions.clone(),
+  };
   let worker_id = state.next_worker_id;
   state.next_worker_id += 1;
   drop(state);
@@ -208,6 +392,7 @@ fn op_create_worker(
     module_specifier,
     use_deno_namespace,
     maybe_source_code,
+    resource_limits,
   )
   .map_err(|e| OpError::other(e.to_string()))?;
   // At this point all interactions with worker happen using thread
@@ -240,9 +425,20 @@ fn op_host_terminate_worker(
   Ok(JsonOp::Sync(json!({})))
 }
 
-fn serialize_worker_event(event: WorkerEvent) -> Value {
-  match event {
-    WorkerEvent::Message(buf) => json!({ "type": "msg", "data": buf }),
+fn serialize_worker_event(
+  event: WorkerEvent,
+  resource_table: &mut ResourceTable,
+) -> Result<Value, OpError> {
+  let value = match event {
+    WorkerEvent::Message(WorkerMessage::Cloned(msg)) => {
+      json!({ "type": "msg", "message": msg.add_to(resource_table) })
+    }
+    // A worker can post a raw buffer, but only Rust hosts read those.
+    WorkerEvent::Message(WorkerMessage::Json(_)) => {
+      return Err(OpError::type_error(
+        "Worker posted a message that is not a structured clone".to_string(),
+      ))
+    }
     WorkerEvent::TerminalError(error) => {
       let mut serialized_error = json!({
         "type": "terminalError",
@@ -251,6 +447,17 @@ fn serialize_worker_event(event: WorkerEvent) -> Value {
         }
       });
 
+      if let Some(limit_error) = error.downcast_ref::<ResourceLimitError>() {
+        return Ok(json!({
+          "type": "terminalError",
+          "error": {
+            "message": limit_error.to_string(),
+            "name": "ResourceLimitError",
+            "limit": limit_error.limit_name(),
+          }
+        }));
+      }
+
       if let Ok(js_error) = error.downcast::<JSError>() {
         serialized_error = json!({
           "type": "terminalError",
@@ -287,11 +494,13 @@ fn serialize_worker_event(event: WorkerEvent) -> Value {
 
       serialized_error
     }
-  }
+  };
+  Ok(value)
 }
 
 /// Get message from guest worker as host
 fn op_host_get_message(
+  isolate_state: &mut CoreIsolateState,
   state: &State,
   args: Value,
   _data: &mut [ZeroCopyBuf],
@@ -305,6 +514,7 @@ fn op_host_get_message(
     worker_handle.clone()
   };
   let state_ = state.clone();
+  let resource_table = isolate_state.resource_table.clone();
   let op = async move {
     let response = match worker_handle.get_event().await? {
       Some(event) => {
@@ -318,7 +528,7 @@ fn op_host_get_message(
             join_handle.join().expect("Worker thread panicked");
           }
         }
-        serialize_worker_event(event)
+        serialize_worker_event(event, &mut resource_table.borrow_mut())?
       }
       None => {
         // Worker shuts down
@@ -339,23 +549,34 @@ fn op_host_get_message(
   Ok(JsonOp::Async(op.boxed_local()))
 }
 
+#[derive(Deserialize)]
+struct HostPostMessageArgs {
+  id: i32,
+  message: MessageArgs,
+}
+
 /// Post message to guest worker as host
 fn op_host_post_message(
+  isolate_state: &mut CoreIsolateState,
   state: &State,
   args: Value,
-  data: &mut [ZeroCopyBuf],
+  _data: &mut [ZeroCopyBuf],
 ) -> Result<JsonOp, OpError> {
-  assert_eq!(data.len(), 1, "Invalid number of arguments");
-  let args: WorkerArgs = serde_json::from_value(args)?;
+  let args: HostPostMessageArgs = serde_json::from_value(args)?;
   let id = args.id as u32;
-  let msg = Vec::from(&*data[0]).into_boxed_slice();
+  let msg = {
+    let mut resource_table = isolate_state.resource_table.borrow_mut();
+    Message::take(&mut resource_table, args.message)?
+  };
 
   debug!("post message to worker {}", id);
   let state = state.borrow();
-  let (_, worker_handle) =
-    state.workers.get(&id).expect("No worker handle found");
+  let (_, worker_handle) = state
+    .workers
+    .get(&id)
+    .ok_or_else(OpError::bad_resource_id)?;
   worker_handle
-    .post_message(msg)
+    .post_message(WorkerMessage::Cloned(msg))
     .map_err(|e| OpError::other(e.to_string()))?;
   Ok(JsonOp::Sync(json!({})))
 }
@@ -62,11 +62,19 @@ impl PermissionState {
     *self
   }
 
-  pub fn fork(self, value: bool) -> Result<PermissionState, OpError> {
-    if value && self == PermissionState::Deny {
-      Err(OpError::permission_denied(
-        "Arguments escalate parent permissions.".to_string(),
-      ))
+  /// Returns the state of a child permission that is granted if `value` is
+  /// true. Only permissions that are granted to the parent can be granted to
+  /// the child.
+  pub fn fork(
+    self,
+    name: &str,
+    value: bool,
+  ) -> Result<PermissionState, OpError> {
+    if value && self != PermissionState::Allow {
+      Err(OpError::permission_denied(format!(
+        "Arguments escalate parent permissions. Parent Permissions don't grant \"{}\"",
+        name
+      )))
     } else if value {
       Ok(PermissionState::Allow)
     } else {
@@ -427,41 +435,66 @@ impl Permissions {
     allow_plugin: bool,
     allow_hrtime: bool,
   ) -> Result<Permissions, OpError> {
-    let allow_read = self.allow_read.fork(allow_read)?;
-    let allow_write = self.allow_write.fork(allow_write)?;
-    let allow_net = self.allow_net.fork(allow_net)?;
-    let allow_env = self.allow_env.fork(allow_env)?;
-    let allow_run = self.allow_run.fork(allow_run)?;
-    let allow_plugin = self.allow_plugin.fork(allow_plugin)?;
-    let allow_hrtime = self.allow_hrtime.fork(allow_hrtime)?;
-    if !(read_allowlist.is_subset(&self.read_allowlist)) {
-      Err(OpError::permission_denied(format!(
-        "Arguments escalate parent permissions. Parent Permissions have only {:?} in `read_allowlist`",
-        self.read_allowlist
-      )))
-    } else if !(write_allowlist.is_subset(&self.write_allowlist)) {
-      Err(OpError::permission_denied(format!(
-        "Arguments escalate parent permissions. Parent Permissions have only {:?} in `write_allowlist`",
-        self.write_allowlist
-      )))
-    } else if !(net_allowlist.is_subset(&self.net_allowlist)) {
-      Err(OpError::permission_denied(format!(
-        "Arguments escalate parent permissions. Parent Permissions have only {:?} in `net_allowlist`",
-        self.net_allowlist
-      )))
-    } else {
-      Ok(Permissions {
-        allow_read,
-        read_allowlist,
-        allow_write,
-        write_allowlist,
-        allow_net,
-        net_allowlist,
-        allow_env,
-        allow_run,
-        allow_plugin,
-        allow_hrtime,
-      })
+    let allow_read = self.allow_read.fork("read", allow_read)?;
+    let allow_write = self.allow_write.fork("write", allow_write)?;
+    let allow_net = self.allow_net.fork("net", allow_net)?;
+    let allow_env = self.allow_env.fork("env", allow_env)?;
+    let allow_run = self.allow_run.fork("run", allow_run)?;
+    let allow_plugin = self.allow_plugin.fork("plugin", allow_plugin)?;
+    let allow_hrtime = self.allow_hrtime.fork("hrtime", allow_hrtime)?;
+    if let Some(path) = read_allowlist
+      .iter()
+      .find(|path| !self.get_state_read(&Some(path)).is_allow())
+    {
+      return Err(OpError::permission_denied(format!(
+        "Arguments escalate parent permissions. Parent Permissions don't grant read access to \"{}\"",
+        path.display()
+      )));
+    }
+    if let Some(path) = write_allowlist
+      .iter()
+      .find(|path| !self.get_state_write(&Some(path)).is_allow())
+    {
+      return Err(OpError::permission_denied(format!(
+        "Arguments escalate parent permissions. Parent Permissions don't grant write access to \"{}\"",
+        path.display()
+      )));
+    }
+    if let Some(host) = net_allowlist
+      .iter()
+      .find(|host| !self.get_state_net_allowlist_entry(host).is_allow())
+    {
+      return Err(OpError::permission_denied(format!(
+        "Arguments escalate parent permissions. Parent Permissions don't grant network access to \"{}\"",
+        host
+      )));
+    }
+    Ok(Permissions {
+      allow_read,
+      read_allowlist,
+      allow_write,
+      write_allowlist,
+      allow_net,
+      net_allowlist,
+      allow_env,
+      allow_run,
+      allow_plugin,
+      allow_hrtime,
+    })
+  }
+
+  /// Returns the state of a `--allow-net` allowlist entry, which is either a
+  /// host or a "host:port" pair.
+  fn get_state_net_allowlist_entry(&self, entry: &str) -> PermissionState {
+    if self.net_allowlist.contains(entry) {
+      return PermissionState::Allow;
+    }
+    match entry.rfind(':') {
+      Some(i) => match entry[i + 1..].parse::<u16>() {
+        Ok(port) => self.get_state_net(&entry[..i], Some(port)),
+        Err(_) => self.get_state_net(entry, None),
+      },
+      None => self.get_state_net(entry, None),
     }
   }
 }
@@ -961,6 +994,11 @@ mod tests {
   fn test_fork() {
     let guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
     let perms0 = Permissions::from_flags(&Flags {
+      allow_read: true,
+      allow_write: true,
+      allow_net: true,
+      allow_env: true,
+      allow_run: true,
       ..Default::default()
     });
     set_prompt_result(true);
@@ -1023,4 +1061,46 @@ mod tests {
     );
     drop(guard);
   }
+
+  #[test]
+  fn test_fork_escalation() {
+    let perms0 = Permissions::from_flags(&Flags {
+      read_allowlist: vec![PathBuf::from("/a/specific")],
+      net_allowlist: svec!["deno.land", "127.0.0.1:4545"],
+      ..Default::default()
+    });
+    let fork = |allow_read, read: &[&str], net: &[&str], allow_env| {
+      perms0.fork(
+        allow_read,
+        read.iter().map(PathBuf::from).collect(),
+        false,
+        HashSet::new(),
+        false,
+        net.iter().map(|h| h.to_string()).collect(),
+        allow_env,
+        false,
+        false,
+        false,
+      )
+    };
+
+    let perms1 = fork(false, &["/a/specific/dir"], &["deno.land:443"], false)
+      .expect("Testing expect");
+    assert!(perms1.check_read(Path::new("/a/specific/dir/file")).is_ok());
+    assert!(perms1.check_read(Path::new("/a/specific/file")).is_err());
+    assert!(perms1.check_net("deno.land", 443).is_ok());
+    assert!(perms1.check_net("deno.land", 80).is_err());
+    assert!(perms1.check_env().is_err());
+    assert!(fork(false, &[], &["127.0.0.1:4545"], false).is_ok());
+
+    // The parent only prompts for these, so they can't be granted.
+    assert!(fork(true, &[], &[], false).is_err());
+    assert!(fork(false, &[], &[], true).is_err());
+    // Paths and hosts outside of the parent's allowlists.
+    assert!(fork(false, &["/a"], &[], false).is_err());
+    assert!(fork(false, &["/b/c"], &[], false).is_err());
+    assert!(fork(false, &[], &["127.0.0.1"], false).is_err());
+    assert!(fork(false, &[], &["127.0.0.1:4546"], false).is_err());
+    assert!(fork(false, &[], &["github.com"], false).is_err());
+  }
 }
@@ -0,0 +1,119 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! CPU profiles and heap snapshots taken through an in-process inspector
+//! session. The output files use the same formats as the Chrome DevTools
+//! "Performance" and "Memory" panels, so they can be loaded there directly.
+
+use crate::inspector::DenoInspector;
+use crate::inspector::InProcessInspectorSession;
+use crate::op_error::OpError;
+use deno_core::ErrBox;
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Records a CPU profile from the moment it is started until `stop()` is
+/// called.
+pub struct CpuProfiler {
+  session: Box<InProcessInspectorSession>,
+  out_dir: PathBuf,
+}
+
+impl CpuProfiler {
+  pub fn start(
+    inspector: &mut DenoInspector,
+    out_dir: PathBuf,
+  ) -> Result<Self, ErrBox> {
+    let mut session = inspector.create_in_process_session();
+    session.post_message("Profiler.enable", None)?;
+    session.post_message("Profiler.start", None)?;
+    Ok(Self { session, out_dir })
+  }
+
+  /// Stops profiling and writes the `.cpuprofile` file, returning its path.
+  pub fn stop(mut self) -> Result<PathBuf, ErrBox> {
+    let mut result = self.session.post_message("Profiler.stop", None)?;
+    let profile = result["profile"].take();
+    if profile.is_null() {
+      return Err(
+        OpError::other("Inspector did not return a CPU profile".to_string())
+          .into(),
+      );
+    }
+
+    fs::create_dir_all(&self.out_dir)?;
+    let path = self.out_dir.join(profile_file_name("CPU", "cpuprofile"));
+    let mut writer = BufWriter::new(File::create(&path)?);
+    serde_json::to_writer(&mut writer, &profile)?;
+    writer.flush()?;
+    Ok(path)
+  }
+}
+
+/// Takes a heap snapshot and writes it to a `.heapsnapshot` file in
+/// `out_dir`, returning its path.
+pub fn write_heap_snapshot(
+  inspector: &mut DenoInspector,
+  out_dir: &Path,
+) -> Result<PathBuf, ErrBox> {
+  let mut session = inspector.create_in_process_session();
+  session.post_message(
+    "HeapProfiler.takeHeapSnapshot",
+    Some(json!({ "reportProgress": false })),
+  )?;
+
+  fs::create_dir_all(out_dir)?;
+  let path = out_dir.join(profile_file_name("Heap", "heapsnapshot"));
+  let mut writer = BufWriter::new(File::create(&path)?);
+  for notification in session.take_notifications() {
+    if notification["method"] != "HeapProfiler.addHeapSnapshotChunk" {
+      continue;
+    }
+    if let Some(chunk) = notification["params"]["chunk"].as_str() {
+      writer.write_all(chunk.as_bytes())?;
+    }
+  }
+  writer.flush()?;
+  Ok(path)
+}
+
+/// Returns a file name like `CPU.1593012345678.4242.0.cpuprofile`, made
+/// unique by the timestamp, the process id and a per-process sequence number.
+fn profile_file_name(prefix: &str, extension: &str) -> String {
+  static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+  let timestamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis())
+    .unwrap_or(0);
+  format!(
+    "{}.{}.{}.{}.{}",
+    prefix,
+    timestamp,
+    process::id(),
+    SEQUENCE.fetch_add(1, Ordering::SeqCst),
+    extension
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_profile_file_name() {
+    let a = profile_file_name("CPU", "cpuprofile");
+    let b = profile_file_name("CPU", "cpuprofile");
+    assert!(a.starts_with("CPU."));
+    assert!(a.ends_with(".cpuprofile"));
+    assert!(a.contains(&format!(".{}.", process::id())));
+    assert_ne!(a, b);
+  }
+}
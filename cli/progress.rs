@@ -0,0 +1,179 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Progress of remote module downloads. When stderr is a terminal a single
+//! line with the number of finished downloads and the downloaded bytes is
+//! redrawn in place, otherwise every download is logged on its own line.
+use crate::colors;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use url::Url;
+
+/// Longest URL that is shown, so that the progress line fits in a terminal.
+const MAX_URL_WIDTH: usize = 50;
+
+#[derive(Default)]
+struct ProgressState {
+  draw: bool,
+  started: usize,
+  finished: usize,
+  bytes: usize,
+  current: String,
+  visible: bool,
+}
+
+impl ProgressState {
+  fn line(&self) -> String {
+    let url = if self.current.chars().count() > MAX_URL_WIDTH {
+      let skip = self.current.chars().count() - MAX_URL_WIDTH + 3;
+      format!("...{}", self.current.chars().skip(skip).collect::<String>())
+    } else {
+      self.current.clone()
+    };
+    format!(
+      "{} [{}/{}] {} {}",
+      colors::green("Download"),
+      self.finished,
+      self.started,
+      crate::human_size(self.bytes as f64),
+      url
+    )
+  }
+
+  fn redraw(&mut self) {
+    let mut stderr = std::io::stderr();
+    if self.finished < self.started {
+      let _ = write!(stderr, "\r\x1b[K{}", self.line());
+      self.visible = true;
+    } else {
+      self.erase();
+    }
+    let _ = stderr.flush();
+  }
+
+  fn erase(&mut self) {
+    if self.visible {
+      eprint!("\r\x1b[K");
+      self.visible = false;
+    }
+  }
+}
+
+/// Shared by all clones of a `SourceFileFetcher`.
+#[derive(Clone, Default)]
+pub struct DownloadProgress(Arc<Mutex<ProgressState>>);
+
+impl DownloadProgress {
+  pub fn new() -> Self {
+    let state = ProgressState {
+      draw: atty::is(atty::Stream::Stderr) && log_enabled!(log::Level::Info),
+      ..ProgressState::default()
+    };
+    Self(Arc::new(Mutex::new(state)))
+  }
+
+  /// Starts a download, which is finished when the returned guard is dropped.
+  pub fn start(&self, url: &Url) -> DownloadGuard {
+    let guard = DownloadGuard {
+      progress: self.clone(),
+      bytes: 0,
+    };
+    let mut state = self.0.lock().unwrap();
+    if !state.draw {
+      drop(state);
+      info!("{} {}", colors::green("Download"), url);
+      return guard;
+    }
+    state.started += 1;
+    state.current = url.to_string();
+    state.redraw();
+    guard
+  }
+
+  /// The line is erased once no download is in progress anymore.
+  fn finish(&self, bytes: usize) {
+    let mut state = self.0.lock().unwrap();
+    if state.draw {
+      state.finished += 1;
+      state.bytes += bytes;
+      state.redraw();
+    }
+  }
+
+  /// Prints a message on its own line, above the progress line.
+  pub fn println(&self, message: &str) {
+    let mut state = self.0.lock().unwrap();
+    if !state.draw {
+      drop(state);
+      info!("{}", message);
+      return;
+    }
+    state.erase();
+    eprintln!("{}", message);
+    state.redraw();
+  }
+}
+
+/// Marks a download as finished when it is dropped, whether it succeeded,
+/// failed or was cancelled.
+pub struct DownloadGuard {
+  progress: DownloadProgress,
+  bytes: usize,
+}
+
+impl DownloadGuard {
+  /// Sets the number of downloaded bytes that are added to the progress.
+  pub fn set_bytes(&mut self, bytes: usize) {
+    self.bytes = bytes;
+  }
+}
+
+impl Drop for DownloadGuard {
+  fn drop(&mut self) {
+    self.progress.finish(self.bytes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_progress_line() {
+    let state = ProgressState {
+      draw: true,
+      started: 3,
+      finished: 1,
+      bytes: 2048,
+      current: "https://deno.land/std/http/server.ts".to_string(),
+      visible: false,
+    };
+    let line = colors::strip_ansi_codes(&state.line()).to_string();
+    assert_eq!(
+      line,
+      "Download [1/3] 2 KB https://deno.land/std/http/server.ts"
+    );
+
+    let state = ProgressState {
+      current: format!("https://deno.land/{}.ts", "a".repeat(100)),
+      ..state
+    };
+    let line = colors::strip_ansi_codes(&state.line()).to_string();
+    assert!(line.ends_with(&format!("...{}.ts", "a".repeat(44))));
+  }
+
+  #[test]
+  fn test_download_guard() {
+    let progress = DownloadProgress(Arc::new(Mutex::new(ProgressState {
+      draw: true,
+      ..ProgressState::default()
+    })));
+    let url = Url::parse("https://deno.land/std/http/server.ts").unwrap();
+    let mut guard = progress.start(&url);
+    guard.set_bytes(10);
+    let _other = progress.start(&url);
+    drop(guard);
+    let state = progress.0.lock().unwrap();
+    assert_eq!((state.started, state.finished, state.bytes), (2, 1, 10));
+  }
+}
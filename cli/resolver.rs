@@ -0,0 +1,101 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+use crate::import_map::ImportMap;
+use deno_core::ErrBox;
+use deno_core::ModuleSpecifier;
+use std::sync::Arc;
+
+/// Resolves import specifiers, applying the import map passed with
+/// `--importmap` before falling back to `ModuleSpecifier::resolve_import`.
+///
+/// It is owned by `GlobalState`, so that the main worker, web workers,
+/// dynamic imports, `Deno.compile`/`Deno.bundle` and subcommands like
+/// `deno info` and `deno doc` all resolve modules the same way.
+#[derive(Clone, Default)]
+pub struct Resolver {
+  maybe_import_map: Option<Arc<ImportMap>>,
+}
+
+impl Resolver {
+  pub fn new(maybe_import_map: Option<ImportMap>) -> Self {
+    Self {
+      maybe_import_map: maybe_import_map.map(Arc::new),
+    }
+  }
+
+  pub fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &str,
+  ) -> Result<ModuleSpecifier, ErrBox> {
+    if let Some(import_map) = &self.maybe_import_map {
+      if let Some(resolved) = import_map.resolve(specifier, referrer)? {
+        return Ok(resolved);
+      }
+    }
+    let resolved = ModuleSpecifier::resolve_import(specifier, referrer)?;
+    Ok(resolved)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn resolver() -> Resolver {
+    let json = r#"{
+      "imports": {
+        "moment": "./moment/moment.ts",
+        "moment/": "./moment/"
+      },
+      "scopes": {
+        "scope/": {
+          "moment": "./scoped_moment.ts"
+        }
+      }
+    }"#;
+    let import_map =
+      ImportMap::from_json("file:///project/import_map.json", json).unwrap();
+    Resolver::new(Some(import_map))
+  }
+
+  #[test]
+  fn test_resolve_with_import_map() {
+    let resolver = resolver();
+    let referrer = "file:///project/main.ts";
+    let resolve =
+      |specifier| resolver.resolve(specifier, referrer).unwrap().to_string();
+    assert_eq!(resolve("moment"), "file:///project/moment/moment.ts");
+    assert_eq!(
+      resolve("moment/other.ts"),
+      "file:///project/moment/other.ts"
+    );
+    // Specifiers that aren't mapped fall back to regular resolution.
+    assert_eq!(resolve("./util.ts"), "file:///project/util.ts");
+    assert_eq!(
+      resolve("https://deno.land/std/http/server.ts"),
+      "https://deno.land/std/http/server.ts"
+    );
+    assert!(resolver.resolve("lodash", referrer).is_err());
+  }
+
+  #[test]
+  fn test_resolve_scopes() {
+    let resolver = resolver();
+    let resolved = resolver
+      .resolve("moment", "file:///project/scope/scoped.ts")
+      .unwrap();
+    assert_eq!(resolved.to_string(), "file:///project/scoped_moment.ts");
+  }
+
+  #[test]
+  fn test_resolve_without_import_map() {
+    let resolver = Resolver::default();
+    let resolved = resolver
+      .resolve("./moment/moment.ts", "file:///project/main.ts")
+      .unwrap();
+    assert_eq!(resolved.to_string(), "file:///project/moment/moment.ts");
+    assert!(resolver
+      .resolve("moment", "file:///project/main.ts")
+      .is_err());
+  }
+}
@@ -0,0 +1,393 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Limits on the resources a worker may consume. When a limit is exceeded
+//! the isolate is terminated with a `ResourceLimitError`: the host of a
+//! `WebWorker` receives it as a terminal error, while the main worker fails
+//! with an error that describes it for the program.
+
+use deno_core::v8;
+use deno_core::CoreIsolate;
+use deno_core::HeapLimits;
+use futures::task::AtomicWaker;
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How often the watchdog thread checks the time limits of an isolate.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceLimits {
+  /// Maximum size of the V8 heap, in bytes.
+  pub max_heap_size: Option<usize>,
+  /// Maximum CPU time spent by the worker thread.
+  pub max_cpu_time: Option<Duration>,
+  /// Maximum time the worker may be alive for, measured from its creation.
+  pub max_wall_time: Option<Duration>,
+  /// Maximum number of simultaneously open resources.
+  pub max_resources: Option<usize>,
+}
+
+impl ResourceLimits {
+  /// Converts a heap size limit in megabytes to bytes, or returns `None` if
+  /// it doesn't fit in a `usize`.
+  pub fn heap_size_from_mb(mb: u64) -> Option<usize> {
+    let bytes = mb.checked_mul(1024 * 1024)?;
+    if bytes > std::usize::MAX as u64 {
+      return None;
+    }
+    Some(bytes as usize)
+  }
+
+  pub fn heap_limits(&self) -> Option<HeapLimits> {
+    self.max_heap_size.map(|max| HeapLimits { initial: 0, max })
+  }
+
+  /// Returns true if the CPU time or wall-clock time is limited, which
+  /// requires a watchdog thread to enforce.
+  pub fn has_time_limits(&self) -> bool {
+    self.max_cpu_time.is_some() || self.max_wall_time.is_some()
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceLimitError {
+  HeapSize(usize),
+  CpuTime(Duration),
+  WallTime(Duration),
+  Resources(usize),
+}
+
+impl ResourceLimitError {
+  /// Name of the limit, matching the option of the `resourceLimits` object
+  /// passed to the `Worker` constructor.
+  pub fn limit_name(&self) -> &'static str {
+    use ResourceLimitError::*;
+    match self {
+      HeapSize(_) => "maxHeapSizeMb",
+      CpuTime(_) => "maxCpuTimeMs",
+      WallTime(_) => "maxWallTimeMs",
+      Resources(_) => "maxResources",
+    }
+  }
+}
+
+impl ResourceLimitError {
+  /// Describes the error for `isolate`, the kind of isolate that exceeded
+  /// the limit, like "Worker".
+  pub fn message(&self, isolate: &str) -> String {
+    use ResourceLimitError::*;
+    match self {
+      HeapSize(max) => format!(
+        "{} exceeded its heap size limit of {} MB",
+        isolate,
+        max / (1024 * 1024)
+      ),
+      CpuTime(max) => format!(
+        "{} exceeded its CPU time limit of {} ms",
+        isolate,
+        max.as_millis()
+      ),
+      WallTime(max) => format!(
+        "{} exceeded its wall-clock time limit of {} ms",
+        isolate,
+        max.as_millis()
+      ),
+      Resources(max) => {
+        format!("{} exceeded its limit of {} open resources", isolate, max)
+      }
+    }
+  }
+}
+
+impl fmt::Display for ResourceLimitError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.message("Worker"))
+  }
+}
+
+impl Error for ResourceLimitError {}
+
+/// Terminates an isolate once it exceeds one of its limits.
+pub trait LimitHandle: Send + Sync + 'static {
+  fn isolate_handle(&self) -> &v8::IsolateHandle;
+
+  /// Terminates the isolate and records `error` to be reported. Only the
+  /// first limit that is exceeded is reported.
+  fn exceed_limit(&self, error: ResourceLimitError);
+}
+
+/// `LimitHandle` of an isolate that isn't owned by a `WebWorker`, like the
+/// main isolate. The worker polls it to stop with the recorded error.
+pub struct IsolateLimitHandle {
+  isolate_handle: v8::IsolateHandle,
+  error: Mutex<Option<ResourceLimitError>>,
+  waker: AtomicWaker,
+}
+
+impl IsolateLimitHandle {
+  pub fn new(isolate_handle: v8::IsolateHandle) -> Self {
+    Self {
+      isolate_handle,
+      error: Mutex::new(None),
+      waker: AtomicWaker::new(),
+    }
+  }
+
+  /// Returns the limit that was exceeded, if any. The current task is woken
+  /// once a limit is exceeded, even if the isolate is idle.
+  pub fn poll_error(&self, cx: &mut Context) -> Option<ResourceLimitError> {
+    self.waker.register(cx.waker());
+    self.error()
+  }
+
+  pub fn error(&self) -> Option<ResourceLimitError> {
+    self.error.lock().unwrap().clone()
+  }
+}
+
+impl LimitHandle for IsolateLimitHandle {
+  fn isolate_handle(&self) -> &v8::IsolateHandle {
+    &self.isolate_handle
+  }
+
+  fn exceed_limit(&self, error: ResourceLimitError) {
+    {
+      let mut current = self.error.lock().unwrap();
+      if current.is_some() {
+        return;
+      }
+      current.replace(error);
+    }
+    self.isolate_handle.terminate_execution();
+    self.waker.wake();
+  }
+}
+
+/// Enforces `limits` on `isolate`, whose V8 heap must have been created with
+/// `limits.heap_limits()`. Time limits are enforced by a watchdog thread,
+/// which stops when the returned sender is dropped. Must be called on the
+/// thread that runs the isolate.
+pub fn enforce_limits(
+  isolate: &mut CoreIsolate,
+  limits: &ResourceLimits,
+  handle: Arc<dyn LimitHandle>,
+) -> Option<std::sync::mpsc::Sender<()>> {
+  if let Some(max_heap_size) = limits.max_heap_size {
+    let handle = handle.clone();
+    isolate.add_near_heap_limit_callback(
+      move |current_limit, initial_limit| {
+        handle.exceed_limit(ResourceLimitError::HeapSize(max_heap_size));
+        // Terminating the isolate only takes effect once V8 gets to unwind the
+        // script, for which it needs a little more memory than the limit. That
+        // room is bounded, so the limit can't keep growing.
+        std::cmp::max(current_limit, initial_limit + initial_limit / 4)
+      },
+    );
+  }
+
+  if let Some(max_resources) = limits.max_resources {
+    let handle = handle.clone();
+    let state_rc = CoreIsolate::state(isolate);
+    let state = state_rc.borrow();
+    state.resource_table.borrow_mut().set_limit(
+      max_resources,
+      Box::new(move || {
+        handle.exceed_limit(ResourceLimitError::Resources(max_resources))
+      }),
+    );
+  }
+
+  if limits.has_time_limits() {
+    Some(start_watchdog(handle, limits))
+  } else {
+    None
+  }
+}
+
+struct CpuTimeBudget {
+  handle: Arc<dyn LimitHandle>,
+  start: Duration,
+  max: Duration,
+  interrupt_pending: AtomicBool,
+}
+
+/// Runs on the isolate's thread, which is the only thread that can read its
+/// own CPU time.
+extern "C" fn check_cpu_time(_isolate: &mut v8::Isolate, data: *mut c_void) {
+  let budget = unsafe { Arc::from_raw(data as *const CpuTimeBudget) };
+  budget.interrupt_pending.store(false, Ordering::SeqCst);
+  if thread_cpu_time() - budget.start > budget.max {
+    budget
+      .handle
+      .exceed_limit(ResourceLimitError::CpuTime(budget.max));
+  }
+}
+
+/// Starts a thread that terminates the isolate once it exceeds its CPU time
+/// or wall-clock time limit. Must be called on the isolate's thread. The
+/// watchdog stops when the returned sender is dropped.
+fn start_watchdog(
+  handle: Arc<dyn LimitHandle>,
+  limits: &ResourceLimits,
+) -> std::sync::mpsc::Sender<()> {
+  let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+  let start_time = Instant::now();
+  let max_wall_time = limits.max_wall_time;
+  let cpu_budget = limits.max_cpu_time.map(|max| {
+    Arc::new(CpuTimeBudget {
+      handle: handle.clone(),
+      start: thread_cpu_time(),
+      max,
+      interrupt_pending: AtomicBool::new(false),
+    })
+  });
+
+  thread::Builder::new()
+    .name("deno-worker-watchdog".to_string())
+    .spawn(move || loop {
+      match stop_rx.recv_timeout(WATCHDOG_INTERVAL) {
+        Err(RecvTimeoutError::Timeout) => {}
+        _ => break,
+      }
+
+      if let Some(max) = max_wall_time {
+        if start_time.elapsed() > max {
+          handle.exceed_limit(ResourceLimitError::WallTime(max));
+          break;
+        }
+      }
+
+      if let Some(budget) = &cpu_budget {
+        // Interrupts only run while the isolate executes JavaScript, so an
+        // idle isolate must not accumulate them.
+        if budget.interrupt_pending.swap(true, Ordering::SeqCst) {
+          continue;
+        }
+        let data = Arc::into_raw(budget.clone()) as *mut c_void;
+        if !handle
+          .isolate_handle()
+          .request_interrupt(check_cpu_time, data)
+        {
+          // The isolate is gone.
+          unsafe { Arc::from_raw(data as *const CpuTimeBudget) };
+          break;
+        }
+      }
+    })
+    .expect("Failed to spawn worker watchdog");
+
+  stop_tx
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Duration {
+  let mut time = libc::timespec {
+    tv_sec: 0,
+    tv_nsec: 0,
+  };
+  let r =
+    unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+  assert_eq!(r, 0);
+  Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+#[cfg(windows)]
+pub fn thread_cpu_time() -> Duration {
+  use winapi::shared::minwindef::FILETIME;
+  use winapi::um::processthreadsapi::{GetCurrentThread, GetThreadTimes};
+
+  let empty = || FILETIME {
+    dwLowDateTime: 0,
+    dwHighDateTime: 0,
+  };
+  let (mut creation, mut exit, mut kernel, mut user) =
+    (empty(), empty(), empty(), empty());
+  let r = unsafe {
+    GetThreadTimes(
+      GetCurrentThread(),
+      &mut creation,
+      &mut exit,
+      &mut kernel,
+      &mut user,
+    )
+  };
+  assert_ne!(r, 0);
+  // FILETIME values are expressed in 100-nanosecond intervals.
+  let to_nanos = |t: FILETIME| {
+    (((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64) * 100
+  };
+  Duration::from_nanos(to_nanos(kernel) + to_nanos(user))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn heap_limits() {
+    let limits = ResourceLimits {
+      max_heap_size: Some(64 * 1024 * 1024),
+      ..Default::default()
+    };
+    assert_eq!(
+      limits.heap_limits(),
+      Some(HeapLimits {
+        initial: 0,
+        max: 64 * 1024 * 1024
+      })
+    );
+    assert_eq!(ResourceLimits::default().heap_limits(), None);
+  }
+
+  #[test]
+  fn heap_size_from_mb() {
+    assert_eq!(
+      ResourceLimits::heap_size_from_mb(64),
+      Some(64 * 1024 * 1024)
+    );
+    assert_eq!(ResourceLimits::heap_size_from_mb(std::u64::MAX), None);
+  }
+
+  #[test]
+  fn thread_cpu_time_increases() {
+    let start = thread_cpu_time();
+    let mut n = 0u64;
+    while thread_cpu_time() - start < Duration::from_millis(10) {
+      n = n.wrapping_add(1);
+    }
+    assert!(n > 0);
+  }
+
+  #[test]
+  fn error_messages() {
+    assert_eq!(
+      ResourceLimitError::HeapSize(16 * 1024 * 1024).to_string(),
+      "Worker exceeded its heap size limit of 16 MB"
+    );
+    assert_eq!(
+      ResourceLimitError::WallTime(Duration::from_millis(500)).to_string(),
+      "Worker exceeded its wall-clock time limit of 500 ms"
+    );
+    assert_eq!(
+      ResourceLimitError::CpuTime(Duration::from_millis(200))
+        .message("Program"),
+      "Program exceeded its CPU time limit of 200 ms"
+    );
+    assert_eq!(
+      ResourceLimitError::Resources(10).limit_name(),
+      "maxResources"
+    );
+  }
+}
@@ -27,6 +27,52 @@ pub fn kill(pid: i32, signo: i32) -> Result<(), OpError> {
   unix_kill(Pid::from_raw(pid), Option::Some(sig)).map_err(OpError::from)
 }
 
+/// Signals that can't be handled by the process, so it is not possible to
+/// listen for them.
+#[cfg(unix)]
+const FORBIDDEN_SIGNALS: &[&str] =
+  &["SIGILL", "SIGFPE", "SIGKILL", "SIGSEGV", "SIGSTOP"];
+
+/// Parses a signal given by name (`SIGUSR2` or `USR2`) or by number, and
+/// returns its number. Returns `None` for unknown signals and signals that
+/// can't be listened for.
+#[cfg(unix)]
+pub fn parse_signal(s: &str) -> Option<i32> {
+  use nix::sys::signal::Signal;
+  use std::convert::TryFrom;
+  use std::str::FromStr;
+  let signal = match s.parse::<i32>() {
+    Ok(signo) => Signal::try_from(signo).ok()?,
+    Err(_) => {
+      let name = s.to_uppercase();
+      if name.starts_with("SIG") {
+        Signal::from_str(&name).ok()?
+      } else {
+        Signal::from_str(&format!("SIG{}", name)).ok()?
+      }
+    }
+  };
+  if FORBIDDEN_SIGNALS.contains(&signal.as_ref()) {
+    return None;
+  }
+  Some(signal as i32)
+}
+
+#[cfg(not(unix))]
+pub fn parse_signal(_s: &str) -> Option<i32> {
+  None
+}
+
+/// Returns a stream that yields every time the process receives `signo`.
+/// Must be called from within a Tokio runtime.
+#[cfg(unix)]
+pub fn signal_stream(
+  signo: i32,
+) -> Result<tokio::signal::unix::Signal, OpError> {
+  use tokio::signal::unix::{signal, SignalKind};
+  signal(SignalKind::from_raw(signo)).map_err(OpError::from)
+}
+
 #[cfg(not(unix))]
 pub fn kill(pid: i32, signal: i32) -> Result<(), OpError> {
   match signal {
@@ -54,3 +100,17 @@ pub fn kill(pid: i32, signal: i32) -> Result<(), OpError> {
   }
   Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_signal_names_and_numbers() {
+    assert_eq!(parse_signal("SIGUSR2"), Some(libc::SIGUSR2));
+    assert_eq!(parse_signal("usr2"), Some(libc::SIGUSR2));
+    assert_eq!(parse_signal(&libc::SIGHUP.to_string()), Some(libc::SIGHUP));
+    assert_eq!(parse_signal("SIGKILL"), None);
+    assert_eq!(parse_signal("NOTASIGNAL"), None);
+  }
+}
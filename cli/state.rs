@@ -2,12 +2,13 @@
 use crate::file_fetcher::SourceFileFetcher;
 use crate::global_state::GlobalState;
 use crate::global_timer::GlobalTimer;
-use crate::import_map::ImportMap;
+use crate::http_util::create_http_client_with_options;
 use crate::metrics::Metrics;
 use crate::op_error::OpError;
 use crate::ops::JsonOp;
 use crate::ops::MinimalOp;
 use crate::permissions::Permissions;
+use crate::profiler::CpuProfiler;
 use crate::tsc::TargetLib;
 use crate::web_worker::WebWorkerHandle;
 use deno_core::Buf;
@@ -47,9 +48,6 @@ pub struct StateInner {
   pub global_state: GlobalState,
   pub permissions: Permissions,
   pub main_module: ModuleSpecifier,
-  /// When flags contains a `.import_map_path` option, the content of the
-  /// import map file will be resolved and set.
-  pub import_map: Option<ImportMap>,
   pub metrics: Metrics,
   pub global_timer: GlobalTimer,
   pub workers: HashMap<u32, (JoinHandle<()>, WebWorkerHandle)>,
@@ -59,6 +57,11 @@ pub struct StateInner {
   pub target_lib: TargetLib,
   pub is_main: bool,
   pub is_internal: bool,
+  /// CPU profiler started for `--cpu-prof`, see `State::stop_cpu_profiler`.
+  pub cpu_profiler: Option<CpuProfiler>,
+  /// HTTP client of `fetch()` calls that don't pass their own
+  /// `Deno.HttpClient`, see `State::http_client`.
+  http_client: Option<reqwest::Client>,
 }
 
 impl State {
@@ -237,6 +240,21 @@ impl State {
       exit_unstable(api_name);
     }
   }
+
+  /// Stops the CPU profiler started for `--cpu-prof`, if any, and writes the
+  /// profile. This is called both when the worker is dropped and by
+  /// `Deno.exit()`, which exits the process without dropping anything.
+  ///
+  /// It must be called before the inspector of the worker is dropped.
+  pub fn stop_cpu_profiler(&self) {
+    let cpu_profiler = self.borrow_mut().cpu_profiler.take();
+    if let Some(cpu_profiler) = cpu_profiler {
+      match cpu_profiler.stop() {
+        Ok(path) => info!("CPU profile written to {}", path.display()),
+        Err(err) => info!("Cannot write CPU profile: {}", err),
+      }
+    }
+  }
 }
 
 pub fn exit_unstable(api_name: &str) {
@@ -255,12 +273,11 @@ impl ModuleLoader for State {
     is_main: bool,
   ) -> Result<ModuleSpecifier, ErrBox> {
     if !is_main {
-      if let Some(import_map) = &self.borrow().import_map {
-        let result = import_map.resolve(specifier, referrer)?;
-        if let Some(r) = result {
-          return Ok(r);
-        }
-      }
+      return self
+        .borrow()
+        .global_state
+        .resolver
+        .resolve(specifier, referrer);
     }
     let module_specifier =
       ModuleSpecifier::resolve_import(specifier, referrer)?;
@@ -290,6 +307,11 @@ impl ModuleLoader for State {
         // Real module name, might be different from initial specifier
         // due to redirections.
         code: compiled_module.code,
+        module_url_redirects: compiled_module
+          .redirects
+          .into_iter()
+          .filter(|url| url != &module_url_specified)
+          .collect(),
         module_url_specified,
         module_url_found: compiled_module.name,
       })
@@ -308,7 +330,6 @@ impl ModuleLoader for State {
     let module_specifier = module_specifier.clone();
     let state = self.borrow();
     let target_lib = state.target_lib.clone();
-    let maybe_import_map = state.import_map.clone();
     // Only "main" module is loaded without permission check,
     // ie. module that is associated with "is_main" state
     // and is not a dynamic import.
@@ -337,7 +358,6 @@ impl ModuleLoader for State {
           target_lib,
           permissions,
           is_dyn_import,
-          maybe_import_map,
         )
         .await
     }
@@ -351,7 +371,6 @@ impl State {
     global_state: GlobalState,
     shared_permissions: Option<Permissions>,
     main_module: ModuleSpecifier,
-    maybe_import_map: Option<ImportMap>,
     is_internal: bool,
   ) -> Result<Self, ErrBox> {
     let seeded_rng = match global_state.flags.seed {
@@ -369,7 +388,6 @@ impl State {
       global_state,
       main_module,
       permissions,
-      import_map: maybe_import_map,
       metrics: Metrics::default(),
       global_timer: GlobalTimer::new(),
       workers: HashMap::new(),
@@ -379,6 +397,8 @@ impl State {
       target_lib: TargetLib::Main,
       is_main: true,
       is_internal,
+      cpu_profiler: None,
+      http_client: None,
     }));
 
     Ok(Self(state))
@@ -405,7 +425,6 @@ impl State {
       global_state,
       main_module,
       permissions,
-      import_map: None,
       metrics: Metrics::default(),
       global_timer: GlobalTimer::new(),
       workers: HashMap::new(),
@@ -415,11 +434,29 @@ impl State {
       target_lib: TargetLib::Worker,
       is_main: false,
       is_internal: false,
+      cpu_profiler: None,
+      http_client: None,
     }));
 
     Ok(Self(state))
   }
 
+  /// Returns the HTTP client shared by all `fetch()` calls of this isolate,
+  /// so that connections are kept alive between them. A client keeps its
+  /// connections on the runtime it was first used on, so every worker
+  /// creates its own client from the configuration in `GlobalState`.
+  pub fn http_client(&self) -> Result<reqwest::Client, ErrBox> {
+    let mut state = self.borrow_mut();
+    if let Some(client) = &state.http_client {
+      return Ok(client.clone());
+    }
+    let client = create_http_client_with_options(
+      state.global_state.http_client_options.clone(),
+    )?;
+    state.http_client = Some(client.clone());
+    Ok(client)
+  }
+
   #[inline]
   pub fn check_read(&self, path: &Path) -> Result<(), OpError> {
     self.borrow().permissions.check_read(path)
@@ -502,7 +539,6 @@ impl State {
       GlobalState::mock(vec!["deno".to_string()], None),
       None,
       module_specifier,
-      None,
       false,
     )
     .unwrap()
@@ -20,10 +20,15 @@ use crate::swc_ecma_parser::Session;
 use crate::swc_ecma_parser::SourceFileInput;
 use crate::swc_ecma_parser::Syntax;
 use crate::swc_ecma_parser::TsConfig;
+use deno_core::ErrBox;
 use std::error::Error;
 use std::fmt;
 use std::sync::Arc;
 use std::sync::RwLock;
+use swc_ecma_codegen::text_writer::JsWriter;
+use swc_ecma_transforms::react;
+use swc_ecma_transforms::typescript;
+use swc_ecma_visit::FoldWith;
 
 fn get_default_es_config() -> EsConfig {
   let mut config = EsConfig::default();
@@ -66,6 +71,23 @@ pub fn get_syntax_for_media_type(media_type: MediaType) -> Syntax {
   }
 }
 
+/// Functions that JSX elements and fragments are transformed to calls of,
+/// like the `jsxFactory` and `jsxFragmentFactory` compiler options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsxOptions {
+  pub factory: String,
+  pub fragment_factory: String,
+}
+
+impl Default for JsxOptions {
+  fn default() -> Self {
+    Self {
+      factory: "React.createElement".to_string(),
+      fragment_factory: "React.Fragment".to_string(),
+    }
+  }
+}
+
 #[derive(Clone, Debug)]
 pub struct SwcDiagnosticBuffer {
   pub diagnostics: Vec<String>,
@@ -207,6 +229,84 @@ impl AstParser {
     })
   }
 
+  /// Strips the types of a TypeScript module and transforms JSX, without
+  /// type checking. Returns the emitted JavaScript and its source map.
+  pub fn transpile(
+    &self,
+    file_name: &str,
+    media_type: MediaType,
+    source_code: &str,
+    jsx: &JsxOptions,
+  ) -> Result<(String, String), ErrBox> {
+    self.parse_module(file_name, media_type, source_code, |parse_result| {
+      let module = self.strip_types(parse_result?, media_type, jsx);
+      self.emit_module(&module, false)
+    })
+  }
+
+  /// Removes TypeScript syntax from a module parsed by this parser and
+  /// transforms JSX to calls of the functions in `jsx`.
+  pub fn strip_types(
+    &self,
+    module: swc_ecma_ast::Module,
+    media_type: MediaType,
+    jsx: &JsxOptions,
+  ) -> swc_ecma_ast::Module {
+    swc_common::GLOBALS.set(&self.globals, || {
+      let module = match media_type {
+        MediaType::JSX | MediaType::TSX => module.fold_with(&mut react::react(
+          self.source_map.clone(),
+          react::Options {
+            pragma: jsx.factory.clone(),
+            pragma_frag: jsx.fragment_factory.clone(),
+            ..Default::default()
+          },
+        )),
+        _ => module,
+      };
+      module.fold_with(&mut typescript::strip())
+    })
+  }
+
+  /// Emits JavaScript for a module parsed by this parser, along with a source
+  /// map that points to every file it was parsed from.
+  ///
+  /// With `minify` whitespace and comments are left out.
+  pub fn emit_module(
+    &self,
+    module: &swc_ecma_ast::Module,
+    minify: bool,
+  ) -> Result<(String, String), ErrBox> {
+    let mut buf = vec![];
+    let mut src_map_buf = vec![];
+    {
+      let writer = Box::new(JsWriter::new(
+        self.source_map.clone(),
+        "\n",
+        &mut buf,
+        Some(&mut src_map_buf),
+      ));
+      let mut emitter = swc_ecma_codegen::Emitter {
+        cfg: swc_ecma_codegen::Config { minify },
+        comments: if minify { None } else { Some(&self.comments) },
+        cm: self.source_map.clone(),
+        wr: writer,
+        handlers: Box::new(NoopHandlers),
+      };
+      emitter.emit_module(module)?;
+    }
+    let code = String::from_utf8(buf)?;
+
+    let mut map_buf = vec![];
+    self
+      .source_map
+      .build_source_map(&mut src_map_buf)
+      .to_writer(&mut map_buf)?;
+    let source_map = String::from_utf8(map_buf)?;
+
+    Ok((code, source_map))
+  }
+
   pub fn get_span_location(&self, span: Span) -> swc_common::Loc {
     self.source_map.lookup_char_pos(span.lo())
   }
@@ -227,3 +327,77 @@ impl AstParser {
     }
   }
 }
+
+struct NoopHandlers;
+
+impl swc_ecma_codegen::Handlers for NoopHandlers {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_transpile() {
+    let source_code = r#"
+      enum Color { Red, Green }
+      interface Point { x: number; y: number }
+      export function length(p: Point): number {
+        return Math.sqrt(p.x * p.x + p.y * p.y);
+      }
+      export const red: Color = Color.Red;
+    "#;
+    let (code, source_map) = AstParser::new()
+      .transpile(
+        "file:///a.ts",
+        MediaType::TypeScript,
+        source_code,
+        &JsxOptions::default(),
+      )
+      .unwrap();
+    assert!(!code.contains("interface"));
+    assert!(!code.contains(": number"));
+    assert!(code.contains("export function length(p)"));
+    assert!(code.contains("Color[Color[\"Red\"] = 0] = \"Red\""));
+    assert!(source_map.contains("\"mappings\""));
+  }
+
+  #[test]
+  fn test_transpile_jsx() {
+    let source_code = "export const div = <div className=\"a\">{1}</div>;";
+    let (code, _) = AstParser::new()
+      .transpile(
+        "file:///a.tsx",
+        MediaType::TSX,
+        source_code,
+        &JsxOptions::default(),
+      )
+      .unwrap();
+    assert!(code.contains("React.createElement(\"div\""));
+  }
+
+  #[test]
+  fn test_transpile_jsx_factory() {
+    let source_code = "export const a = <><div /></>;";
+    let jsx = JsxOptions {
+      factory: "h".to_string(),
+      fragment_factory: "Fragment".to_string(),
+    };
+    let (code, _) = AstParser::new()
+      .transpile("file:///a.tsx", MediaType::TSX, source_code, &jsx)
+      .unwrap();
+    assert!(code.contains("h(Fragment"));
+    assert!(code.contains("h(\"div\""));
+    assert!(!code.contains("React"));
+  }
+
+  #[test]
+  fn test_transpile_syntax_error() {
+    let result = AstParser::new().transpile(
+      "file:///a.ts",
+      MediaType::TypeScript,
+      "export const a: = 1;",
+      &JsxOptions::default(),
+    );
+    assert!(result.is_err());
+  }
+}
@@ -0,0 +1 @@
+import "./subdir/import_chain_mid.ts";
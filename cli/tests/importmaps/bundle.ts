@@ -0,0 +1,6 @@
+Deno.bundle(new URL("test.ts", import.meta.url).href).then(
+  ([diagnostics, emit]) => {
+    console.log(diagnostics ?? "bundled");
+    console.log(emit.includes("Hello from remapped moment!"));
+  },
+);
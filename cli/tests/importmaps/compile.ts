@@ -0,0 +1,5 @@
+Deno.compile(new URL("test.ts", import.meta.url).href).then(
+  ([diagnostics]) => {
+    console.log(diagnostics ?? "compiled");
+  },
+);
@@ -0,0 +1 @@
+export * from "lodash/doc.ts";
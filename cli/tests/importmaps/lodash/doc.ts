@@ -0,0 +1,8 @@
+/** Splits `array` into groups the length of `size`. */
+export function chunk<T>(array: T[], size: number): T[][] {
+  const chunks = [];
+  for (let i = 0; i < array.length; i += size) {
+    chunks.push(array.slice(i, i + size));
+  }
+  return chunks;
+}
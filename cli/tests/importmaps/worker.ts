@@ -0,0 +1,6 @@
+const worker = new Worker(new URL("worker_module.ts", import.meta.url).href, {
+  type: "module",
+});
+worker.onmessage = (): void => {
+  worker.terminate();
+};
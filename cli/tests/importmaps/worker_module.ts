@@ -0,0 +1,3 @@
+import "moment";
+
+postMessage("done");
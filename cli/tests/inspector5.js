@@ -0,0 +1,8 @@
+new Worker(new URL("inspector_worker.js", import.meta.url).href, {
+  type: "module",
+  name: "inspected_worker",
+});
+console.log("hello from the main module");
+
+// This process will be killed before the timeout is over.
+await new Promise((res, _) => setTimeout(res, 10000));
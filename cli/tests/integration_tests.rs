@@ -339,6 +339,421 @@ fn cache_test() {
   drop(g);
 }
 
+#[test]
+fn auth_tokens() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let module_url = "http://localhost:4545/cli/tests/subdir/auth_required.ts";
+
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .env("DENO_AUTH_TOKENS", "wrongtoken42@localhost:4545")
+    .env("NO_COLOR", "1")
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(module_url)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("401 Unauthorized"));
+  assert!(!stderr.contains("wrongtoken42"));
+
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .env("DENO_AUTH_TOKENS", "abcdef123456789@localhost:4545")
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(module_url)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let stdout = std::str::from_utf8(&output.stdout).unwrap();
+  assert_eq!(stdout, "Hello from a private registry\n");
+
+  // The token must not end up in $DENO_DIR.
+  for entry in walkdir::WalkDir::new(deno_dir.path()) {
+    let entry = entry.unwrap();
+    if entry.file_type().is_file() {
+      let contents = std::fs::read(entry.path()).unwrap();
+      let contents = String::from_utf8_lossy(&contents);
+      assert!(!contents.contains("abcdef123456789"));
+    }
+  }
+  drop(g);
+}
+
+#[test]
+fn incremental_build_info() {
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let t = TempDir::new().expect("tempdir fail");
+  let main = t.path().join("main.ts");
+  let dep = t.path().join("dep.ts");
+  std::fs::write(&main, "import { a } from \"./dep.ts\";\nconsole.log(a);\n")
+    .unwrap();
+  std::fs::write(&dep, "export const a: number = 1;\n").unwrap();
+  std::fs::write(
+    t.path().join("tsconfig.json"),
+    "{ \"compilerOptions\": { \"strict\": false } }\n",
+  )
+  .unwrap();
+  let deno = |args: &[&str]| {
+    let output = util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .env("NO_COLOR", "1")
+      .current_dir(t.path())
+      .args(args)
+      .output()
+      .expect("Failed to spawn script");
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+  };
+  let build_info_files = || {
+    walkdir::WalkDir::new(deno_dir.path().join("gen"))
+      .into_iter()
+      .map(|entry| entry.unwrap())
+      .filter(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        name.starts_with("main.ts.") && name.ends_with(".buildinfo")
+      })
+      .count()
+  };
+
+  assert!(deno(&["cache", "main.ts"]).contains("Check"));
+  assert_eq!(build_info_files(), 1);
+  assert!(!deno(&["cache", "main.ts"]).contains("Check"));
+
+  // Build info is kept per compiler config.
+  assert!(
+    deno(&["cache", "--config", "tsconfig.json", "main.ts"]).contains("Check")
+  );
+  assert_eq!(build_info_files(), 2);
+
+  std::fs::write(&dep, "export const a: number = 2;\n").unwrap();
+  assert!(deno(&["cache", "main.ts"]).contains("Check"));
+  assert!(!deno(&["cache", "main.ts"]).contains("Check"));
+
+  // Without build info an unchanged graph is type checked again.
+  assert!(deno(&["cache", "--no-incremental", "main.ts"]).contains("Check"));
+  assert_eq!(build_info_files(), 2);
+  assert!(!deno(&["cache", "main.ts"]).contains("Check"));
+}
+
+#[test]
+fn cache_clean() {
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let t = TempDir::new().expect("tempdir fail");
+  let a = t.path().join("a.ts");
+  let b = t.path().join("b.ts");
+  std::fs::write(&a, "export const a: number = 1;\n").unwrap();
+  std::fs::write(&b, "export const b: number = 2;\n").unwrap();
+
+  let deno = |args: &[&str]| {
+    let output = util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .env("NO_COLOR", "1")
+      .current_dir(t.path())
+      .args(args)
+      .output()
+      .expect("Failed to spawn script");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+  };
+  let gen_files = || {
+    let mut files: Vec<String> =
+      walkdir::WalkDir::new(deno_dir.path().join("gen"))
+        .into_iter()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name != ".lock")
+        .collect();
+    files.sort();
+    files
+  };
+
+  deno(&["cache", "a.ts", "b.ts"]);
+  let compiled = gen_files();
+  assert!(compiled.contains(&"a.ts.js".to_string()));
+  assert!(compiled.contains(&"b.ts.js".to_string()));
+
+  // The output of a.ts no longer matches its source.
+  std::fs::write(&a, "export const a: number = 3;\n").unwrap();
+  let stdout = deno(&["cache", "clean", "--dry-run"]);
+  assert!(stdout.contains("a.ts.js"));
+  assert!(!stdout.contains("b.ts.js"));
+  assert!(stdout.contains("Would remove"));
+  assert_eq!(gen_files(), compiled);
+
+  let stdout = deno(&["cache", "clean"]);
+  assert!(stdout.starts_with("Removed"));
+  let files = gen_files();
+  assert!(!files.iter().any(|f| f.starts_with("a.ts.")));
+  assert!(files.contains(&"b.ts.js".to_string()));
+
+  // Output compiled with another config isn't stale.
+  let config = t.path().join("tsconfig.json");
+  std::fs::write(&config, r#"{ "compilerOptions": { "strict": false } }"#)
+    .unwrap();
+  deno(&["cache", "--config", "tsconfig.json", "b.ts"]);
+  let stdout = deno(&["cache", "clean", "--dry-run"]);
+  assert!(!stdout.contains("b.ts.js"));
+
+  // A remote module with corrupt metadata is removed with its output, the
+  // modules that it imports are kept.
+  let _g = util::http_server();
+  deno(&["cache", "http://localhost:4545/cli/tests/subdir/mod1.ts"]);
+  let deps_dir = deno_dir.path().join("deps/http/localhost_PORT4545");
+  let mod1_metadata = std::fs::read_dir(&deps_dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .find(|path| {
+      path.to_string_lossy().ends_with(".metadata.json")
+        && std::fs::read_to_string(path).unwrap().contains("mod1.ts")
+    })
+    .unwrap();
+  let mod1 = std::path::PathBuf::from(
+    mod1_metadata
+      .to_string_lossy()
+      .trim_end_matches(".metadata.json"),
+  );
+  assert!(mod1.is_file());
+  assert!(gen_files().contains(&"mod1.ts.js".to_string()));
+  std::fs::write(&mod1_metadata, "{").unwrap();
+
+  let stdout = deno(&["cache", "clean"]);
+  assert!(stdout.starts_with("Removed"));
+  assert!(!mod1.exists());
+  assert!(!mod1_metadata.exists());
+  let files = gen_files();
+  assert!(!files.iter().any(|f| f.starts_with("mod1.ts.")));
+  assert!(files.contains(&"mod2.ts.js".to_string()));
+  assert!(files.contains(&"b.ts.js".to_string()));
+
+  deno(&["cache", "--gc", "--max-size=0"]);
+  assert!(gen_files().is_empty());
+  assert!(!deps_dir.exists());
+}
+
+#[test]
+fn cache_concurrent_writes() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let module_url = "http://localhost:4545/cli/tests/subdir/mod1.ts";
+
+  // Processes that share $DENO_DIR download and compile the same modules at
+  // the same time.
+  let children: Vec<_> = (0..8)
+    .map(|_| {
+      util::deno_cmd()
+        .env("DENO_DIR", deno_dir.path())
+        .current_dir(util::root_path())
+        .arg("cache")
+        .arg("--reload")
+        .arg(module_url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn script")
+    })
+    .collect();
+  for child in children {
+    let output = child.wait_with_output().unwrap();
+    assert!(
+      output.status.success(),
+      "{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+  }
+
+  // No partially written files or temporary files are left behind, and each
+  // cache has a single lock file.
+  for entry in walkdir::WalkDir::new(deno_dir.path()) {
+    let entry = entry.unwrap();
+    let name = entry.file_name().to_string_lossy().to_string();
+    assert!(!name.ends_with(".tmp"));
+    if name.ends_with(".lock") {
+      assert_eq!(entry.depth(), 2);
+    }
+    if name.ends_with(".metadata.json") {
+      let json = std::fs::read_to_string(entry.path()).unwrap();
+      assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+    }
+  }
+
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::root_path())
+    .arg("cache")
+    .arg("--cached-only")
+    .arg(module_url)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  drop(g);
+}
+
+#[test]
+fn cache_corrupt_metadata() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let module_url = "http://localhost:4545/cli/tests/subdir/print_hello.ts";
+  let deno = || {
+    util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .current_dir(util::root_path())
+      .arg("cache")
+      .arg(module_url)
+      .output()
+      .expect("Failed to spawn script")
+  };
+  assert!(deno().status.success());
+
+  // Corrupt metadata is a cache miss, the module is downloaded again.
+  for entry in walkdir::WalkDir::new(deno_dir.path().join("deps")) {
+    let entry = entry.unwrap();
+    if entry
+      .file_name()
+      .to_string_lossy()
+      .ends_with(".metadata.json")
+    {
+      std::fs::write(entry.path(), "{\"headers\": {").unwrap();
+    }
+  }
+  let output = deno();
+  assert!(output.status.success());
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  assert!(stderr.contains("Download"));
+  drop(g);
+}
+
+#[test]
+fn cache_retry_download() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  // The test server answers the first two requests with 503.
+  let module_url = "http://localhost:4545/cli/tests/subdir/flaky_hello.ts";
+  let output = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .env("NO_COLOR", "1")
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg("--max-downloads=1")
+    .arg(module_url)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let stdout = std::str::from_utf8(&output.stdout).unwrap();
+  assert_eq!(stdout, "Hello after retries\n");
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("Retry"));
+  assert!(stderr.contains("503 Service Unavailable"));
+  drop(g);
+}
+
+#[test]
+fn import_map_remote_lock_write() {
+  let g = util::http_server();
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let lock_file = deno_dir.path().join("lock.json");
+  let import_map_url =
+    "http://localhost:4545/cli/tests/importmaps/import_map.json";
+  let status = util::deno_cmd()
+    .env("DENO_DIR", deno_dir.path())
+    .current_dir(util::tests_path())
+    .arg("cache")
+    .arg("--unstable")
+    .arg(format!("--importmap={}", import_map_url))
+    .arg(format!("--lock={}", lock_file.to_str().unwrap()))
+    .arg("--lock-write")
+    .arg("importmaps/test.ts")
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+  let lock = std::fs::read_to_string(&lock_file).unwrap();
+  assert!(lock.contains(import_map_url));
+  drop(g);
+}
+
+#[test]
+fn import_map_from_project_config() {
+  // cli/tests/import_map_config/deno.json points at importmaps/import_map.json
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path().join("import_map_config"))
+    .arg("run")
+    .arg("--quiet")
+    .arg("--reload")
+    .arg("--unstable")
+    .arg("../importmaps/test.ts")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let expected =
+    std::fs::read_to_string(util::tests_path().join("033_import_map.out"))
+      .unwrap();
+  let stdout = std::str::from_utf8(&output.stdout).unwrap();
+  assert_eq!(stdout, expected);
+
+  // The project configuration file is only used with --unstable.
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path().join("import_map_config"))
+    .arg("run")
+    .arg("--quiet")
+    .arg("../001_hello.js")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "Hello World\n"
+  );
+}
+
+#[test]
+fn import_map_project_config_parse_error() {
+  let t = TempDir::new().expect("tempdir fail");
+  std::fs::write(t.path().join("deno.json"), "{").unwrap();
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("run")
+    .arg("--quiet")
+    .arg("--unstable")
+    .arg(util::tests_path().join("001_hello.js"))
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  assert_eq!(
+    std::str::from_utf8(&output.stdout).unwrap(),
+    "Hello World\n"
+  );
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("Warning"));
+  assert!(stderr.contains("deno.json"));
+}
+
+#[test]
+fn no_check_output_is_checked_later() {
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let deno = |args: &[&str]| {
+    util::deno_cmd()
+      .env("DENO_DIR", deno_dir.path())
+      .current_dir(util::tests_path())
+      .arg("run")
+      .args(args)
+      .arg("no_check.ts")
+      .output()
+      .expect("Failed to spawn script")
+  };
+  assert!(deno(&["--no-check"]).status.success());
+  // The transpiled modules in the cache are type checked without --no-check.
+  let output = deno(&[]);
+  assert!(!output.status.success());
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  assert!(stderr.contains("TS2322"));
+}
+
 #[test]
 fn fmt_test() {
   let t = TempDir::new().expect("tempdir fail");
@@ -917,6 +1332,145 @@ fn bundle_import_map() {
   assert_eq!(output.stderr, b"");
 }
 
+#[test]
+fn bundle_minify() {
+  let mod1 = util::root_path().join("cli/tests/subdir/mod1.ts");
+  assert!(mod1.is_file());
+  let t = TempDir::new().expect("tempdir fail");
+  let bundle = t.path().join("mod1.bundle.js");
+  let mut deno = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("bundle")
+    .arg("--minify")
+    .arg(mod1)
+    .arg(&bundle)
+    .spawn()
+    .expect("failed to spawn script");
+  let status = deno.wait().expect("failed to wait for the child process");
+  assert!(status.success());
+  let code = std::fs::read_to_string(&bundle).unwrap();
+  assert!(!code.contains("    "));
+
+  // The minified bundle must still work.
+  let test = t.path().join("test.js");
+  std::fs::write(
+    &test,
+    "
+      import { printHello3 } from \"./mod1.bundle.js\";
+      printHello3(); ",
+  )
+  .expect("error writing file");
+
+  let output = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(&test)
+    .output()
+    .expect("failed to spawn script");
+  assert!(std::str::from_utf8(&output.stdout)
+    .unwrap()
+    .trim()
+    .ends_with("Hello"));
+  assert_eq!(output.stderr, b"");
+}
+
+#[test]
+fn bundle_source_map() {
+  // Errors thrown from a bundle are reported at their original location.
+  let mod1 = util::root_path().join("cli/tests/subdir/mod1.ts");
+  let t = TempDir::new().expect("tempdir fail");
+  let bundle = t.path().join("mod1.bundle.js");
+  let status = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("bundle")
+    .arg(mod1)
+    .arg(&bundle)
+    .spawn()
+    .expect("failed to spawn script")
+    .wait()
+    .expect("failed to wait for the child process");
+  assert!(status.success());
+
+  let test = t.path().join("test.js");
+  std::fs::write(
+    &test,
+    "
+      import { throwsError } from \"./mod1.bundle.js\";
+      throwsError(); ",
+  )
+  .expect("error writing file");
+
+  let output = util::deno_cmd()
+    .current_dir(util::root_path())
+    .arg("run")
+    .arg(&test)
+    .output()
+    .expect("failed to spawn script");
+  assert!(!output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("exception from mod1"));
+  assert!(stderr.contains("subdir/mod1.ts:16:"));
+}
+
+#[test]
+fn import_map_entry_points() {
+  // Every way of loading a module resolves the specifiers of
+  // importmaps/test.ts through the same import map.
+  let entry_points: Vec<(Vec<&str>, &str)> = vec![
+    (
+      vec!["run", "--reload", "test.ts"],
+      "Hello from scoped moment!",
+    ),
+    (
+      vec!["run", "--reload", "dynamic.ts"],
+      "Hello from remapped moment!",
+    ),
+    (
+      vec!["run", "--reload", "--allow-read", "worker.ts"],
+      "Hello from remapped moment!",
+    ),
+    (
+      vec!["run", "--reload", "--allow-read", "compile.ts"],
+      "compiled",
+    ),
+    (
+      vec!["run", "--reload", "--allow-read", "bundle.ts"],
+      "bundled\ntrue",
+    ),
+    (vec!["bundle", "test.ts"], "Hello from remapped moment!"),
+    (vec!["info", "test.ts"], "importmaps/moment/moment.ts"),
+    (vec!["doc", "doc.ts"], "function chunk"),
+  ];
+  for (args, expected) in entry_points {
+    let (subcommand, args) = args.split_first().unwrap();
+    let output = util::deno_cmd()
+      .current_dir(util::root_path().join("cli/tests/importmaps"))
+      .env("NO_COLOR", "1")
+      .arg(subcommand)
+      .arg("--unstable")
+      .arg("--importmap=import_map.json")
+      .args(args)
+      .output()
+      .expect("failed to spawn script");
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+      output.status.success(),
+      "deno {} {:?} failed: {}",
+      subcommand,
+      args,
+      stderr
+    );
+    assert!(
+      stdout.contains(expected),
+      "deno {} {:?} printed: {}",
+      subcommand,
+      args,
+      stdout
+    );
+  }
+}
+
 #[test]
 fn repl_test_console_log() {
   let (out, err) = util::run_and_collect_output(
@@ -1429,6 +1983,24 @@ itest!(import_map_no_unstable {
   exit_code: 70,
 });
 
+itest!(import_map_remote {
+  args:
+    "run --quiet --reload --import-map=http://localhost:4545/cli/tests/importmaps/import_map.json --unstable importmaps/test.ts",
+  output: "033_import_map.out",
+  http_server: true,
+});
+
+itest!(no_check {
+  args: "run --quiet --reload --no-check no_check.ts",
+  output: "no_check.ts.out",
+});
+
+// Without --quiet, a type check would print a "Check" line.
+itest!(no_check_no_compile {
+  args: "run --reload --no-check no_check.ts",
+  output: "no_check.ts.out",
+});
+
 itest!(_034_onload {
   args: "run --quiet --reload 034_onload/main.ts",
   output: "034_onload.out",
@@ -1528,6 +2100,21 @@ itest!(_054_info_local_imports {
   exit_code: 0,
 });
 
+itest!(info_json {
+  args: "info --quiet --json 005_more_imports.ts",
+  output: "info_json.out",
+});
+
+itest!(info_dot {
+  args: "info --quiet --dot 005_more_imports.ts",
+  output: "info_dot.out",
+});
+
+itest!(info_why {
+  args: "info --quiet --why subdir/print_hello.ts 005_more_imports.ts",
+  output: "info_why.out",
+});
+
 itest!(_056_make_temp_file_write_perm {
   args:
     "run --quiet --allow-read --allow-write=./subdir/ 056_make_temp_file_write_perm.ts",
@@ -1589,6 +2176,12 @@ itest!(lock_check_err {
   http_server: true,
 });
 
+itest!(lock_check_redirect {
+  args: "run --reload --lock=lock_check_redirect.json redirect_chain.js",
+  output: "redirect_chain.js.out",
+  http_server: true,
+});
+
 // TODO(ry) Re-enable flaky test https://github.com/denoland/deno/issues/4049
 itest_ignore!(lock_check_err2 {
   args: "run --lock=lock_check_err2.json 019_media_types.ts",
@@ -1643,6 +2236,18 @@ itest!(config {
   output: "config.ts.out",
 });
 
+itest!(wall_time_limit {
+  args: "run --reload --max-wall-time=200 wall_time_limit.js",
+  exit_code: 1,
+  output: "wall_time_limit.js.out",
+});
+
+itest!(cpu_time_limit {
+  args: "run --reload --max-cpu-time=200 cpu_time_limit.js",
+  exit_code: 1,
+  output: "cpu_time_limit.js.out",
+});
+
 itest!(error_001 {
   args: "run --reload error_001.ts",
   exit_code: 1,
@@ -1677,6 +2282,12 @@ itest!(error_004_missing_module {
   output: "error_004_missing_module.ts.out",
 });
 
+itest!(error_import_chain {
+  args: "run --reload import_chain.ts",
+  exit_code: 1,
+  output: "import_chain.ts.out",
+});
+
 itest!(error_005_missing_dynamic_import {
   args: "run --reload --allow-read --quiet error_005_missing_dynamic_import.ts",
   exit_code: 1,
@@ -2038,6 +2649,26 @@ itest!(cafile_eval {
   http_server: true,
 });
 
+itest!(unsafely_ignore_certificate_errors {
+  args: "run --quiet --reload --allow-net --unsafely-ignore-certificate-errors=localhost unsafely_ignore_certificate_errors.ts",
+  output: "unsafely_ignore_certificate_errors.ts.out",
+  http_server: true,
+});
+
+itest!(unsafely_ignore_certificate_errors_fetch {
+  args: "run --reload --allow-net --unsafely-ignore-certificate-errors=localhost unsafely_ignore_certificate_errors_fetch.ts",
+  output: "unsafely_ignore_certificate_errors_fetch.ts.out",
+  http_server: true,
+});
+
+itest!(connect_tls_invalid_certificate {
+  args:
+    "run --quiet --reload --allow-net unsafely_ignore_certificate_errors.ts",
+  output: "connect_tls_invalid_certificate.ts.out",
+  http_server: true,
+  exit_code: 1,
+});
+
 itest_ignore!(cafile_info {
   args:
     "info --cert tls/RootCA.pem https://localhost:5545/cli/tests/cafile_info.ts",
@@ -2718,6 +3349,141 @@ async fn inspector_break_on_first_line() {
   child.wait().unwrap();
 }
 
+#[tokio::test]
+async fn inspector_json_list_includes_workers() {
+  let script = util::tests_path().join("inspector5.js");
+  let inspect_flag = inspect_flag_with_unique_port("--inspect");
+  let host = inspect_flag.split('=').nth(1).unwrap().to_string();
+  let mut child = util::deno_cmd()
+    .arg("run")
+    .arg(&inspect_flag)
+    .arg(script)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+  let main_ws_url = extract_ws_url_from_stderr(&mut stderr_lines);
+  let worker_ws_url = extract_ws_url_from_stderr(&mut stderr_lines);
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  // The main module and the worker run concurrently, so wait for both to
+  // start without relying on the order of their output.
+  let mut stdout_first_lines =
+    vec![stdout_lines.next().unwrap(), stdout_lines.next().unwrap()];
+  stdout_first_lines.sort();
+  assert_eq!(
+    stdout_first_lines,
+    vec!["hello from the main module", "hello from the worker"]
+  );
+
+  let body = reqwest::get(&format!("http://{}/json/list", host))
+    .await
+    .unwrap()
+    .text()
+    .await
+    .unwrap();
+  let targets: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+  assert_eq!(targets.len(), 2);
+  let find_target = |ws_url: &url::Url| {
+    targets
+      .iter()
+      .find(|t| t["webSocketDebuggerUrl"] == ws_url.as_str())
+      .unwrap()
+      .clone()
+  };
+  let main_target = find_target(&main_ws_url);
+  assert!(main_target["title"].as_str().unwrap().ends_with(" - main"));
+  assert!(main_target["url"]
+    .as_str()
+    .unwrap()
+    .ends_with("inspector5.js"));
+  let worker_target = find_target(&worker_ws_url);
+  assert!(worker_target["title"]
+    .as_str()
+    .unwrap()
+    .ends_with(" - inspected_worker"));
+  assert!(worker_target["url"]
+    .as_str()
+    .unwrap()
+    .ends_with("inspector_worker.js"));
+
+  child.kill().unwrap();
+  child.wait().unwrap();
+}
+
+#[tokio::test]
+async fn inspector_break_on_first_line_of_worker() {
+  let script = util::tests_path().join("inspector5.js");
+  let mut child = util::deno_cmd()
+    .arg("run")
+    .arg(inspect_flag_with_unique_port("--inspect"))
+    .arg("--inspect-brk-workers")
+    .arg(script)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+  let _main_ws_url = extract_ws_url_from_stderr(&mut stderr_lines);
+  let worker_ws_url = extract_ws_url_from_stderr(&mut stderr_lines);
+
+  let (socket, response) = tokio_tungstenite::connect_async(worker_ws_url)
+    .await
+    .expect("Can't connect");
+  assert_eq!(response.status(), 101); // Switching protocols.
+
+  let (mut socket_tx, socket_rx) = socket.split();
+  let mut socket_rx =
+    socket_rx.map(|msg| msg.unwrap().to_string()).filter(|msg| {
+      let pass = !msg.starts_with(r#"{"method":"Debugger.scriptParsed","#);
+      futures::future::ready(pass)
+    });
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+
+  use TestStep::*;
+  let test_steps = vec![
+    // The main module is not paused.
+    StdOut("hello from the main module"),
+    WsSend(r#"{"id":1,"method":"Runtime.enable"}"#),
+    WsSend(r#"{"id":2,"method":"Debugger.enable"}"#),
+    WsRecv(
+      r#"{"method":"Runtime.executionContextCreated","params":{"context":{"id":1,"#,
+    ),
+    WsRecv(r#"{"id":1,"result":{}}"#),
+    WsRecv(r#"{"id":2,"result":{"debuggerId":"#),
+    WsSend(r#"{"id":3,"method":"Runtime.runIfWaitingForDebugger"}"#),
+    WsRecv(r#"{"id":3,"result":{}}"#),
+    WsRecv(r#"{"method":"Debugger.paused","#),
+    WsSend(r#"{"id":4,"method":"Debugger.resume"}"#),
+    WsRecv(r#"{"id":4,"result":{}}"#),
+    StdOut("hello from the worker"),
+  ];
+
+  for step in test_steps {
+    match step {
+      StdOut(s) => assert_eq!(&stdout_lines.next().unwrap(), s),
+      WsRecv(s) => assert!(socket_rx.next().await.unwrap().starts_with(s)),
+      WsSend(s) => socket_tx.send(s.into()).await.unwrap(),
+      _ => unreachable!(),
+    }
+  }
+
+  child.kill().unwrap();
+  child.wait().unwrap();
+}
+
 #[tokio::test]
 async fn inspector_pause() {
   let script = util::tests_path().join("inspector1.js");
@@ -3011,6 +3777,129 @@ async fn inspector_runtime_evaluate_does_not_crash() {
   child.wait().unwrap();
 }
 
+#[test]
+fn cpu_prof_writes_profile_on_exit() {
+  let out_dir = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("run")
+    .arg(format!("--cpu-prof={}", out_dir.path().display()))
+    .arg("002_hello.ts")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("CPU profile written to"));
+  // No inspector server should have been started.
+  assert!(!stderr.contains("Debugger listening on"));
+
+  let profiles = std::fs::read_dir(out_dir.path())
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .collect::<Vec<_>>();
+  assert_eq!(profiles.len(), 1);
+  let profile_path = &profiles[0];
+  assert_eq!(profile_path.extension().unwrap(), "cpuprofile");
+  let profile: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(profile_path).unwrap())
+      .unwrap();
+  assert!(profile["nodes"].is_array());
+  assert!(profile["startTime"].is_number());
+}
+
+#[test]
+fn cpu_prof_writes_profile_on_deno_exit() {
+  let out_dir = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("run")
+    .arg(format!("--cpu-prof={}", out_dir.path().display()))
+    .arg("exit_error42.ts")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert_eq!(output.status.code(), Some(42));
+  let stderr = std::str::from_utf8(&output.stderr).unwrap();
+  assert!(stderr.contains("CPU profile written to"));
+  assert_eq!(std::fs::read_dir(out_dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn cpu_prof_quiet() {
+  let out_dir = TempDir::new().expect("tempdir fail");
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("run")
+    .arg("--quiet")
+    .arg(format!("--cpu-prof={}", out_dir.path().display()))
+    .arg("002_hello.ts")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap()
+    .wait_with_output()
+    .unwrap();
+  assert!(output.status.success());
+  assert_eq!(output.stderr, b"");
+  assert_eq!(std::fs::read_dir(out_dir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn heap_snapshot_on_signal() {
+  let cwd = TempDir::new().expect("tempdir fail");
+  let script = util::tests_path().join("inspector4.js");
+  let mut child = util::deno_cmd()
+    .current_dir(cwd.path())
+    .arg("run")
+    .arg("--heap-snapshot-signal=SIGUSR2")
+    .arg(script)
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+
+  // Wait until the script is running before sending the signal.
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  assert_eq!(stdout_lines.next().unwrap(), "hello");
+
+  nix::sys::signal::kill(
+    nix::unistd::Pid::from_raw(child.id() as i32),
+    nix::sys::signal::Signal::SIGUSR2,
+  )
+  .unwrap();
+
+  let stderr = child.stderr.as_mut().unwrap();
+  let mut stderr_lines =
+    std::io::BufReader::new(stderr).lines().map(|r| r.unwrap());
+  assert!(stderr_lines
+    .next()
+    .unwrap()
+    .starts_with("Heap snapshot written to"));
+  child.kill().unwrap();
+  child.wait().unwrap();
+
+  let snapshots = std::fs::read_dir(cwd.path())
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .filter(|path| path.extension().unwrap() == "heapsnapshot")
+    .collect::<Vec<_>>();
+  assert_eq!(snapshots.len(), 1);
+  let snapshot: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(&snapshots[0]).unwrap())
+      .unwrap();
+  assert!(snapshot["snapshot"]["meta"].is_object());
+}
+
 #[test]
 fn exec_path() {
   let output = util::deno_cmd()
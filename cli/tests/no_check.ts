@@ -0,0 +1,5 @@
+import { greet } from "./subdir/no_check_greet.ts";
+
+// Type errors are not reported with --no-check.
+const count: string = 1;
+greet(count);
@@ -0,0 +1,4 @@
+// 4548 redirects to 4546, which redirects to 4545.
+import { redirect } from "http://localhost:4548/cli/tests/subdir/redirects/redirect1.js";
+import { redirect as again } from "http://localhost:4546/cli/tests/subdir/redirects/redirect1.js";
+console.log(redirect, redirect === again);
@@ -0,0 +1,6 @@
+const retained = [];
+self.onmessage = function () {
+  while (true) {
+    retained.push(new Array(1024 * 1024).fill(retained.length));
+  }
+};
@@ -0,0 +1,5 @@
+onmessage = function (e) {
+  // Transfer ArrayBuffers back instead of copying them.
+  const transfer = e.data instanceof ArrayBuffer ? [e.data] : [];
+  postMessage(e.data, transfer);
+};
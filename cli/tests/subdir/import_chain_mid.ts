@@ -0,0 +1,2 @@
+import "./does_not_exist.ts";
+import "./does_not_exist_either.ts";
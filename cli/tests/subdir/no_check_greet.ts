@@ -0,0 +1,8 @@
+interface Greeting {
+  count: number;
+}
+
+export function greet(count: number): void {
+  const greeting: Greeting = { count };
+  console.log(`Hello ${greeting.count} time(s)`);
+}
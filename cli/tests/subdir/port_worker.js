@@ -0,0 +1,12 @@
+onmessage = function (e) {
+  const [port] = e.ports;
+  if (e.data === "send") {
+    port.postMessage("hello from the other worker");
+    port.close();
+  } else {
+    port.onmessage = (portEvent) => {
+      postMessage(portEvent.data);
+      port.close();
+    };
+  }
+};
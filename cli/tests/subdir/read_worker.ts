@@ -0,0 +1,8 @@
+onmessage = async function (e): Promise<void> {
+  try {
+    await Deno.readTextFile(e.data);
+    postMessage("ok");
+  } catch (err) {
+    postMessage(err.name);
+  }
+};
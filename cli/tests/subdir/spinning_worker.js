@@ -0,0 +1,5 @@
+self.onmessage = function () {
+  while (true) {
+    // spin
+  }
+};
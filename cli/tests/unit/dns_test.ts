@@ -0,0 +1,127 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { assertEquals, assertThrowsAsync, unitTest } from "./test_util.ts";
+
+const nameServer = { ipAddr: "127.0.0.1", port: 4553 };
+
+function encodeName(name: string): number[] {
+  const bytes: number[] = [];
+  for (const label of name.split(".").filter((l) => l.length > 0)) {
+    bytes.push(label.length, ...new TextEncoder().encode(label));
+  }
+  bytes.push(0);
+  return bytes;
+}
+
+function encodeString(s: string): number[] {
+  const bytes = new TextEncoder().encode(s);
+  return [bytes.length, ...bytes];
+}
+
+function u16(n: number): number[] {
+  return [n >> 8, n & 0xff];
+}
+
+/** Records served by the stub resolver, by query name and type. */
+const records: Record<string, number[][]> = {
+  "example.com/1": [[127, 0, 0, 1]],
+  "example.com/15": [[...u16(10), ...encodeName("mail.example.com")]],
+  "example.com/16": [[...encodeString("v=spf1"), ...encodeString("-all")]],
+  "_sip._udp.example.com/33": [
+    [...u16(0), ...u16(5), ...u16(5060), ...encodeName("sip.example.com")],
+  ],
+};
+
+/** Answers each query with the matching `records`, or with NXDOMAIN. */
+async function serveDns(socket: Deno.DatagramConn): Promise<void> {
+  while (true) {
+    let query: Uint8Array, addr: Deno.Addr;
+    try {
+      [query, addr] = await socket.receive();
+    } catch {
+      // The socket was closed.
+      return;
+    }
+    // Skip the header, then read the question.
+    let offset = 12;
+    const labels = [];
+    while (query[offset] !== 0) {
+      const label = query.subarray(offset + 1, offset + 1 + query[offset]);
+      labels.push(new TextDecoder().decode(label));
+      offset += label.length + 1;
+    }
+    const questionEnd = offset + 5;
+    const type = (query[offset + 1] << 8) | query[offset + 2];
+    const answers = records[`${labels.join(".").toLowerCase()}/${type}`] ?? [];
+
+    const response = [
+      query[0],
+      query[1],
+      // QR, RD and RA are set. RCODE is NXDOMAIN if there are no answers.
+      0x81,
+      answers.length > 0 ? 0x80 : 0x83,
+      ...u16(1),
+      ...u16(answers.length),
+      ...u16(0),
+      ...u16(0),
+      ...query.subarray(12, questionEnd),
+    ];
+    for (const rdata of answers) {
+      // A pointer to the name in the question.
+      response.push(0xc0, 12, ...u16(type), ...u16(1), 0, 0, 0, 60);
+      response.push(...u16(rdata.length), ...rdata);
+    }
+    await socket.send(new Uint8Array(response), addr);
+  }
+}
+
+unitTest(async function resolveDnsPermissionDenied(): Promise<void> {
+  await assertThrowsAsync(async () => {
+    await Deno.resolveDns("example.com", "A", { nameServer });
+  }, Deno.errors.PermissionDenied);
+});
+
+unitTest(
+  { perms: { net: true } },
+  async function resolveDnsInvalidRecordType(): Promise<void> {
+    await assertThrowsAsync(
+      async () => {
+        // eslint-disable-next-line @typescript-eslint/no-explicit-any
+        await Deno.resolveDns("example.com", "NS" as any, { nameServer });
+      },
+      TypeError,
+      "Unsupported record type: NS"
+    );
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function resolveDnsWithStubResolver(): Promise<void> {
+    const socket = Deno.listenDatagram({
+      hostname: "127.0.0.1",
+      port: nameServer.port,
+      transport: "udp",
+    });
+    const server = serveDns(socket);
+
+    assertEquals(await Deno.resolveDns("example.com", "A", { nameServer }), [
+      "127.0.0.1",
+    ]);
+    assertEquals(await Deno.resolveDns("example.com", "MX", { nameServer }), [
+      { preference: 10, exchange: "mail.example.com." },
+    ]);
+    assertEquals(await Deno.resolveDns("example.com", "TXT", { nameServer }), [
+      ["v=spf1", "-all"],
+    ]);
+    assertEquals(
+      await Deno.resolveDns("_sip._udp.example.com", "SRV", { nameServer }),
+      [{ priority: 0, weight: 5, port: 5060, target: "sip.example.com." }]
+    );
+    await assertThrowsAsync(async () => {
+      await Deno.resolveDns("missing.example.com", "A", { nameServer });
+    }, Deno.errors.NotFound);
+
+    socket.close();
+    await server;
+  }
+);
@@ -898,3 +898,110 @@ unitTest(
     }
   }
 );
+
+unitTest(
+  { perms: { net: true, read: true } },
+  async function fetchWithCustomHttpClient(): Promise<void> {
+    const caData = Deno.readTextFileSync("cli/tests/tls/RootCA.pem");
+    const client = Deno.createHttpClient({ caData });
+    const response = await fetch(
+      "https://localhost:5545/cli/tests/fixture.json",
+      { client }
+    );
+    const json = await response.json();
+    assertEquals(json.name, "deno");
+    client.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchWithClosedHttpClient(): Promise<void> {
+    const client = Deno.createHttpClient();
+    client.close();
+    await assertThrowsAsync(async () => {
+      await fetch("http://localhost:4545/cli/tests/fixture.json", { client });
+    }, Deno.errors.BadResource);
+  }
+);
+
+unitTest(
+  { perms: { net: false } },
+  function createHttpClientProxyPerm(): void {
+    assertThrows(() => {
+      Deno.createHttpClient({ proxy: "http://localhost:4555" });
+    }, Deno.errors.PermissionDenied);
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchStreamedRequestBody(): Promise<void> {
+    const chunks = ["Hello", ", ", "World", "!"];
+    const body = new ReadableStream<Uint8Array>({
+      start(controller): void {
+        for (const chunk of chunks) {
+          controller.enqueue(new TextEncoder().encode(chunk));
+        }
+        controller.close();
+      },
+    });
+    const response = await fetch("http://localhost:4545/echo_server", {
+      method: "POST",
+      body,
+    });
+    assertEquals(await response.text(), chunks.join(""));
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortBeforeResponse(): Promise<void> {
+    const controller = new AbortController();
+    const promise = fetch("http://localhost:4545/cli/tests/fixture.json", {
+      signal: controller.signal,
+    });
+    controller.abort();
+    try {
+      await promise;
+      fail("Response should have been aborted");
+    } catch (e) {
+      assert(e instanceof DOMException);
+      assertEquals(e.name, "AbortError");
+    }
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAlreadyAborted(): Promise<void> {
+    const controller = new AbortController();
+    controller.abort();
+    try {
+      await fetch("http://localhost:4545/cli/tests/fixture.json", {
+        signal: controller.signal,
+      });
+      fail("Response should have been aborted");
+    } catch (e) {
+      assertEquals(e.name, "AbortError");
+    }
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function fetchAbortWhileReadingBody(): Promise<void> {
+    const controller = new AbortController();
+    const response = await fetch(
+      "http://localhost:4545/cli/tests/fixture.json",
+      { signal: controller.signal }
+    );
+    controller.abort();
+    try {
+      await response.text();
+      fail("Body should have been aborted");
+    } catch (e) {
+      assertEquals(e.name, "AbortError");
+    }
+  }
+);
@@ -0,0 +1,77 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { assert, assertEquals, unitTest } from "./test_util.ts";
+
+unitTest({ perms: { net: true } }, async function httpServerBasic(): Promise<
+  void
+> {
+  const listener = Deno.listen({ port: 4501 });
+  const promise = (async (): Promise<void> => {
+    const conn = await listener.accept();
+    const httpConn = Deno.serveHttp(conn);
+    const event = await httpConn.nextRequest();
+    assert(event);
+    const { request, respondWith } = event;
+    assertEquals(request.method, "GET");
+    assertEquals(request.url, "http://127.0.0.1:4501/foo?bar=baz");
+    assertEquals(await request.text(), "");
+    await respondWith(
+      new Response("Hello World", { headers: { "x-foo": "bar" } })
+    );
+    httpConn.close();
+  })();
+
+  const resp = await fetch("http://127.0.0.1:4501/foo?bar=baz");
+  assertEquals(resp.status, 200);
+  assertEquals(resp.headers.get("x-foo"), "bar");
+  assertEquals(await resp.text(), "Hello World");
+  await promise;
+  listener.close();
+});
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerStreamingBodies(): Promise<void> {
+    const listener = Deno.listen({ port: 4501 });
+    const promise = (async (): Promise<void> => {
+      const conn = await listener.accept();
+      const httpConn = Deno.serveHttp(conn);
+      for await (const { request, respondWith } of httpConn) {
+        assertEquals(request.method, "POST");
+        const text = await request.text();
+        const body = new ReadableStream<Uint8Array>({
+          start(controller): void {
+            for (const word of text.split(" ")) {
+              controller.enqueue(new TextEncoder().encode(word + "\n"));
+            }
+            controller.close();
+          },
+        });
+        await respondWith(new Response(body, { status: 201 }));
+        httpConn.close();
+      }
+    })();
+
+    const resp = await fetch("http://127.0.0.1:4501/", {
+      method: "POST",
+      body: "hello streaming world",
+    });
+    assertEquals(resp.status, 201);
+    assertEquals(resp.headers.get("transfer-encoding"), "chunked");
+    assertEquals(await resp.text(), "hello\nstreaming\nworld\n");
+    await promise;
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { net: true } },
+  async function httpServerConnectionClosedByPeer(): Promise<void> {
+    const listener = Deno.listen({ port: 4501 });
+    const client = await Deno.connect({ port: 4501 });
+    const conn = await listener.accept();
+    const httpConn = Deno.serveHttp(conn);
+    client.close();
+    assertEquals(await httpConn.nextRequest(), null);
+    listener.close();
+  }
+);
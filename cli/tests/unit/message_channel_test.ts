@@ -0,0 +1,82 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import { unitTest, assert, assertEquals, assertThrows } from "./test_util.ts";
+
+unitTest(async function messageChannelStructuredClone(): Promise<void> {
+  const { port1, port2 } = new MessageChannel();
+  const cyclic: { self?: unknown } = {};
+  cyclic.self = cyclic;
+  const received = new Promise<MessageEvent>((resolve): void => {
+    port2.onmessage = resolve;
+  });
+  port1.postMessage({
+    map: new Map([["a", 1]]),
+    date: new Date(0),
+    bytes: new Uint8Array([1, 2, 3]),
+    cyclic,
+  });
+  const { data } = await received;
+  assertEquals(data.map.get("a"), 1);
+  assertEquals(data.date.getTime(), 0);
+  assertEquals(Array.from(data.bytes), [1, 2, 3]);
+  assert(data.cyclic.self === data.cyclic);
+  port1.close();
+  port2.close();
+});
+
+unitTest(async function messageChannelTransferArrayBuffer(): Promise<void> {
+  const { port1, port2 } = new MessageChannel();
+  const received = new Promise<MessageEvent>((resolve): void => {
+    port2.onmessage = resolve;
+  });
+  const buf = new Uint8Array([1, 2, 3]).buffer;
+  port1.postMessage(buf, [buf]);
+  assertEquals(buf.byteLength, 0);
+  const { data } = await received;
+  assertEquals(Array.from(new Uint8Array(data)), [1, 2, 3]);
+  port1.close();
+  port2.close();
+});
+
+unitTest(async function messageChannelTransferPort(): Promise<void> {
+  const channel1 = new MessageChannel();
+  const channel2 = new MessageChannel();
+  const received = new Promise<MessageEvent>((resolve): void => {
+    channel1.port2.onmessage = resolve;
+  });
+  channel1.port1.postMessage("port", [channel2.port1]);
+  const event = await received;
+  assertEquals(event.data, "port");
+  assertEquals(event.ports.length, 1);
+
+  const echoed = new Promise<MessageEvent>((resolve): void => {
+    channel2.port2.onmessage = resolve;
+  });
+  event.ports[0].postMessage("through transferred port");
+  assertEquals((await echoed).data, "through transferred port");
+
+  for (const port of [channel1.port1, channel1.port2, channel2.port2]) {
+    port.close();
+  }
+  event.ports[0].close();
+});
+
+unitTest(function messageChannelDataCloneError(): void {
+  const { port1, port2 } = new MessageChannel();
+  assertThrows(() => {
+    port1.postMessage({ f(): void {} });
+  }, DOMException);
+  assertThrows(() => {
+    port1.postMessage(null, [port1]);
+  }, DOMException);
+  port1.close();
+  port2.close();
+});
+
+unitTest(function messageChannelPostAfterClose(): void {
+  const { port1, port2 } = new MessageChannel();
+  port1.close();
+  const buf = new Uint8Array([1, 2, 3]).buffer;
+  port1.postMessage(buf, [buf]);
+  assertEquals(buf.byteLength, 3);
+  port2.close();
+});
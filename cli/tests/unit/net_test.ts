@@ -533,3 +533,84 @@ unitTest(
     await resolvable;
   }
 );
+
+unitTest({ perms: { net: true } }, async function netTcpSetNoDelay(): Promise<
+  void
+> {
+  const listener = Deno.listen({ hostname: "127.0.0.1", port: 3500 });
+  const acceptPromise = listener.accept();
+  const conn = await Deno.connect({ hostname: "127.0.0.1", port: 3500 });
+  const accepted = await acceptPromise;
+  conn.setNoDelay();
+  conn.setNoDelay(false);
+  accepted.setKeepAlive();
+  accepted.setKeepAlive(true, 1000);
+  accepted.setKeepAlive(false);
+  conn.close();
+  accepted.close();
+  listener.close();
+});
+
+unitTest(
+  {
+    perms: { net: true },
+    ignore: Deno.build.os === "windows",
+  },
+  function netTcpListenReusePort(): void {
+    const options = { hostname: "127.0.0.1", port: 3500, reusePort: true };
+    const listener1 = Deno.listen(options);
+    const listener2 = Deno.listen(options);
+    assertEquals(listener2.addr, listener1.addr);
+    listener1.close();
+    listener2.close();
+  }
+);
+
+unitTest({ perms: { net: true } }, function netTcpListenAddrInUse(): void {
+  const listener = Deno.listen({ hostname: "127.0.0.1", port: 3500 });
+  assertThrows(() => {
+    Deno.listen({ hostname: "127.0.0.1", port: 3500 });
+  }, Deno.errors.AddrInUse);
+  listener.close();
+});
+
+unitTest(
+  {
+    perms: { net: true },
+    ignore: Deno.build.os === "windows",
+  },
+  function netUdpSetBroadcast(): void {
+    const socket = Deno.listenDatagram({
+      hostname: "127.0.0.1",
+      port: 3500,
+      transport: "udp",
+    });
+    socket.setBroadcast();
+    socket.setBroadcast(false);
+    socket.close();
+  }
+);
+
+unitTest(
+  {
+    perms: { net: true },
+    ignore: Deno.build.os === "windows",
+  },
+  function netUdpMulticast(): void {
+    const socket = Deno.listenDatagram({
+      hostname: "0.0.0.0",
+      port: 3500,
+      transport: "udp",
+    });
+    assertThrows(
+      () => {
+        socket.joinMulticastV4("127.0.0.1");
+      },
+      TypeError,
+      "not a multicast address"
+    );
+    socket.joinMulticastV4("224.0.0.251", "0.0.0.0");
+    socket.leaveMulticast("224.0.0.251", "0.0.0.0");
+    socket.close();
+  }
+);
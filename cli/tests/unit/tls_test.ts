@@ -232,3 +232,152 @@ unitTest(
     conn.close();
   }
 );
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function listenTLSAlpn(): Promise<void> {
+    const listener = Deno.listenTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/localhost.crt",
+      keyFile: "cli/tests/tls/localhost.key",
+      alpnProtocols: ["h2", "foo"],
+    });
+    const accepted = listener.accept();
+    const conn = (await Deno.connectTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/RootCA.pem",
+      alpnProtocols: ["foo", "bar"],
+    })) as Deno.TlsConn;
+    const serverConn = (await accepted) as Deno.TlsConn;
+
+    assertEquals(conn.alpnProtocol, "foo");
+    assertEquals(serverConn.alpnProtocol, "foo");
+    assertEquals(conn.peerCertificates.length, 1);
+    assert(conn.peerCertificates[0].startsWith("-----BEGIN CERTIFICATE-----"));
+    assertEquals(serverConn.peerCertificates, []);
+
+    conn.close();
+    serverConn.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function listenTLSClientCertificateRequired(): Promise<void> {
+    const listener = Deno.listenTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/localhost.crt",
+      keyFile: "cli/tests/tls/localhost.key",
+      clientCaFile: "cli/tests/tls/RootCA.pem",
+    });
+    const connected = Deno.connectTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/RootCA.pem",
+    }).catch(() => null);
+    await assertThrowsAsync(async () => {
+      await listener.accept();
+    });
+    // With TLS 1.3 the client only learns about the rejection after its side
+    // of the handshake completed.
+    const conn = await connected;
+    conn?.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  function listenTLSInvalidClientAuth(): void {
+    assertThrows(
+      () => {
+        Deno.listenTls({
+          hostname: "localhost",
+          port: 3500,
+          certFile: "cli/tests/tls/localhost.crt",
+          keyFile: "cli/tests/tls/localhost.key",
+          clientAuth: "require",
+        });
+      },
+      TypeError,
+      "clientAuth requires clientCaFile"
+    );
+  }
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function connectTLSClientCertificate(): Promise<void> {
+    const listener = Deno.listenTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/localhost.crt",
+      keyFile: "cli/tests/tls/localhost.key",
+      clientCaFile: "cli/tests/tls/RootCA.pem",
+    });
+    const accepted = listener.accept();
+    const conn = await Deno.connectTls({
+      hostname: "localhost",
+      port: 3500,
+      certFile: "cli/tests/tls/RootCA.pem",
+      clientCertFile: "cli/tests/tls/client.crt",
+      clientKeyFile: "cli/tests/tls/client.key",
+    });
+    const serverConn = (await accepted) as Deno.TlsConn;
+    assertEquals(serverConn.peerCertificates.length, 1);
+
+    await conn.write(encoder.encode("hello"));
+    const buf = new Uint8Array(5);
+    assertEquals(await serverConn.read(buf), 5);
+    assertEquals(decoder.decode(buf), "hello");
+
+    conn.close();
+    serverConn.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function connectTLSServerName(): Promise<void> {
+    const listener = Deno.listenTls({
+      hostname: "127.0.0.1",
+      port: 3500,
+      certFile: "cli/tests/tls/localhost.crt",
+      keyFile: "cli/tests/tls/localhost.key",
+    });
+    const accepted = listener.accept();
+    // The certificate is only valid for "localhost".
+    const conn = await Deno.connectTls({
+      hostname: "127.0.0.1",
+      port: 3500,
+      certFile: "cli/tests/tls/RootCA.pem",
+      serverName: "localhost",
+    });
+    const serverConn = await accepted;
+    conn.close();
+    serverConn.close();
+    listener.close();
+  }
+);
+
+unitTest(
+  { perms: { read: true, net: true } },
+  async function connectTLSClientCertificateWithoutKey(): Promise<void> {
+    await assertThrowsAsync(
+      async () => {
+        await Deno.connectTls({
+          hostname: "localhost",
+          port: 3500,
+          clientCertFile: "cli/tests/tls/client.crt",
+        });
+      },
+      TypeError,
+      "clientCertFile and clientKeyFile must be passed together"
+    );
+  }
+);
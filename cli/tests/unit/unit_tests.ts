@@ -17,6 +17,7 @@ import "./custom_event_test.ts";
 import "./dir_test.ts";
 import "./dispatch_minimal_test.ts";
 import "./dispatch_json_test.ts";
+import "./dns_test.ts";
 import "./dom_exception_test.ts";
 import "./error_stack_test.ts";
 import "./event_test.ts";
@@ -30,10 +31,12 @@ import "./fs_events_test.ts";
 import "./get_random_values_test.ts";
 import "./globals_test.ts";
 import "./headers_test.ts";
+import "./http_test.ts";
 import "./internals_test.ts";
 import "./io_test.ts";
 import "./link_test.ts";
 import "./make_temp_test.ts";
+import "./message_channel_test.ts";
 import "./metrics_test.ts";
 import "./dom_iterable_test.ts";
 import "./mkdir_test.ts";
@@ -70,6 +73,7 @@ import "./umask_test.ts";
 import "./url_test.ts";
 import "./url_search_params_test.ts";
 import "./utime_test.ts";
+import "./websocket_test.ts";
 import "./write_file_test.ts";
 import "./write_text_file_test.ts";
 import "./performance_test.ts";
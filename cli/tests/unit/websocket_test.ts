@@ -0,0 +1,83 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+  createResolvable,
+  unitTest,
+} from "./test_util.ts";
+
+unitTest(function websocketInvalidUrl(): void {
+  assertThrows(
+    () => new WebSocket("http://localhost:4502"),
+    DOMException,
+    "Only ws: and wss: schemes are allowed"
+  );
+  assertThrows(() => new WebSocket("not a url"), DOMException);
+});
+
+unitTest(async function websocketPermissionDenied(): Promise<void> {
+  const closed = createResolvable<CloseEvent>();
+  const ws = new WebSocket("ws://localhost:4502");
+  ws.onerror = (e): void => {
+    assert(e instanceof ErrorEvent);
+    assert(e.message.includes("network access"));
+  };
+  ws.onclose = (e): void => closed.resolve(e);
+  const event = await closed;
+  assertEquals(event.code, 1006);
+  assertEquals(event.wasClean, false);
+  assertEquals(ws.readyState, WebSocket.CLOSED);
+});
+
+unitTest({ perms: { net: true } }, async function websocketEcho(): Promise<
+  void
+> {
+  const listener = Deno.listen({ port: 4502 });
+  const serverClosed = createResolvable<CloseEvent>();
+  const promise = (async (): Promise<void> => {
+    const conn = await listener.accept();
+    const socket = await Deno.upgradeWebSocket(conn, { protocols: ["echo"] });
+    assertEquals(socket.url, "/path");
+    assertEquals(socket.protocol, "echo");
+    socket.binaryType = "arraybuffer";
+    socket.onmessage = (e): void => socket.send(e.data);
+    socket.onclose = (e): void => serverClosed.resolve(e);
+  })();
+
+  const ws = new WebSocket("ws://localhost:4502/path", ["foo", "echo"]);
+  assertEquals(ws.readyState, WebSocket.CONNECTING);
+  const opened = createResolvable<void>();
+  ws.onopen = (): void => opened.resolve();
+  await opened;
+  assertEquals(ws.readyState, WebSocket.OPEN);
+  assertEquals(ws.protocol, "echo");
+  await promise;
+
+  const messages: unknown[] = [];
+  const received = createResolvable<void>();
+  ws.binaryType = "arraybuffer";
+  ws.onmessage = (e): void => {
+    messages.push(e.data);
+    if (messages.length === 2) {
+      received.resolve();
+    }
+  };
+  ws.send("hello");
+  ws.send(new Uint8Array([1, 2, 3]));
+  await received;
+  assertEquals(messages[0], "hello");
+  assertEquals(new Uint8Array(messages[1] as ArrayBuffer), [1, 2, 3]);
+
+  const closed = createResolvable<CloseEvent>();
+  ws.onclose = (e): void => closed.resolve(e);
+  ws.close(1000, "done");
+  assertEquals(ws.readyState, WebSocket.CLOSING);
+  const event = await closed;
+  assertEquals(event.wasClean, true);
+  assertEquals(event.code, 1000);
+  assertEquals(event.reason, "done");
+  const serverEvent = await serverClosed;
+  assertEquals(serverEvent.code, 1000);
+  listener.close();
+});
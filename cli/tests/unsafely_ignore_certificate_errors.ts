@@ -0,0 +1,3 @@
+const conn = await Deno.connectTls({ hostname: "localhost", port: 5545 });
+console.log("connected");
+conn.close();
@@ -0,0 +1,6 @@
+import { printHello } from "https://localhost:5545/cli/tests/subdir/mod2.ts";
+
+printHello();
+const res = await fetch("https://localhost:5545/cli/tests/fixture.json");
+console.log(res.status);
+await res.text();
@@ -0,0 +1,2 @@
+console.log("start");
+setTimeout(() => console.log("unreachable"), 60000);
@@ -7,7 +7,11 @@
 // yet implemented. Once it gets implemented this file should be
 // again moved to `cli/js/` as an unit test file.
 
-import { assert, assertEquals } from "../../std/testing/asserts.ts";
+import {
+  assert,
+  assertEquals,
+  assertThrows,
+} from "../../std/testing/asserts.ts";
 
 export interface ResolvableMethods<T> {
   resolve: (value?: T | PromiseLike<T>) => void;
@@ -316,3 +320,156 @@ Deno.test({
     w.terminate();
   },
 });
+
+Deno.test({
+  name: "worker terminated on CPU time limit",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const w = new Worker(
+      new URL("subdir/spinning_worker.js", import.meta.url).href,
+      { type: "module", resourceLimits: { maxCpuTimeMs: 100 } }
+    );
+    w.onerror = (e: ErrorEvent): void => {
+      e.preventDefault();
+      assertEquals(e.error.name, "ResourceLimitError");
+      assertEquals(e.error.limit, "maxCpuTimeMs");
+      assertEquals(e.message, "Worker exceeded its CPU time limit of 100 ms");
+      promise.resolve();
+    };
+    w.postMessage(null);
+    await promise;
+  },
+});
+
+Deno.test({
+  name: "worker terminated on heap size limit",
+  fn: async function (): Promise<void> {
+    const promise = createResolvable();
+    const w = new Worker(
+      new URL("subdir/allocating_worker.js", import.meta.url).href,
+      { type: "module", resourceLimits: { maxHeapSizeMb: 32 } }
+    );
+    w.onerror = (e: ErrorEvent): void => {
+      e.preventDefault();
+      assertEquals(e.error.name, "ResourceLimitError");
+      assertEquals(e.error.limit, "maxHeapSizeMb");
+      promise.resolve();
+    };
+    w.postMessage(null);
+    await promise;
+  },
+});
+
+Deno.test({
+  name: "worker with restricted permissions",
+  fn: async function (): Promise<void> {
+    const w = new Worker(
+      new URL("subdir/read_worker.ts", import.meta.url).href,
+      {
+        type: "module",
+        deno: { namespace: true, permissions: { read: ["./subdir"] } },
+      }
+    );
+    const results: string[] = [];
+    const promise = createResolvable();
+    w.onmessage = (e): void => {
+      results.push(e.data);
+      if (results.length === 2) {
+        promise.resolve();
+      }
+    };
+    w.postMessage("subdir/test_worker.js");
+    w.postMessage("workers_test.ts");
+    await promise;
+    assertEquals(results.sort(), ["PermissionDenied", "ok"]);
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker permissions can't escalate parent permissions",
+  fn: function (): void {
+    assertThrows(() => {
+      new Worker(new URL("subdir/read_worker.ts", import.meta.url).href, {
+        type: "module",
+        deno: { namespace: true, permissions: { env: true } },
+      });
+    }, Deno.errors.PermissionDenied);
+  },
+});
+
+Deno.test({
+  name: "worker structured clone and transfer",
+  fn: async function (): Promise<void> {
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" }
+    );
+    const events: MessageEvent[] = [];
+    const promise = createResolvable();
+    w.onmessage = (e): void => {
+      events.push(e);
+      if (events.length === 2) {
+        promise.resolve();
+      }
+    };
+    const cyclic: { self?: unknown } = {};
+    cyclic.self = cyclic;
+    w.postMessage({
+      map: new Map([["a", 1]]),
+      date: new Date(0),
+      bytes: new Uint8Array([1, 2, 3]),
+      cyclic,
+    });
+    const buf = new Uint8Array([4, 5, 6]).buffer;
+    w.postMessage(buf, [buf]);
+    assertEquals(buf.byteLength, 0);
+    await promise;
+
+    const { data } = events[0];
+    assertEquals(data.map.get("a"), 1);
+    assertEquals(data.date.getTime(), 0);
+    assertEquals(Array.from(data.bytes), [1, 2, 3]);
+    assert(data.cyclic.self === data.cyclic);
+    assertEquals(Array.from(new Uint8Array(events[1].data)), [4, 5, 6]);
+    w.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker to worker communication through MessageChannel",
+  fn: async function (): Promise<void> {
+    const sender = new Worker(
+      new URL("subdir/port_worker.js", import.meta.url).href,
+      { type: "module" }
+    );
+    const receiver = new Worker(
+      new URL("subdir/port_worker.js", import.meta.url).href,
+      { type: "module" }
+    );
+    const promise = createResolvable<string>();
+    receiver.onmessage = (e): void => promise.resolve(e.data);
+
+    const { port1, port2 } = new MessageChannel();
+    receiver.postMessage("receive", [port2]);
+    sender.postMessage("send", [port1]);
+    assertEquals(await promise, "hello from the other worker");
+
+    sender.terminate();
+    receiver.terminate();
+  },
+});
+
+Deno.test({
+  name: "worker postMessage after terminate doesn't transfer",
+  fn: function (): void {
+    const w = new Worker(
+      new URL("subdir/echo_worker.js", import.meta.url).href,
+      { type: "module" }
+    );
+    w.terminate();
+    const buf = new Uint8Array([1, 2, 3]).buffer;
+    w.postMessage(buf, [buf]);
+    assertEquals(buf.byteLength, 3);
+  },
+});
@@ -15,6 +15,7 @@ use crate::msg::MediaType;
 use crate::op_error::OpError;
 use crate::ops;
 use crate::permissions::Permissions;
+use crate::resource_limits::ResourceLimits;
 use crate::source_maps::SourceMapGetter;
 use crate::startup_data;
 use crate::state::State;
@@ -22,10 +23,12 @@ use crate::swc_common::comments::CommentKind;
 use crate::swc_common::Span;
 use crate::swc_ecma_ast;
 use crate::swc_util::AstParser;
+use crate::swc_util::JsxOptions;
 use crate::swc_util::SwcDiagnosticBuffer;
 use crate::version;
 use crate::web_worker::WebWorker;
 use crate::worker::WorkerEvent;
+use crate::worker::WorkerMessage;
 use core::task::Context;
 use deno_core::Buf;
 use deno_core::ErrBox;
@@ -122,10 +125,17 @@ pub const AVAILABLE_LIBS: &[&str] = &[
   "webworker.importscripts",
 ];
 
+/// Number of hex digits of the compiler config hash in the extension of
+/// `.buildinfo` files.
+pub const BUILD_INFO_HASH_LEN: usize = 16;
+
 #[derive(Debug, Clone)]
 pub struct CompiledModule {
   pub code: String,
   pub name: String,
+  /// URLs that were redirected through to reach `name`, starting with the
+  /// requested one.
+  pub redirects: Vec<String>,
 }
 
 pub struct CompilerWorker(WebWorker);
@@ -133,7 +143,13 @@ pub struct CompilerWorker(WebWorker);
 impl CompilerWorker {
   pub fn new(name: String, startup_data: StartupData, state: State) -> Self {
     let state_ = state.clone();
-    let mut worker = WebWorker::new(name, startup_data, state_, false);
+    let mut worker = WebWorker::new(
+      name,
+      startup_data,
+      state_,
+      false,
+      ResourceLimits::default(),
+    );
     {
       let isolate = &mut worker.isolate;
       ops::compiler::init(isolate, &state);
@@ -168,6 +184,10 @@ impl Future for CompilerWorker {
 lazy_static! {
   static ref CHECK_JS_RE: Regex =
     Regex::new(r#""checkJs"\s*?:\s*?true"#).unwrap();
+  static ref JSX_FACTORY_RE: Regex =
+    Regex::new(r#""jsxFactory"\s*?:\s*?"([^"]+)""#).unwrap();
+  static ref JSX_FRAGMENT_FACTORY_RE: Regex =
+    Regex::new(r#""jsxFragmentFactory"\s*?:\s*?"([^"]+)""#).unwrap();
 }
 
 /// Create a new worker with snapshot of TS compiler and setup compiler's
@@ -180,14 +200,9 @@ fn create_compiler_worker(
   // like 'eval', 'repl'
   let entry_point =
     ModuleSpecifier::resolve_url_or_path("./__$deno$ts_compiler.ts").unwrap();
-  let worker_state = State::new(
-    global_state.clone(),
-    Some(permissions),
-    entry_point,
-    None,
-    true,
-  )
-  .expect("Unable to create worker state");
+  let worker_state =
+    State::new(global_state.clone(), Some(permissions), entry_point, true)
+      .expect("Unable to create worker state");
 
   // TODO(bartlomieju): this metric is never used anywhere
   // Count how many times we start the compiler worker.
@@ -218,6 +233,9 @@ pub struct CompilerConfig {
   pub content: Option<Vec<u8>>,
   pub hash: Vec<u8>,
   pub compile_js: bool,
+  /// From `compilerOptions.jsxFactory` and `jsxFragmentFactory`, for JSX
+  /// that is transformed without the compiler.
+  pub jsx: JsxOptions,
 }
 
 impl CompilerConfig {
@@ -271,11 +289,23 @@ impl CompilerConfig {
       false
     };
 
+    let mut jsx = JsxOptions::default();
+    if let Some(config_content) = &config {
+      let config_str = std::str::from_utf8(config_content)?;
+      if let Some(captures) = JSX_FACTORY_RE.captures(config_str) {
+        jsx.factory = captures[1].to_string();
+      }
+      if let Some(captures) = JSX_FRAGMENT_FACTORY_RE.captures(config_str) {
+        jsx.fragment_factory = captures[1].to_string();
+      }
+    }
+
     let ts_config = Self {
       path: config_path.unwrap_or_else(|| Ok(PathBuf::new())).ok(),
       content: config,
       hash: config_hash,
       compile_js,
+      jsx,
     };
 
     Ok(ts_config)
@@ -285,9 +315,18 @@ impl CompilerConfig {
 /// Information associated with compiled file in cache.
 /// version_hash is used to validate versions of the file
 /// and could be used to remove stale file in cache.
+/// source_hash only depends on the source code, so that `deno cache clean`
+/// can tell whether output is out of date regardless of the compiler config.
+/// It's missing in metadata that was written by older versions.
+/// no_check is set when the file was only transpiled with `--no-check`, so
+/// that it is compiled again when modules are type checked.
 #[derive(Deserialize, Serialize)]
 pub struct CompiledFileMetadata {
   pub version_hash: String,
+  #[serde(default)]
+  pub source_hash: Option<String>,
+  #[serde(default)]
+  pub no_check: bool,
 }
 
 impl CompiledFileMetadata {
@@ -312,6 +351,19 @@ fn source_code_version_hash(
   crate::checksum::gen(&[source_code, version.as_bytes(), config_hash])
 }
 
+/// Emit a SHA256 hash based on source code only.
+pub fn source_code_hash(source_code: &[u8]) -> String {
+  crate::checksum::gen(&[source_code])
+}
+
+/// Extension of the file that the TS program state of a root module is cached
+/// in. It contains a hash of the compiler config, so that the state of one
+/// `--config` isn't used for, or overwritten by, a compilation with another.
+fn build_info_extension(config_hash: &[u8]) -> String {
+  let hash = crate::checksum::gen(&[config_hash]);
+  format!("{}.buildinfo", &hash[..BUILD_INFO_HASH_LEN])
+}
+
 fn maybe_log_stats(maybe_stats: Option<Vec<Stat>>) {
   if let Some(stats) = maybe_stats {
     debug!("DEBUG - Compilation Statistics:");
@@ -334,6 +386,11 @@ pub struct TsCompilerInner {
   pub use_disk_cache: bool,
   /// This setting is controlled by `compilerOptions.checkJs`
   pub compile_js: bool,
+  /// This setting is controlled by `--no-incremental` flag. Unless the flag
+  /// is provided the cached build info is used to skip type checking of an
+  /// unchanged module graph, and is passed to the compiler, so that only
+  /// files affected by a change are type checked again.
+  pub use_incremental: bool,
 }
 
 #[derive(Clone)]
@@ -360,14 +417,6 @@ struct EmittedSource {
   contents: String,
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct BundleResponse {
-  diagnostics: Diagnostic,
-  bundle_output: Option<String>,
-  stats: Option<Vec<Stat>>,
-}
-
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct CompileResponse {
@@ -401,6 +450,7 @@ impl TsCompiler {
   ) -> Result<Self, ErrBox> {
     let config = CompilerConfig::load(flags.config_path.clone())?;
     let use_disk_cache = !flags.reload;
+    let use_incremental = !flags.no_incremental;
 
     Ok(TsCompiler(Arc::new(TsCompilerInner {
       file_fetcher,
@@ -410,6 +460,7 @@ impl TsCompiler {
       config,
       compiled: Mutex::new(HashSet::new()),
       use_disk_cache,
+      use_incremental,
     })))
   }
 
@@ -450,15 +501,35 @@ impl TsCompiler {
     false
   }
 
+  /// Returns true if any module of the graph has compiled source in the cache
+  /// that was only transpiled with `--no-check`.
+  fn has_unchecked_output(&self, module_graph: &ModuleGraph) -> bool {
+    module_graph.values().any(|module_graph_file| {
+      match Url::parse(&module_graph_file.url) {
+        Ok(url) => self
+          .get_metadata(&url)
+          .map_or(false, |metadata| metadata.no_check),
+        Err(_) => false,
+      }
+    })
+  }
+
   fn has_valid_cache(
     &self,
     url: &Url,
     build_info: &Option<String>,
   ) -> Result<bool, ErrBox> {
     if let Some(build_info_str) = build_info.as_ref() {
-      let build_inf_json: Value = serde_json::from_str(build_info_str)?;
-      let program_val = build_inf_json["program"].as_object().unwrap();
-      let file_infos = program_val["fileInfos"].as_object().unwrap();
+      // Corrupt build info is a cache miss.
+      let build_inf_json: Value = match serde_json::from_str(build_info_str) {
+        Ok(json) => json,
+        Err(_) => return Ok(false),
+      };
+      let file_infos = match build_inf_json["program"]["fileInfos"].as_object()
+      {
+        Some(file_infos) => file_infos,
+        None => return Ok(false),
+      };
 
       if !self.has_compiled_source(url) {
         return Ok(false);
@@ -514,26 +585,45 @@ impl TsCompiler {
     source_file: &SourceFile,
     target: TargetLib,
     permissions: Permissions,
-    module_graph: ModuleGraph,
+    module_graph: &ModuleGraph,
     allow_js: bool,
   ) -> Result<(), ErrBox> {
     let module_url = source_file.url.clone();
-    let build_info_key = self
-      .disk_cache
-      .get_cache_filename_with_extension(&module_url, "buildinfo");
-    let build_info = match self.disk_cache.get(&build_info_key) {
-      Ok(bytes) => Some(String::from_utf8(bytes)?),
-      Err(_) => None,
+    let build_info_key = self.get_build_info_filename(&module_url);
+    let (build_info, has_valid_cache) = {
+      // The build info and the compiled files are checked together.
+      let _lock = self.disk_cache.lock_shared()?;
+      let build_info = match self.disk_cache.get(&build_info_key) {
+        Ok(bytes) => String::from_utf8(bytes).ok(),
+        Err(_) => None,
+      };
+      // Modules that were only transpiled with `--no-check` are compiled
+      // again. TS doesn't emit modules that didn't change since the build
+      // info was written, so it isn't used then.
+      let build_info = if self.has_unchecked_output(module_graph) {
+        None
+      } else {
+        build_info
+      };
+      // Only use disk cache if neither `--reload` nor `--no-incremental` flag
+      // was used or this file has already been compiled during current
+      // process lifetime.
+      let has_valid_cache = ((self.use_disk_cache && self.use_incremental)
+        || self.has_compiled(&source_file.url))
+        && self.has_valid_cache(&source_file.url, &build_info)?;
+      (build_info, has_valid_cache)
     };
-
-    // Only use disk cache if `--reload` flag was not used or this file has
-    // already been compiled during current process lifetime.
-    if (self.use_disk_cache || self.has_compiled(&source_file.url))
-      && self.has_valid_cache(&source_file.url, &build_info)?
-    {
+    if has_valid_cache {
       return Ok(());
     }
 
+    // The build info is still written with `--no-incremental`, so that later
+    // runs without the flag can use it.
+    let build_info = if self.use_disk_cache && self.use_incremental {
+      build_info
+    } else {
+      None
+    };
     let module_graph_json =
       serde_json::to_value(module_graph).expect("Failed to serialize data");
     let target = match target {
@@ -561,7 +651,7 @@ impl TsCompiler {
         "config": str::from_utf8(&config_data).unwrap(),
         "cwd": cwd,
         "sourceFileMap": module_graph_json,
-        "buildInfo": if self.use_disk_cache { build_info } else { None },
+        "buildInfo": build_info,
       }),
       _ => json!({
         "type": msg::CompilerRequestType::Compile,
@@ -572,7 +662,7 @@ impl TsCompiler {
         "performance": performance,
         "cwd": cwd,
         "sourceFileMap": module_graph_json,
-        "buildInfo": if self.use_disk_cache { build_info } else { None },
+        "buildInfo": build_info,
       }),
     };
 
@@ -594,6 +684,8 @@ impl TsCompiler {
 
     maybe_log_stats(compile_response.stats);
 
+    // The build info and the emitted files are written together.
+    let _lock = self.disk_cache.lock()?;
     if let Some(build_info) = compile_response.build_info {
       self.cache_build_info(&module_url, build_info)?;
     }
@@ -601,85 +693,56 @@ impl TsCompiler {
     Ok(())
   }
 
-  /// For a given module, generate a single file JavaScript output that includes
-  /// all the dependencies for that module.
-  pub async fn bundle(
-    &self,
-    global_state: GlobalState,
-    module_specifier: ModuleSpecifier,
-  ) -> Result<String, ErrBox> {
-    debug!(
-      "Invoking the compiler to bundle. module_name: {}",
-      module_specifier.to_string()
-    );
-
-    let permissions = Permissions::allow_all();
-    let mut module_graph_loader = ModuleGraphLoader::new(
-      self.file_fetcher.clone(),
-      global_state.maybe_import_map.clone(),
-      permissions.clone(),
-      false,
-      true,
-    );
-    module_graph_loader
-      .add_to_graph(&module_specifier, None)
-      .await?;
-    let module_graph = module_graph_loader.get_graph();
-    let module_graph_json =
-      serde_json::to_value(module_graph).expect("Failed to serialize data");
-
-    let root_names = vec![module_specifier.to_string()];
-    let target = "main";
-    let cwd = std::env::current_dir().unwrap();
-    let performance = match global_state.flags.log_level {
-      Some(Level::Debug) => true,
-      _ => false,
-    };
-
-    let compiler_config = self.config.clone();
-
-    // TODO(bartlomieju): this is non-sense; CompilerConfig's `path` and `content` should
-    // be optional
-    let j = match (compiler_config.path, compiler_config.content) {
-      (Some(config_path), Some(config_data)) => json!({
-        "type": msg::CompilerRequestType::Bundle,
-        "target": target,
-        "rootNames": root_names,
-        "unstable": self.flags.unstable,
-        "performance": performance,
-        "configPath": config_path,
-        "config": str::from_utf8(&config_data).unwrap(),
-        "cwd": cwd,
-        "sourceFileMap": module_graph_json,
-      }),
-      _ => json!({
-        "type": msg::CompilerRequestType::Bundle,
-        "target": target,
-        "rootNames": root_names,
-        "unstable": self.flags.unstable,
-        "performance": performance,
-        "cwd": cwd,
-        "sourceFileMap": module_graph_json,
-      }),
-    };
-
-    let req_msg = j.to_string().into_boxed_str().into_boxed_bytes();
-
-    let msg =
-      execute_in_same_thread(global_state, permissions, req_msg).await?;
-    let json_str = std::str::from_utf8(&msg).unwrap();
-
-    let bundle_response: BundleResponse = serde_json::from_str(json_str)?;
+  /// Emit JavaScript for every module in the graph that needs it by only
+  /// stripping types with swc, which is used instead of `compile` with the
+  /// `--no-check` flag. The compiler worker isn't started.
+  ///
+  /// Like `compile`, modules that have a valid compiled source in the disk
+  /// cache are skipped unless `--reload` flag was provided.
+  pub fn transpile(&self, module_graph: &ModuleGraph) -> Result<(), ErrBox> {
+    for module_graph_file in module_graph.values() {
+      let needs_transpile = match module_graph_file.media_type {
+        MediaType::TypeScript | MediaType::TSX | MediaType::JSX => true,
+        MediaType::JavaScript => self.compile_js,
+        _ => false,
+      };
+      // Declaration files have nothing to emit. The module that a redirect
+      // leads to is in the graph under its final URL, which it is emitted
+      // for.
+      if !needs_transpile
+        || module_graph_file.url.ends_with(".d.ts")
+        || !module_graph_file.redirect.is_empty()
+      {
+        continue;
+      }
 
-    maybe_log_stats(bundle_response.stats);
+      let specifier = ModuleSpecifier::resolve_url(&module_graph_file.url)?;
+      let url = specifier.as_url();
+      // The compiled file, its metadata and its source map are checked and
+      // written together.
+      let _lock = self.disk_cache.lock()?;
+      if (self.use_disk_cache || self.has_compiled(url))
+        && self.has_compiled_source(url)
+      {
+        continue;
+      }
 
-    if !bundle_response.diagnostics.items.is_empty() {
-      return Err(ErrBox::from(bundle_response.diagnostics));
+      let (code, source_map) = AstParser::new().transpile(
+        url.as_str(),
+        module_graph_file.media_type,
+        &module_graph_file.source_code,
+        &self.config.jsx,
+      )?;
+      self.cache_compiled_file(
+        &specifier,
+        module_graph_file.source_code.as_bytes(),
+        &code,
+        true,
+      )?;
+      self.cache_source_map(&specifier, &source_map)?;
     }
 
-    assert!(bundle_response.bundle_output.is_some());
-    let output = bundle_response.bundle_output.unwrap();
-    Ok(output)
+    Ok(())
   }
 
   /// Get associated `CompiledFileMetadata` for given module if it exists.
@@ -702,19 +765,28 @@ impl TsCompiler {
     None
   }
 
+  /// Build info is keyed by root module and compiler config.
+  fn get_build_info_filename(&self, url: &Url) -> PathBuf {
+    self.disk_cache.get_cache_filename_with_extension(
+      url,
+      &build_info_extension(&self.config.hash),
+    )
+  }
+
   fn cache_build_info(
     &self,
     url: &Url,
     build_info: String,
   ) -> std::io::Result<()> {
-    let js_key = self
+    let build_info_key = self.get_build_info_filename(url);
+    self
       .disk_cache
-      .get_cache_filename_with_extension(url, "buildinfo");
-    self.disk_cache.set(&js_key, build_info.as_bytes())?;
+      .set(&build_info_key, build_info.as_bytes())?;
 
     Ok(())
   }
 
+  /// The caller holds the lock of the disk cache.
   fn cache_emitted_files(
     &self,
     emit_map: HashMap<String, EmittedSource>,
@@ -739,7 +811,12 @@ impl TsCompiler {
       if emitted_name.ends_with(".map") {
         self.cache_source_map(&specifier, &source.contents)?;
       } else if emitted_name.ends_with(".js") {
-        self.cache_compiled_file(&specifier, source_file, &source.contents)?;
+        self.cache_compiled_file(
+          &specifier,
+          &source_file.source_code,
+          &source.contents,
+          false,
+        )?;
       } else {
         panic!("Trying to cache unknown file type {}", emitted_name);
       }
@@ -759,6 +836,7 @@ impl TsCompiler {
         .unwrap()
         .to_string(),
       name: module_url.to_string(),
+      redirects: vec![],
     };
 
     Ok(compiled_module)
@@ -780,6 +858,7 @@ impl TsCompiler {
 
     let compiled_module = SourceFile {
       url: module_url.clone(),
+      redirects: vec![],
       filename: compiled_code_filename,
       media_type: msg::MediaType::JavaScript,
       source_code: compiled_code,
@@ -793,11 +872,13 @@ impl TsCompiler {
   ///
   /// Along compiled file a special metadata file is saved as well containing
   /// hash that can be validated to avoid unnecessary recompilation.
+  /// `no_check` is set if the file was only transpiled.
   fn cache_compiled_file(
     &self,
     module_specifier: &ModuleSpecifier,
-    source_file: SourceFile,
+    source_code: &[u8],
     contents: &str,
+    no_check: bool,
   ) -> std::io::Result<()> {
     let js_key = self
       .disk_cache
@@ -805,13 +886,14 @@ impl TsCompiler {
     self.disk_cache.set(&js_key, contents.as_bytes())?;
     self.mark_compiled(module_specifier.as_url());
 
-    let version_hash = source_code_version_hash(
-      &source_file.source_code,
-      version::DENO,
-      &self.config.hash,
-    );
+    let version_hash =
+      source_code_version_hash(source_code, version::DENO, &self.config.hash);
 
-    let compiled_file_metadata = CompiledFileMetadata { version_hash };
+    let compiled_file_metadata = CompiledFileMetadata {
+      version_hash,
+      source_hash: Some(source_code_hash(source_code)),
+      no_check,
+    };
     let meta_key = self
       .disk_cache
       .get_cache_filename_with_extension(module_specifier.as_url(), "meta");
@@ -837,6 +919,7 @@ impl TsCompiler {
 
     let source_map_file = SourceFile {
       url: module_specifier.as_url().to_owned(),
+      redirects: vec![],
       filename: source_map_filename,
       media_type: msg::MediaType::JavaScript,
       source_code,
@@ -926,28 +1009,20 @@ impl TsCompiler {
       return match self.get_source_map_file(&module_specifier) {
         Ok(out) => Some(out.source_code),
         Err(_) => {
-          // Check if map is inlined
+          // Check if map is inlined, either in the compiled module or, for
+          // files that aren't compiled (like bundles), in the source itself.
           if let Ok(compiled_source) =
             self.get_compiled_module(module_specifier.as_url())
           {
-            let mut content_lines = compiled_source
-              .code
-              .split('\n')
-              .map(|s| s.to_string())
-              .collect::<Vec<String>>();
-
-            if !content_lines.is_empty() {
-              let last_line = content_lines.pop().unwrap();
-              if last_line.starts_with(
-                "//# sourceMappingURL=data:application/json;base64,",
-              ) {
-                let encoded = last_line.trim_start_matches(
-                  "//# sourceMappingURL=data:application/json;base64,",
-                );
-                let decoded_map =
-                  base64::decode(encoded).expect("failed to parse source map");
-                return Some(decoded_map);
-              }
+            if let Some(map) = get_inline_source_map(&compiled_source.code) {
+              return Some(map);
+            }
+          }
+          if let Some(source_file) =
+            self.try_resolve_and_get_source_file(script_name)
+          {
+            if let Ok(code) = str::from_utf8(&source_file.source_code) {
+              return get_inline_source_map(code);
             }
           }
 
@@ -960,6 +1035,18 @@ impl TsCompiler {
   }
 }
 
+/// Returns the decoded source map if the last line of `code` is an inline
+/// `sourceMappingURL` comment.
+fn get_inline_source_map(code: &str) -> Option<Vec<u8>> {
+  let last_line = code.trim_end().rsplit('\n').next()?;
+  let prefix = "//# sourceMappingURL=data:application/json;base64,";
+  if last_line.starts_with(prefix) {
+    let encoded = last_line.trim_start_matches(prefix);
+    return base64::decode(encoded).ok();
+  }
+  None
+}
+
 async fn execute_in_same_thread(
   global_state: GlobalState,
   permissions: Permissions,
@@ -967,7 +1054,7 @@ async fn execute_in_same_thread(
 ) -> Result<Buf, ErrBox> {
   let mut worker = create_compiler_worker(global_state.clone(), permissions);
   let handle = worker.thread_safe_handle();
-  handle.post_message(req)?;
+  handle.post_message(WorkerMessage::Json(req))?;
 
   let mut event_fut = handle.get_event().boxed_local();
 
@@ -980,7 +1067,10 @@ async fn execute_in_same_thread(
           .expect("Empty message");
 
         let buf = match event {
-          WorkerEvent::Message(buf) => Ok(buf),
+          WorkerEvent::Message(WorkerMessage::Json(buf)) => Ok(buf),
+          WorkerEvent::Message(WorkerMessage::Cloned(_)) => {
+            unreachable!("The compiler worker only sends JSON messages")
+          }
           WorkerEvent::Error(error) => Err(error),
           WorkerEvent::TerminalError(error) => Err(error),
         }?;
@@ -1004,7 +1094,7 @@ async fn create_runtime_module_graph(
   let mut root_names = vec![];
   let mut module_graph_loader = ModuleGraphLoader::new(
     global_state.file_fetcher.clone(),
-    None,
+    global_state.resolver.clone(),
     permissions,
     false,
     false,
@@ -1084,6 +1174,7 @@ pub async fn runtime_compile(
   let response: RuntimeCompileResponse = serde_json::from_str(json_str)?;
 
   if response.diagnostics.is_empty() && sources.is_none() {
+    let _lock = compiler.disk_cache.lock()?;
     compiler.cache_emitted_files(response.emit_map)?;
   }
 
@@ -1432,8 +1523,11 @@ fn parse_deno_types(comment: &str) -> Option<String> {
 mod tests {
   use super::*;
   use crate::deno_dir;
+  use crate::file_fetcher::DEFAULT_MAX_DOWNLOADS;
   use crate::fs as deno_fs;
   use crate::http_cache;
+  use crate::http_util::HttpClientOptions;
+  use crate::resolver::Resolver;
   use deno_core::ModuleSpecifier;
   use std::path::PathBuf;
   use tempfile::TempDir;
@@ -1486,6 +1580,7 @@ mod tests {
       ModuleSpecifier::resolve_url_or_path(p.to_str().unwrap()).unwrap();
     let out = SourceFile {
       url: specifier.as_url().clone(),
+      redirects: vec![],
       filename: PathBuf::from(p.to_str().unwrap().to_string()),
       media_type: msg::MediaType::TypeScript,
       source_code: include_bytes!("./tests/002_hello.ts").to_vec(),
@@ -1505,13 +1600,14 @@ mod tests {
       mock_state.flags.cache_blocklist.clone(),
       false,
       false,
-      None,
+      HttpClientOptions::default(),
+      DEFAULT_MAX_DOWNLOADS,
     )
     .unwrap();
 
     let mut module_graph_loader = ModuleGraphLoader::new(
       file_fetcher.clone(),
-      None,
+      Resolver::default(),
       Permissions::allow_all(),
       false,
       false,
@@ -1535,7 +1631,7 @@ mod tests {
         &out,
         TargetLib::Main,
         Permissions::allow_all(),
-        module_graph,
+        &module_graph,
         false,
       )
       .await;
@@ -1552,32 +1648,6 @@ mod tests {
       .starts_with("//# sourceMappingURL=data:application/json;base64"));
   }
 
-  #[tokio::test]
-  async fn test_bundle() {
-    let p = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-      .parent()
-      .unwrap()
-      .join("cli/tests/002_hello.ts");
-    use deno_core::ModuleSpecifier;
-    let module_name =
-      ModuleSpecifier::resolve_url_or_path(p.to_str().unwrap()).unwrap();
-
-    let mock_state = GlobalState::mock(
-      vec![
-        String::from("deno"),
-        p.to_string_lossy().into(),
-        String::from("$deno$/bundle.js"),
-      ],
-      None,
-    );
-
-    let result = mock_state
-      .ts_compiler
-      .bundle(mock_state.clone(), module_name)
-      .await;
-    assert!(result.is_ok());
-  }
-
   #[test]
   fn test_source_code_version_hash() {
     assert_eq!(
@@ -1601,6 +1671,32 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_build_info_extension() {
+    let extension = build_info_extension(b"");
+    assert!(extension.ends_with(".buildinfo"));
+    assert_eq!(extension.len(), BUILD_INFO_HASH_LEN + ".buildinfo".len());
+    assert_eq!(extension, build_info_extension(b""));
+    // Different config should result in different build info.
+    assert_ne!(
+      extension,
+      build_info_extension(b"{\"compilerOptions\": {}}")
+    );
+  }
+
+  #[test]
+  fn test_get_inline_source_map() {
+    let map = r#"{"version":3,"sources":[],"mappings":""}"#;
+    let code = format!(
+      "console.log(1);\n//# sourceMappingURL=data:application/json;base64,{}\n",
+      base64::encode(map)
+    );
+    assert_eq!(get_inline_source_map(&code), Some(map.as_bytes().to_vec()));
+    assert_eq!(get_inline_source_map("console.log(1);\n"), None);
+    let invalid = "//# sourceMappingURL=data:application/json;base64,!!\n";
+    assert_eq!(get_inline_source_map(invalid), None);
+  }
+
   #[test]
   fn test_compile_js() {
     let temp_dir = TempDir::new().expect("tempdir fail");
@@ -1630,6 +1726,28 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_compiler_config_jsx() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let path = temp_dir.path().join("tsconfig.json");
+    let path_str = path.to_str().unwrap().to_string();
+
+    deno_fs::write_file(&path, b"{}", 0o666).unwrap();
+    let config = CompilerConfig::load(Some(path_str.clone())).unwrap();
+    assert_eq!(config.jsx, JsxOptions::default());
+
+    let json_str = r#"{
+      "compilerOptions": {
+        "jsxFactory": "h",
+        "jsxFragmentFactory": "Fragment"
+      }
+    }"#;
+    deno_fs::write_file(&path, json_str.as_bytes(), 0o666).unwrap();
+    let config = CompilerConfig::load(Some(path_str)).unwrap();
+    assert_eq!(config.jsx.factory, "h");
+    assert_eq!(config.jsx.fragment_factory, "Fragment");
+  }
+
   #[test]
   fn test_compiler_config_load() {
     let temp_dir = TempDir::new().expect("tempdir fail");
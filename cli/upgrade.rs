@@ -7,13 +7,16 @@
 //! the same functions as ops available in JS runtime.
 
 extern crate semver_parser;
+use crate::flags::Flags;
 use crate::futures::FutureExt;
+use crate::http_util::create_http_client;
 use crate::http_util::fetch_once;
 use crate::http_util::FetchOnceResult;
+use crate::http_util::ProxyConfig;
 use crate::op_error::OpError;
 use crate::ErrBox;
 use regex::Regex;
-use reqwest::{redirect::Policy, Client};
+use reqwest::Client;
 use semver_parser::version::parse as semver_parse;
 use semver_parser::version::Version;
 use std::fs;
@@ -53,11 +56,15 @@ async fn get_latest_version(client: &Client) -> Result<Version, ErrBox> {
 /// Asynchronously updates deno executable to greatest version
 /// if greatest version is available.
 pub async fn upgrade_command(
+  flags: Flags,
   dry_run: bool,
   force: bool,
   version: Option<String>,
 ) -> Result<(), ErrBox> {
-  let client = Client::builder().redirect(Policy::none()).build()?;
+  let client = create_http_client(
+    flags.ca_file.clone(),
+    ProxyConfig::from_flags(&flags)?,
+  )?;
   let current_version = semver_parse(crate::version::DENO).unwrap();
 
   let install_version = match version {
@@ -120,7 +127,7 @@ fn download_package(
   let url = url.clone();
   let version = version.clone();
   let fut = async move {
-    match fetch_once(client.clone(), &url, None).await {
+    match fetch_once(client.clone(), &url, None, None).await {
       Ok(result) => {
         println!(
           "Version has been found\nDeno is upgrading to version {}",
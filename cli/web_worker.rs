@@ -1,10 +1,16 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 use crate::ops;
+use crate::resource_limits::enforce_limits;
+use crate::resource_limits::LimitHandle;
+use crate::resource_limits::ResourceLimitError;
+use crate::resource_limits::ResourceLimits;
 use crate::state::State;
 use crate::worker::Worker;
 use crate::worker::WorkerEvent;
 use crate::worker::WorkerHandle;
+use crate::worker::WorkerMessage;
 use deno_core::v8;
+use deno_core::CoreIsolate;
 use deno_core::ErrBox;
 use deno_core::StartupData;
 use futures::channel::mpsc;
@@ -17,6 +23,7 @@ use std::pin::Pin;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::task::Context;
 use std::task::Poll;
 
@@ -33,6 +40,7 @@ pub struct WebWorkerHandle {
   terminate_tx: mpsc::Sender<()>,
   terminated: Arc<AtomicBool>,
   isolate_handle: v8::IsolateHandle,
+  limit_exceeded: Arc<Mutex<Option<ResourceLimitError>>>,
 }
 
 impl Deref for WebWorkerHandle {
@@ -63,6 +71,33 @@ impl WebWorkerHandle {
       sender.try_send(()).expect("Failed to terminate");
     }
   }
+
+  /// Returns the limit that caused the worker to be terminated, if any.
+  pub fn take_limit_error(&self) -> Option<ResourceLimitError> {
+    self.limit_exceeded.lock().unwrap().take()
+  }
+}
+
+impl LimitHandle for WebWorkerHandle {
+  fn isolate_handle(&self) -> &v8::IsolateHandle {
+    &self.isolate_handle
+  }
+
+  /// Terminates the worker because it exceeded one of its resource limits.
+  /// The host receives the error as a terminal error once the worker has
+  /// stopped.
+  fn exceed_limit(&self, error: ResourceLimitError) {
+    {
+      let mut limit_exceeded = self.limit_exceeded.lock().unwrap();
+      // Nothing to report if the worker was already terminated by the host
+      // or by another limit.
+      if limit_exceeded.is_some() || self.terminated.load(Ordering::Relaxed) {
+        return;
+      }
+      limit_exceeded.replace(error);
+    }
+    self.terminate();
+  }
 }
 
 /// This worker is implementation of `Worker` Web API
@@ -78,6 +113,7 @@ pub struct WebWorker {
   terminate_rx: mpsc::Receiver<()>,
   handle: WebWorkerHandle,
   pub has_deno_namespace: bool,
+  _watchdog_stop_tx: Option<std::sync::mpsc::Sender<()>>,
 }
 
 impl WebWorker {
@@ -86,9 +122,11 @@ impl WebWorker {
     startup_data: StartupData,
     state: State,
     has_deno_namespace: bool,
+    resource_limits: ResourceLimits,
   ) -> Self {
     let state_ = state.clone();
-    let mut worker = Worker::new(name, startup_data, state_);
+    let mut worker =
+      Worker::new(name, startup_data, state_, resource_limits.heap_limits());
 
     let terminated = Arc::new(AtomicBool::new(false));
     let isolate_handle = worker.isolate.thread_safe_handle();
@@ -99,14 +137,22 @@ impl WebWorker {
       terminated,
       isolate_handle,
       terminate_tx,
+      limit_exceeded: Arc::new(Mutex::new(None)),
     };
 
+    let watchdog_stop_tx = enforce_limits(
+      &mut worker.isolate,
+      &resource_limits,
+      Arc::new(handle.clone()),
+    );
+
     let mut web_worker = Self {
       worker,
       event_loop_idle: false,
       terminate_rx,
       handle,
       has_deno_namespace,
+      _watchdog_stop_tx: watchdog_stop_tx,
     };
 
     let handle = web_worker.thread_safe_handle();
@@ -122,17 +168,21 @@ impl WebWorker {
       );
       ops::worker_host::init(isolate, &state);
       ops::io::init(isolate, &state);
+      ops::message_port::init(isolate, &state);
       ops::resources::init(isolate, &state);
       ops::errors::init(isolate, &state);
       ops::timers::init(isolate, &state);
       ops::fetch::init(isolate, &state);
+      ops::websocket::init(isolate, &state);
 
       if has_deno_namespace {
         ops::runtime_compiler::init(isolate, &state);
         ops::fs::init(isolate, &state);
         ops::fs_events::init(isolate, &state);
+        ops::http::init(isolate, &state);
         ops::plugin::init(isolate, &state);
         ops::net::init(isolate, &state);
+        ops::dns::init(isolate, &state);
         ops::tls::init(isolate, &state);
         ops::os::init(isolate, &state);
         ops::permissions::init(isolate, &state);
@@ -167,29 +217,42 @@ impl DerefMut for WebWorker {
   }
 }
 
+impl WebWorker {
+  /// Completes the worker after it has been terminated, reporting the
+  /// exceeded resource limit to the host if that was the cause.
+  fn finish_terminated(&mut self) -> Poll<Result<(), ErrBox>> {
+    if let Some(error) = self.handle.take_limit_error() {
+      let mut sender = self.worker.internal_channels.sender.clone();
+      sender
+        .try_send(WorkerEvent::TerminalError(error.into()))
+        .expect("Failed to post message to host");
+    }
+    Poll::Ready(Ok(()))
+  }
+}
+
 impl Future for WebWorker {
   type Output = Result<(), ErrBox>;
 
   fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
     let inner = self.get_mut();
-    let worker = &mut inner.worker;
 
     let terminated = inner.handle.terminated.load(Ordering::Relaxed);
 
     if terminated {
-      return Poll::Ready(Ok(()));
+      return inner.finish_terminated();
     }
 
     if !inner.event_loop_idle {
-      match worker.poll_unpin(cx) {
+      match inner.worker.poll_unpin(cx) {
         Poll::Ready(r) => {
           let terminated = inner.handle.terminated.load(Ordering::Relaxed);
           if terminated {
-            return Poll::Ready(Ok(()));
+            return inner.finish_terminated();
           }
 
           if let Err(e) = r {
-            let mut sender = worker.internal_channels.sender.clone();
+            let mut sender = inner.worker.internal_channels.sender.clone();
             sender
               .try_send(WorkerEvent::Error(e))
               .expect("Failed to post message to host");
@@ -203,22 +266,33 @@ impl Future for WebWorker {
     if let Poll::Ready(r) = inner.terminate_rx.poll_next_unpin(cx) {
       // terminate_rx should never be closed
       assert!(r.is_some());
-      return Poll::Ready(Ok(()));
+      return inner.finish_terminated();
     }
 
+    let worker = &mut inner.worker;
     if let Poll::Ready(r) =
       worker.internal_channels.receiver.poll_next_unpin(cx)
     {
       match r {
         Some(msg) => {
-          let msg = String::from_utf8(msg.to_vec()).unwrap();
-          let script = format!("workerMessageRecvCallback({})", msg);
+          let script = match msg {
+            WorkerMessage::Cloned(msg) => {
+              let state_rc = CoreIsolate::state(&worker.isolate);
+              let state = state_rc.borrow();
+              let msg = msg.add_to(&mut state.resource_table.borrow_mut());
+              format!("workerMessageRecvCallback({})", msg)
+            }
+            WorkerMessage::Json(buf) => format!(
+              "workerMessageRecvCallback({})",
+              String::from_utf8_lossy(&buf)
+            ),
+          };
 
           if let Err(e) = worker.execute(&script) {
             // If execution was terminated during message callback then
             // just ignore it
             if inner.handle.terminated.load(Ordering::Relaxed) {
-              return Poll::Ready(Ok(()));
+              return inner.finish_terminated();
             }
 
             // Otherwise forward error to host
@@ -247,6 +321,8 @@ mod tests {
   use crate::state::State;
   use crate::tokio_util;
   use crate::worker::WorkerEvent;
+  use crate::worker::WorkerMessage;
+  use std::time::Duration;
 
   fn create_test_worker() -> WebWorker {
     let state = State::mock("./hello.js");
@@ -255,9 +331,10 @@ mod tests {
       startup_data::deno_isolate_init(),
       state,
       false,
+      ResourceLimits::default(),
     );
     worker
-      .execute("bootstrap.workerRuntime(\"TEST\", false)")
+      .execute("bootstrap.workerRuntime(\"TEST\", false, \"TEST\", true)")
       .unwrap();
     worker
   }
@@ -291,19 +368,19 @@ mod tests {
 
     tokio_util::run_basic(async move {
       let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
-      let r = handle.post_message(msg.clone());
+      let r = handle.post_message(WorkerMessage::Json(msg.clone()));
       assert!(r.is_ok());
 
       let maybe_msg = handle.get_event().await.unwrap();
       assert!(maybe_msg.is_some());
 
-      let r = handle.post_message(msg.clone());
+      let r = handle.post_message(WorkerMessage::Json(msg.clone()));
       assert!(r.is_ok());
 
       let maybe_msg = handle.get_event().await.unwrap();
       assert!(maybe_msg.is_some());
       match maybe_msg {
-        Some(WorkerEvent::Message(buf)) => {
+        Some(WorkerEvent::Message(WorkerMessage::Json(buf))) => {
           assert_eq!(*buf, *b"[1,2,3]");
         }
         _ => unreachable!(),
@@ -313,7 +390,7 @@ mod tests {
         .to_string()
         .into_boxed_str()
         .into_boxed_bytes();
-      let r = handle.post_message(msg);
+      let r = handle.post_message(WorkerMessage::Json(msg));
       assert!(r.is_ok());
       let event = handle.get_event().await.unwrap();
       assert!(event.is_none());
@@ -340,7 +417,7 @@ mod tests {
 
     tokio_util::run_basic(async move {
       let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
-      let r = handle.post_message(msg.clone());
+      let r = handle.post_message(WorkerMessage::Json(msg.clone()));
       assert!(r.is_ok());
       let event = handle.get_event().await.unwrap();
       assert!(event.is_none());
@@ -348,4 +425,54 @@ mod tests {
     });
     join_handle.join().expect("Failed to join worker thread");
   }
+
+  #[test]
+  fn terminated_on_wall_time_limit() {
+    let (handle_sender, handle_receiver) =
+      std::sync::mpsc::sync_channel::<WebWorkerHandle>(1);
+
+    let join_handle = std::thread::spawn(move || {
+      let state = State::mock("./hello.js");
+      let mut worker = WebWorker::new(
+        "TEST".to_string(),
+        startup_data::deno_isolate_init(),
+        state,
+        false,
+        ResourceLimits {
+          max_wall_time: Some(Duration::from_millis(500)),
+          ..Default::default()
+        },
+      );
+      worker
+        .execute("bootstrap.workerRuntime(\"TEST\", false, \"TEST\", true)")
+        .unwrap();
+      worker
+        .execute("onmessage = () => { while (true) {} }")
+        .unwrap();
+      let handle = worker.thread_safe_handle();
+      handle_sender.send(handle).unwrap();
+      let r = tokio_util::run_basic(worker);
+      assert!(r.is_ok())
+    });
+
+    let mut handle = handle_receiver.recv().unwrap();
+
+    tokio_util::run_basic(async move {
+      let msg = json!("hi").to_string().into_boxed_str().into_boxed_bytes();
+      let r = handle.post_message(WorkerMessage::Json(msg));
+      assert!(r.is_ok());
+      match handle.get_event().await.unwrap() {
+        Some(WorkerEvent::TerminalError(error)) => {
+          let error = error.downcast::<ResourceLimitError>().unwrap();
+          assert_eq!(
+            error,
+            ResourceLimitError::WallTime(Duration::from_millis(500))
+          );
+        }
+        _ => unreachable!(),
+      }
+      handle.sender.close_channel();
+    });
+    join_handle.join().expect("Failed to join worker thread");
+  }
 }
@@ -2,13 +2,21 @@
 use crate::fmt_errors::JSError;
 use crate::global_state::GlobalState;
 use crate::inspector::DenoInspector;
+use crate::op_error::OpError;
 use crate::ops;
 use crate::ops::io::get_stdio;
+use crate::ops::message_port::Message;
+use crate::profiler::CpuProfiler;
+use crate::resource_limits::enforce_limits;
+use crate::resource_limits::IsolateLimitHandle;
+use crate::resource_limits::ResourceLimitError;
+use crate::resource_limits::ResourceLimits;
 use crate::startup_data;
 use crate::state::State;
 use deno_core::Buf;
 use deno_core::CoreIsolate;
 use deno_core::ErrBox;
+use deno_core::HeapLimits;
 use deno_core::ModuleId;
 use deno_core::ModuleSpecifier;
 use deno_core::StartupData;
@@ -28,30 +36,39 @@ use std::task::Poll;
 use tokio::sync::Mutex as AsyncMutex;
 use url::Url;
 
+/// Messages exchanged between a worker and its host.
+pub enum WorkerMessage {
+  /// A structured clone, see `serializeMessage()`.
+  Cloned(Message),
+  /// JSON, used when the host is written in Rust, like for the TS compiler
+  /// worker.
+  Json(Buf),
+}
+
 /// Events that are sent to host from child
 /// worker.
 pub enum WorkerEvent {
-  Message(Buf),
+  Message(WorkerMessage),
   Error(ErrBox),
   TerminalError(ErrBox),
 }
 
 pub struct WorkerChannelsInternal {
   pub sender: mpsc::Sender<WorkerEvent>,
-  pub receiver: mpsc::Receiver<Buf>,
+  pub receiver: mpsc::Receiver<WorkerMessage>,
 }
 
 #[derive(Clone)]
 pub struct WorkerHandle {
-  pub sender: mpsc::Sender<Buf>,
+  pub sender: mpsc::Sender<WorkerMessage>,
   pub receiver: Arc<AsyncMutex<mpsc::Receiver<WorkerEvent>>>,
 }
 
 impl WorkerHandle {
   /// Post message to worker as a host.
-  pub fn post_message(&self, buf: Buf) -> Result<(), ErrBox> {
+  pub fn post_message(&self, msg: WorkerMessage) -> Result<(), ErrBox> {
     let mut sender = self.sender.clone();
-    sender.try_send(buf).map_err(ErrBox::from)
+    sender.try_send(msg).map_err(ErrBox::from)
   }
 
   /// Get the event with lock.
@@ -63,7 +80,7 @@ impl WorkerHandle {
 }
 
 fn create_channels() -> (WorkerChannelsInternal, WorkerHandle) {
-  let (in_tx, in_rx) = mpsc::channel::<Buf>(1);
+  let (in_tx, in_rx) = mpsc::channel::<WorkerMessage>(1);
   let (out_tx, out_rx) = mpsc::channel::<WorkerEvent>(1);
   let internal_channels = WorkerChannelsInternal {
     sender: out_tx,
@@ -92,6 +109,12 @@ pub struct Worker {
   pub name: String,
   pub isolate: deno_core::EsIsolate,
   pub inspector: Option<Box<DenoInspector>>,
+  #[cfg(unix)]
+  heap_snapshot_signal: Option<tokio::signal::unix::Signal>,
+  /// Set by `enforce_limits()`, for workers that report exceeded limits
+  /// themselves rather than through a `WebWorkerHandle`.
+  limit_handle: Option<Arc<IsolateLimitHandle>>,
+  _watchdog_stop_tx: Option<std::sync::mpsc::Sender<()>>,
   pub state: State,
   pub waker: AtomicWaker,
   pub(crate) internal_channels: WorkerChannelsInternal,
@@ -99,9 +122,21 @@ pub struct Worker {
 }
 
 impl Worker {
-  pub fn new(name: String, startup_data: StartupData, state: State) -> Self {
+  pub fn new(
+    name: String,
+    startup_data: StartupData,
+    state: State,
+    heap_limits: Option<HeapLimits>,
+  ) -> Self {
     let loader = Rc::new(state.clone());
-    let mut isolate = deno_core::EsIsolate::new(loader, startup_data, false);
+    let mut isolate = match heap_limits {
+      Some(heap_limits) => deno_core::EsIsolate::with_heap_limits(
+        loader,
+        startup_data,
+        heap_limits,
+      ),
+      None => deno_core::EsIsolate::new(loader, startup_data, false),
+    };
 
     {
       let global_state = state.borrow().global_state.clone();
@@ -112,15 +147,58 @@ impl Worker {
       });
     }
 
-    let inspector = {
+    let (is_main, is_internal, flags) = {
       let state = state.borrow();
-      let global_state = &state.global_state;
-      global_state
-        .flags
-        .inspect
-        .or(global_state.flags.inspect_brk)
-        .filter(|_| !state.is_internal)
-        .map(|inspector_host| DenoInspector::new(&mut isolate, inspector_host))
+      let flags = state.global_state.flags.clone();
+      (state.is_main, state.is_internal, flags)
+    };
+    let inspector_host = flags.inspect.or(flags.inspect_brk);
+    // Profiling only applies to the main module, but it is driven through the
+    // inspector, so we create one even if no inspector server was requested.
+    let profile = is_main
+      && (flags.cpu_prof.is_some() || flags.heap_snapshot_signal.is_some());
+    // Every non-internal worker, including web workers, gets its own
+    // inspector target.
+    let mut inspector = if is_internal {
+      None
+    } else if inspector_host.is_some() || profile {
+      let url = state.borrow().main_module.to_string();
+      Some(DenoInspector::new(
+        &mut isolate,
+        inspector_host,
+        &name,
+        &url,
+      ))
+    } else {
+      None
+    };
+
+    // The profiler is kept in the state, so that `Deno.exit()` can write the
+    // profile before the process exits.
+    if let (Some(out_dir), Some(inspector)) = (&flags.cpu_prof, &mut inspector)
+    {
+      if is_main {
+        match CpuProfiler::start(inspector, out_dir.clone()) {
+          Ok(cpu_profiler) => {
+            state.borrow_mut().cpu_profiler = Some(cpu_profiler)
+          }
+          Err(err) => info!("Cannot start CPU profiler: {}", err),
+        }
+      }
+    }
+
+    #[cfg(unix)]
+    let heap_snapshot_signal = match flags.heap_snapshot_signal {
+      Some(signo) if inspector.is_some() && is_main => {
+        match crate::signal::signal_stream(signo) {
+          Ok(signal) => Some(signal),
+          Err(err) => {
+            info!("Cannot listen for heap snapshot signal: {}", err);
+            None
+          }
+        }
+      }
+      _ => None,
     };
 
     let (internal_channels, external_channels) = create_channels();
@@ -129,6 +207,10 @@ impl Worker {
       name,
       isolate,
       inspector,
+      #[cfg(unix)]
+      heap_snapshot_signal,
+      limit_handle: None,
+      _watchdog_stop_tx: None,
       state,
       waker: AtomicWaker::new(),
       internal_channels,
@@ -136,6 +218,26 @@ impl Worker {
     }
   }
 
+  /// Terminates the isolate once it exceeds `limits`, making the worker fail
+  /// with an error that names the exceeded limit. The isolate must have been
+  /// created with `limits.heap_limits()`.
+  pub fn enforce_limits(&mut self, limits: &ResourceLimits) {
+    let handle =
+      Arc::new(IsolateLimitHandle::new(self.isolate.thread_safe_handle()));
+    self._watchdog_stop_tx =
+      enforce_limits(&mut self.isolate, limits, handle.clone());
+    self.limit_handle = Some(handle);
+  }
+
+  /// Replaces the error of a script that was terminated because the isolate
+  /// exceeded one of its limits by the exceeded limit.
+  fn check_limits<T>(&self, result: Result<T, ErrBox>) -> Result<T, ErrBox> {
+    match self.limit_handle.as_ref().and_then(|h| h.error()) {
+      Some(error) if result.is_err() => Err(program_limit_error(error)),
+      _ => result,
+    }
+  }
+
   /// Same as execute2() but the filename defaults to "$CWD/__anonymous__".
   pub fn execute(&mut self, js_source: &str) -> Result<(), ErrBox> {
     let path = env::current_dir().unwrap().join("__anonymous__");
@@ -150,7 +252,8 @@ impl Worker {
     js_filename: &str,
     js_source: &str,
   ) -> Result<(), ErrBox> {
-    self.isolate.execute(js_filename, js_source)
+    let result = self.isolate.execute(js_filename, js_source);
+    self.check_limits(result)
   }
 
   /// Loads and instantiates specified JavaScript module.
@@ -168,7 +271,8 @@ impl Worker {
   ) -> Result<(), ErrBox> {
     let id = self.preload_module(module_specifier).await?;
     self.wait_for_inspector_session();
-    self.isolate.mod_evaluate(id)
+    let result = self.isolate.mod_evaluate(id);
+    self.check_limits(result)
   }
 
   /// Loads, instantiates and executes provided source code
@@ -183,7 +287,8 @@ impl Worker {
       .load_module(module_specifier, Some(code))
       .await?;
     self.wait_for_inspector_session();
-    self.isolate.mod_evaluate(id)
+    let result = self.isolate.mod_evaluate(id);
+    self.check_limits(result)
   }
 
   /// Returns a way to communicate with the Worker from other threads.
@@ -194,7 +299,12 @@ impl Worker {
   fn wait_for_inspector_session(&mut self) {
     let should_break_on_first_statement = self.inspector.is_some() && {
       let state = self.state.borrow();
-      state.is_main && state.global_state.flags.inspect_brk.is_some()
+      let flags = &state.global_state.flags;
+      if state.is_main {
+        flags.inspect_brk.is_some()
+      } else {
+        flags.inspect_brk_workers
+      }
     };
     if should_break_on_first_statement {
       self
@@ -204,10 +314,37 @@ impl Worker {
         .wait_for_session_and_break_on_next_statement()
     }
   }
+
+  #[cfg(unix)]
+  fn poll_heap_snapshot_signal(&mut self, cx: &mut Context) {
+    let signal = match self.heap_snapshot_signal.as_mut() {
+      Some(signal) => signal,
+      None => return,
+    };
+    while let Poll::Ready(Some(())) = signal.poll_recv(cx) {
+      let inspector = self.inspector.as_mut().unwrap();
+      // The working directory may have been removed since the process
+      // started.
+      let dir = env::current_dir().unwrap_or_else(|_| env::temp_dir());
+      match crate::profiler::write_heap_snapshot(inspector, &dir) {
+        Ok(path) => info!("Heap snapshot written to {}", path.display()),
+        Err(err) => info!("Cannot write heap snapshot: {}", err),
+      }
+    }
+  }
+}
+
+/// Limits enforced by `Worker::enforce_limits()` apply to the whole program,
+/// rather than to a web worker.
+fn program_limit_error(error: ResourceLimitError) -> ErrBox {
+  OpError::other(error.message("Program")).into()
 }
 
 impl Drop for Worker {
   fn drop(&mut self) {
+    // The profiler's inspector session must be closed before the inspector
+    // itself is dropped.
+    self.state.stop_cpu_profiler();
     // The Isolate object must outlive the Inspector object, but this is
     // currently not enforced by the type system.
     self.inspector.take();
@@ -222,6 +359,15 @@ impl Future for Worker {
 
     // We always poll the inspector if it exists.
     let _ = inner.inspector.as_mut().map(|i| i.poll_unpin(cx));
+    #[cfg(unix)]
+    inner.poll_heap_snapshot_signal(cx);
+    if let Some(limit_handle) = &inner.limit_handle {
+      // The isolate may be idle when a limit is exceeded, so it's not enough
+      // to wait for the terminated script to fail.
+      if let Some(error) = limit_handle.poll_error(cx) {
+        return Poll::Ready(Err(program_limit_error(error)));
+      }
+    }
     inner.waker.register(cx.waker());
     inner.isolate.poll_unpin(cx)
   }
@@ -252,16 +398,25 @@ impl MainWorker {
   // TODO(ry) combine MainWorker::new and MainWorker::create.
   fn new(name: String, startup_data: StartupData, state: State) -> Self {
     let state_ = state.clone();
-    let mut worker = Worker::new(name, startup_data, state_);
+    let resource_limits =
+      state.borrow().global_state.flags.resource_limits.clone();
+    let mut worker =
+      Worker::new(name, startup_data, state_, resource_limits.heap_limits());
+    if resource_limits != ResourceLimits::default() {
+      worker.enforce_limits(&resource_limits);
+    }
     {
       let isolate = &mut worker.isolate;
       ops::runtime::init(isolate, &state);
       ops::runtime_compiler::init(isolate, &state);
+      ops::dns::init(isolate, &state);
       ops::errors::init(isolate, &state);
       ops::fetch::init(isolate, &state);
       ops::fs::init(isolate, &state);
       ops::fs_events::init(isolate, &state);
+      ops::http::init(isolate, &state);
       ops::io::init(isolate, &state);
+      ops::message_port::init(isolate, &state);
       ops::plugin::init(isolate, &state);
       ops::net::init(isolate, &state);
       ops::tls::init(isolate, &state);
@@ -274,6 +429,7 @@ impl MainWorker {
       ops::signal::init(isolate, &state);
       ops::timers::init(isolate, &state);
       ops::tty::init(isolate, &state);
+      ops::websocket::init(isolate, &state);
       ops::worker_host::init(isolate, &state);
     }
     Self(worker)
@@ -283,13 +439,7 @@ impl MainWorker {
     global_state: GlobalState,
     main_module: ModuleSpecifier,
   ) -> Result<MainWorker, ErrBox> {
-    let state = State::new(
-      global_state.clone(),
-      None,
-      main_module,
-      global_state.maybe_import_map.clone(),
-      false,
-    )?;
+    let state = State::new(global_state.clone(), None, main_module, false)?;
     let mut worker = MainWorker::new(
       "main".to_string(),
       startup_data::deno_isolate_init(),
@@ -340,10 +490,10 @@ mod tests {
       .join("cli/tests/esm_imports_a.js");
     let module_specifier =
       ModuleSpecifier::resolve_url_or_path(&p.to_string_lossy()).unwrap();
-    let global_state = GlobalState::new(flags::Flags::default()).unwrap();
+    let global_state =
+      tokio_util::run_basic(GlobalState::new(flags::Flags::default())).unwrap();
     let state =
-      State::new(global_state, None, module_specifier.clone(), None, false)
-        .unwrap();
+      State::new(global_state, None, module_specifier.clone(), false).unwrap();
     let state_ = state.clone();
     tokio_util::run_basic(async move {
       let mut worker =
@@ -370,10 +520,10 @@ mod tests {
       .join("tests/circular1.ts");
     let module_specifier =
       ModuleSpecifier::resolve_url_or_path(&p.to_string_lossy()).unwrap();
-    let global_state = GlobalState::new(flags::Flags::default()).unwrap();
+    let global_state =
+      tokio_util::run_basic(GlobalState::new(flags::Flags::default())).unwrap();
     let state =
-      State::new(global_state, None, module_specifier.clone(), None, false)
-        .unwrap();
+      State::new(global_state, None, module_specifier.clone(), false).unwrap();
     let state_ = state.clone();
     tokio_util::run_basic(async move {
       let mut worker =
@@ -408,15 +558,10 @@ mod tests {
       reload: true,
       ..flags::Flags::default()
     };
-    let global_state = GlobalState::new(flags).unwrap();
-    let state = State::new(
-      global_state.clone(),
-      None,
-      module_specifier.clone(),
-      None,
-      false,
-    )
-    .unwrap();
+    let global_state = GlobalState::new(flags).await.unwrap();
+    let state =
+      State::new(global_state.clone(), None, module_specifier.clone(), false)
+        .unwrap();
     let mut worker = MainWorker::new(
       "TEST".to_string(),
       startup_data::deno_isolate_init(),
@@ -437,6 +582,42 @@ mod tests {
     drop(http_server_guard);
   }
 
+  #[tokio::test]
+  async fn execute_no_check() {
+    let p = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+      .parent()
+      .unwrap()
+      .join("cli/tests/no_check.ts");
+    let module_specifier =
+      ModuleSpecifier::resolve_url_or_path(&p.to_string_lossy()).unwrap();
+    let flags = flags::Flags {
+      subcommand: flags::DenoSubcommand::Run {
+        script: module_specifier.to_string(),
+      },
+      reload: true,
+      no_check: true,
+      ..flags::Flags::default()
+    };
+    let global_state = GlobalState::new(flags).await.unwrap();
+    let state =
+      State::new(global_state.clone(), None, module_specifier.clone(), false)
+        .unwrap();
+    let mut worker = MainWorker::new(
+      "TEST".to_string(),
+      startup_data::deno_isolate_init(),
+      state.clone(),
+    );
+    worker.execute("bootstrap.mainRuntime()").unwrap();
+    // The type error in the module is ignored.
+    worker.execute_module(&module_specifier).await.unwrap();
+    if let Err(e) = (&mut *worker).await {
+      panic!("Future got unexpected error: {:?}", e);
+    }
+    let state = state.borrow();
+    // Check that we didn't start the compiler.
+    assert_eq!(state.global_state.compiler_starts.load(Ordering::SeqCst), 0);
+  }
+
   fn create_test_worker() -> MainWorker {
     let state = State::mock("./hello.js");
     let mut worker = MainWorker::new(
@@ -46,6 +46,12 @@ lazy_static! {
       v8::ExternalReference {
         function: decode.map_fn_to()
       },
+      v8::ExternalReference {
+        function: serialize.map_fn_to()
+      },
+      v8::ExternalReference {
+        function: deserialize.map_fn_to()
+      },
       v8::ExternalReference {
         function: get_promise_details.map_fn_to(),
       }
@@ -167,6 +173,16 @@ pub fn initialize_context<'s>(
   let decode_val = decode_tmpl.get_function(scope).unwrap();
   core_val.set(scope, decode_key.into(), decode_val.into());
 
+  let serialize_key = v8::String::new(scope, "serialize").unwrap();
+  let serialize_tmpl = v8::FunctionTemplate::new(scope, serialize);
+  let serialize_val = serialize_tmpl.get_function(scope).unwrap();
+  core_val.set(scope, serialize_key.into(), serialize_val.into());
+
+  let deserialize_key = v8::String::new(scope, "deserialize").unwrap();
+  let deserialize_tmpl = v8::FunctionTemplate::new(scope, deserialize);
+  let deserialize_val = deserialize_tmpl.get_function(scope).unwrap();
+  core_val.set(scope, deserialize_key.into(), deserialize_val.into());
+
   let get_promise_details_key =
     v8::String::new(scope, "getPromiseDetails").unwrap();
   let get_promise_details_tmpl =
@@ -678,6 +694,153 @@ fn decode(
   rv.set(text_str.into())
 }
 
+struct SerializeDeserialize;
+
+impl v8::ValueSerializerImpl for SerializeDeserialize {
+  fn throw_data_clone_error<'s>(
+    &mut self,
+    scope: &mut v8::HandleScope<'s>,
+    message: v8::Local<'s, v8::String>,
+  ) {
+    let error = v8::Exception::error(scope, message);
+    scope.throw_exception(error);
+  }
+}
+
+impl v8::ValueDeserializerImpl for SerializeDeserialize {}
+
+fn throw_type_error(scope: &mut v8::HandleScope, message: &str) {
+  let message = v8::String::new(scope, message).unwrap();
+  let exception = v8::Exception::type_error(scope, message);
+  scope.throw_exception(exception);
+}
+
+/// A value serialized by `Deno.core.serialize()`, together with the backing
+/// stores of the `ArrayBuffer`s it transfers.
+///
+/// Serialized values are kept in the resource table of the isolate that
+/// created them, and ops can move them to the resource table of another
+/// isolate. Only `Deno.core.deserialize()` in the isolate that owns the
+/// resource can claim it.
+pub struct SerializedValue {
+  pub data: Box<[u8]>,
+  pub array_buffers: Vec<v8::SharedRef<v8::BackingStore>>,
+}
+
+// The backing stores are no longer referenced by any isolate once they have
+// been detached, so they can be moved across threads like a `ZeroCopyBuf`.
+unsafe impl Send for SerializedValue {}
+
+/// Serializes a value with the structured clone algorithm and adds the result
+/// to the resource table. Returns the rid of the `SerializedValue`.
+///
+/// The `ArrayBuffer`s in the optional transfer list (second argument) are
+/// detached and their backing stores are handed over to the matching
+/// `deserialize()` call without being copied.
+fn serialize(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let mut transfer = vec![];
+  let transfer_arg = args.get(1);
+  if !transfer_arg.is_undefined() {
+    let list = match v8::Local::<v8::Array>::try_from(transfer_arg) {
+      Ok(list) => list,
+      Err(_) => return throw_type_error(scope, "Invalid transfer list"),
+    };
+    for i in 0..list.length() {
+      let index = v8::Integer::new(scope, i as i32);
+      let item = list.get(scope, index.into()).unwrap();
+      match v8::Local::<v8::ArrayBuffer>::try_from(item) {
+        Ok(ab) => transfer.push(ab),
+        Err(_) => {
+          return throw_type_error(
+            scope,
+            "Transfer list may only contain ArrayBuffers",
+          )
+        }
+      }
+    }
+  }
+
+  let mut serializer =
+    v8::ValueSerializer::new(scope, Box::new(SerializeDeserialize));
+  serializer.write_header();
+  for (i, ab) in transfer.iter().enumerate() {
+    serializer.transfer_array_buffer(i as u32, *ab);
+  }
+
+  let context = scope.get_current_context();
+  if serializer.write_value(context, args.get(0)) != Some(true) {
+    // The serializer has thrown a DataCloneError.
+    return;
+  }
+  let data = serializer.release().into_boxed_slice();
+
+  let array_buffers = transfer
+    .iter()
+    .map(|ab| {
+      let backing_store = ab.get_backing_store();
+      ab.detach();
+      backing_store
+    })
+    .collect();
+
+  let state_rc = CoreIsolate::state(scope);
+  let resource_table = state_rc.borrow().resource_table.clone();
+  let rid = resource_table.borrow_mut().add(
+    "serializedValue",
+    Box::new(SerializedValue {
+      data,
+      array_buffers,
+    }),
+  );
+  rv.set(v8::Integer::new(scope, rid as i32).into());
+}
+
+/// Reverses `serialize()`. Takes the rid of a `SerializedValue`, which is
+/// removed from the resource table, and the transferred `ArrayBuffer`s with
+/// it.
+fn deserialize(
+  scope: &mut v8::HandleScope,
+  args: v8::FunctionCallbackArguments,
+  mut rv: v8::ReturnValue,
+) {
+  let rid = match v8::Local::<v8::Uint32>::try_from(args.get(0)) {
+    Ok(rid) => rid.value() as u32,
+    Err(_) => return throw_type_error(scope, "Invalid argument"),
+  };
+  let value = {
+    let state_rc = CoreIsolate::state(scope);
+    let resource_table = state_rc.borrow().resource_table.clone();
+    let mut resource_table = resource_table.borrow_mut();
+    resource_table.remove::<SerializedValue>(rid)
+  };
+  let value = match value {
+    Some(value) => value,
+    None => return throw_type_error(scope, "Bad serialized value rid"),
+  };
+
+  let context = scope.get_current_context();
+  let mut deserializer = v8::ValueDeserializer::new(
+    scope,
+    Box::new(SerializeDeserialize),
+    &value.data,
+  );
+  if deserializer.read_header(context) != Some(true) {
+    return throw_type_error(scope, "Invalid serialized value");
+  }
+  for (i, backing_store) in value.array_buffers.iter().enumerate() {
+    let ab = v8::ArrayBuffer::with_backing_store(scope, backing_store);
+    deserializer.transfer_array_buffer(i as u32, ab);
+  }
+
+  if let Some(value) = deserializer.read_value(context) {
+    rv.set(value);
+  }
+}
+
 fn queue_microtask(
   scope: &mut v8::HandleScope,
   args: v8::FunctionCallbackArguments,
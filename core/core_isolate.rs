@@ -20,9 +20,11 @@ use futures::stream::FuturesUnordered;
 use futures::stream::StreamExt;
 use futures::task::AtomicWaker;
 use futures::Future;
+use std::any::Any;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::From;
+use std::ffi::c_void;
 use std::mem::forget;
 use std::ops::Deref;
 use std::ops::DerefMut;
@@ -73,6 +75,13 @@ pub enum StartupData<'a> {
 
 type JSErrorCreateFn = dyn Fn(JSError) -> ErrBox;
 
+/// Initial and maximum size of the V8 heap, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeapLimits {
+  pub initial: usize,
+  pub max: usize,
+}
+
 /// A single execution context of JavaScript. Corresponds roughly to the "Web
 /// Worker" concept in the DOM. An CoreIsolate is a Future that can be used with
 /// Tokio. The CoreIsolate future completes when there is an error or when all
@@ -89,6 +98,7 @@ pub struct CoreIsolate {
   has_snapshotted: bool,
   needs_init: bool,
   startup_script: Option<OwnedScript>,
+  near_heap_limit_callback: Option<(Box<dyn Any>, v8::NearHeapLimitCallback)>,
 }
 
 /// Internal state for CoreIsolate which is stored in one of v8::Isolate's
@@ -124,6 +134,14 @@ impl DerefMut for CoreIsolate {
 
 impl Drop for CoreIsolate {
   fn drop(&mut self) {
+    if let Some((_, callback)) = self.near_heap_limit_callback.take() {
+      self
+        .v8_isolate
+        .as_mut()
+        .unwrap()
+        .remove_near_heap_limit_callback(callback, 0);
+    }
+
     if let Some(creator) = self.snapshot_creator.take() {
       // TODO(ry): in rusty_v8, `SnapShotCreator::get_owned_isolate()` returns
       // a `struct OwnedIsolate` which is not actually owned, hence the need
@@ -166,6 +184,25 @@ impl CoreIsolate {
   /// startup_data defines the snapshot or script used at startup to initialize
   /// the isolate.
   pub fn new(startup_data: StartupData, will_snapshot: bool) -> Self {
+    Self::create(startup_data, will_snapshot, None)
+  }
+
+  /// Same as `new()`, but the V8 heap of the isolate is bounded by
+  /// `heap_limits`. When the heap reaches its maximum size V8 aborts the
+  /// process, unless a callback registered with
+  /// `add_near_heap_limit_callback()` raises the limit.
+  pub fn with_heap_limits(
+    startup_data: StartupData,
+    heap_limits: HeapLimits,
+  ) -> Self {
+    Self::create(startup_data, false, Some(heap_limits))
+  }
+
+  fn create(
+    startup_data: StartupData,
+    will_snapshot: bool,
+    heap_limits: Option<HeapLimits>,
+  ) -> Self {
     static DENO_INIT: Once = Once::new();
     DENO_INIT.call_once(|| {
       unsafe { v8_init() };
@@ -195,6 +232,9 @@ impl CoreIsolate {
     } else {
       let mut params = v8::Isolate::create_params()
         .external_references(&**bindings::EXTERNAL_REFERENCES);
+      if let Some(heap_limits) = heap_limits {
+        params = params.heap_limits(heap_limits.initial, heap_limits.max);
+      }
       let snapshot_loaded = if let Some(snapshot) = startup_snapshot {
         params = match snapshot {
           Snapshot::Static(data) => params.snapshot_blob(data),
@@ -244,6 +284,7 @@ impl CoreIsolate {
       has_snapshotted: false,
       needs_init: true,
       startup_script,
+      near_heap_limit_callback: None,
     }
   }
 
@@ -356,6 +397,42 @@ impl CoreIsolate {
     let mut state = state_rc.borrow_mut();
     state.op_registry.register(name, op)
   }
+
+  /// Registers a callback that V8 calls when the heap is close to its limit.
+  /// The callback receives the current and the initial heap limit, and
+  /// returns the new heap limit. Returning a somewhat larger limit gives the
+  /// isolate room to unwind after calling `terminate_execution()`; the
+  /// callback may be called again while it does, so returning e.g. twice the
+  /// current limit every time would effectively lift the limit.
+  ///
+  /// Only one callback can be registered; registering a new one replaces the
+  /// previous one.
+  pub fn add_near_heap_limit_callback<C>(&mut self, callback: C)
+  where
+    C: FnMut(usize, usize) -> usize + 'static,
+  {
+    let mut boxed_callback = Box::new(callback);
+    let data = &mut *boxed_callback as *mut C as *mut c_void;
+    let isolate = self.v8_isolate.as_mut().unwrap();
+    if let Some((_, prev_callback)) = self.near_heap_limit_callback.take() {
+      isolate.remove_near_heap_limit_callback(prev_callback, 0);
+    }
+    isolate.add_near_heap_limit_callback(near_heap_limit_callback::<C>, data);
+    self.near_heap_limit_callback =
+      Some((boxed_callback, near_heap_limit_callback::<C>));
+  }
+}
+
+extern "C" fn near_heap_limit_callback<C>(
+  data: *mut c_void,
+  current_heap_limit: usize,
+  initial_heap_limit: usize,
+) -> usize
+where
+  C: FnMut(usize, usize) -> usize + 'static,
+{
+  let callback = unsafe { &mut *(data as *mut C) };
+  callback(current_heap_limit, initial_heap_limit)
 }
 
 impl Future for CoreIsolate {
@@ -1168,6 +1245,20 @@ pub mod tests {
     });
   }
 
+  #[test]
+  fn test_serialize_deserialize() {
+    run_in_task(|mut cx| {
+      let (mut isolate, _dispatch_count) = setup(Mode::Async);
+      js_check(isolate.execute(
+        "serialize_deserialize_test.js",
+        include_str!("serialize_deserialize_test.js"),
+      ));
+      if let Poll::Ready(Err(_)) = isolate.poll_unpin(&mut cx) {
+        unreachable!();
+      }
+    });
+  }
+
   #[test]
   fn will_snapshot() {
     let snapshot = {
@@ -1194,4 +1285,36 @@ pub mod tests {
     let mut isolate2 = CoreIsolate::new(startup_data, false);
     js_check(isolate2.execute("check.js", "if (a != 3) throw Error('x')"));
   }
+
+  #[test]
+  fn test_heap_limits() {
+    let heap_limits = HeapLimits {
+      initial: 0,
+      max: 20 * 1024 * 1024,
+    };
+    let mut isolate =
+      CoreIsolate::with_heap_limits(StartupData::None, heap_limits);
+    let callback_invoke_count = Arc::new(AtomicUsize::new(0));
+    let inner_invoke_count = callback_invoke_count.clone();
+    let isolate_handle = isolate.thread_safe_handle();
+    isolate.add_near_heap_limit_callback(
+      move |current_limit, initial_limit| {
+        inner_invoke_count.fetch_add(1, Ordering::SeqCst);
+        isolate_handle.terminate_execution();
+        // Only give the isolate a bounded amount of room to unwind.
+        std::cmp::max(current_limit, initial_limit + initial_limit / 4)
+      },
+    );
+    let err = isolate
+      .execute(
+        "script name",
+        r#"let s = ""; while(true) { s += "Hello"; }"#,
+      )
+      .expect_err("script should fail");
+    assert_eq!(
+      "Uncaught Error: execution terminated",
+      err.downcast::<JSError>().unwrap().message
+    );
+    assert!(callback_invoke_count.load(Ordering::SeqCst) > 0);
+  }
 }
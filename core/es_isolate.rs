@@ -38,6 +38,7 @@ use crate::modules::Modules;
 use crate::modules::PrepareLoadFuture;
 use crate::modules::RecursiveModuleLoad;
 use crate::CoreIsolate;
+use crate::HeapLimits;
 use crate::StartupData;
 
 /// More specialized version of `CoreIsolate` that provides loading
@@ -79,7 +80,25 @@ impl EsIsolate {
     startup_data: StartupData,
     will_snapshot: bool,
   ) -> Self {
-    let mut core_isolate = CoreIsolate::new(startup_data, will_snapshot);
+    let core_isolate = CoreIsolate::new(startup_data, will_snapshot);
+    Self::from_core_isolate(core_isolate, loader)
+  }
+
+  /// Same as `new()`, but the V8 heap of the isolate is bounded by
+  /// `heap_limits`. See `CoreIsolate::with_heap_limits()`.
+  pub fn with_heap_limits(
+    loader: Rc<dyn ModuleLoader>,
+    startup_data: StartupData,
+    heap_limits: HeapLimits,
+  ) -> Self {
+    let core_isolate = CoreIsolate::with_heap_limits(startup_data, heap_limits);
+    Self::from_core_isolate(core_isolate, loader)
+  }
+
+  fn from_core_isolate(
+    mut core_isolate: CoreIsolate,
+    loader: Rc<dyn ModuleLoader>,
+  ) -> Self {
     {
       core_isolate.set_host_initialize_import_meta_object_callback(
         bindings::host_initialize_import_meta_object_callback,
@@ -454,6 +473,7 @@ impl EsIsolate {
     let ModuleSource {
       code,
       module_url_specified,
+      module_url_redirects,
       module_url_found,
     } = info;
 
@@ -473,12 +493,15 @@ impl EsIsolate {
     //   2b. The module with resolved module name has not yet been registered
     //     -> register & alias
 
-    // If necessary, register an alias.
+    // If necessary, register an alias for every hop of the redirect.
     if module_url_specified != module_url_found {
       let mut state = state_rc.borrow_mut();
       state
         .modules
         .alias(&module_url_specified, &module_url_found);
+      for redirect in &module_url_redirects {
+        state.modules.alias(redirect, &module_url_found);
+      }
     }
 
     let maybe_mod_id = {
@@ -865,6 +888,7 @@ pub mod tests {
       self.load_count.fetch_add(1, Ordering::Relaxed);
       let info = ModuleSource {
         module_url_specified: specifier.to_string(),
+        module_url_redirects: vec![],
         module_url_found: specifier.to_string(),
         code: "export function b() { return 'b' }".to_owned(),
       };
@@ -23,9 +23,11 @@ mod zero_copy_buf;
 
 pub use rusty_v8 as v8;
 
+pub use crate::bindings::SerializedValue;
 pub use crate::core_isolate::js_check;
 pub use crate::core_isolate::CoreIsolate;
 pub use crate::core_isolate::CoreIsolateState;
+pub use crate::core_isolate::HeapLimits;
 pub use crate::core_isolate::Script;
 pub use crate::core_isolate::Snapshot;
 pub use crate::core_isolate::StartupData;
@@ -37,14 +37,19 @@ pub type ModuleLoadId = i32;
 /// "https://example.com/b.ts" may point to "https://example.com/c.ts"
 /// By keeping track of specified and found URL we can alias modules and avoid
 /// recompiling the same code 3 times.
-// TODO(bartlomieju): I have a strong opinion we should store all redirects
-// that happened; not only first and final target. It would simplify a lot
-// of things throughout the codebase otherwise we may end up requesting
-// intermediate redirects from file loader.
+///
+/// Redirects may have several hops, eg. "https://example.com/a.ts" might
+/// redirect to "https://example.com/b.ts" which redirects to
+/// "https://example.com/c.ts". All intermediate URLs are aliased as well, so
+/// that importing any of them later doesn't request them from the loader
+/// again.
 #[derive(Debug, Eq, PartialEq)]
 pub struct ModuleSource {
   pub code: String,
   pub module_url_specified: String,
+  /// URLs between `module_url_specified` and `module_url_found`, in the
+  /// order they were redirected through.
+  pub module_url_redirects: Vec<String>,
   pub module_url_found: String,
 }
 
@@ -217,6 +222,7 @@ impl RecursiveModuleLoad {
         futures::future::ok(ModuleSource {
           code: code.to_owned(),
           module_url_specified: module_specifier.to_string(),
+          module_url_redirects: vec![],
           module_url_found: module_specifier.to_string(),
         })
         .boxed()
@@ -591,6 +597,12 @@ mod tests {
       // pretend redirect - real module name is different than one requested
       "/redirect2.js" => Some((REDIRECT2_SRC, "file:///dir/redirect2.js")),
       "/dir/redirect3.js" => Some((REDIRECT3_SRC, "file:///redirect3.js")),
+      "/redirect_chain.js" => {
+        Some((REDIRECT_CHAIN_SRC, "file:///redirect_chain.js"))
+      }
+      // pretend redirect with two hops: hop1.js -> hop2.js -> dir/hop3.js
+      "/hop1.js" => Some((HOP3_SRC, "file:///dir/hop3.js")),
+      "/hop2.js" => Some((HOP3_SRC, "file:///dir/hop3.js")),
       "/slow.js" => Some((SLOW_SRC, "file:///slow.js")),
       "/never_ready.js" => {
         Some(("should never be Ready", "file:///never_ready.js"))
@@ -603,6 +615,13 @@ mod tests {
     }
   }
 
+  fn mock_redirects(url: &str) -> Vec<String> {
+    match url {
+      "file:///hop1.js" => vec!["file:///hop2.js".to_string()],
+      _ => vec![],
+    }
+  }
+
   #[derive(Debug, PartialEq)]
   enum MockError {
     ResolveErr,
@@ -645,6 +664,7 @@ mod tests {
         Some(src) => Poll::Ready(Ok(ModuleSource {
           code: src.0.to_owned(),
           module_url_specified: inner.url.clone(),
+          module_url_redirects: mock_redirects(&inner.url),
           module_url_found: src.1.to_owned(),
         })),
         None => Poll::Ready(Err(MockError::LoadErr.into())),
@@ -859,6 +879,51 @@ mod tests {
     Deno.core.print("redirect3");
   "#;
 
+  #[test]
+  fn test_redirect_chain_load() {
+    let loader = MockLoader::new();
+    let loads = loader.loads.clone();
+    let mut isolate = EsIsolate::new(Rc::new(loader), StartupData::None, false);
+
+    let fut = async move {
+      let spec =
+        ModuleSpecifier::resolve_url("file:///redirect_chain.js").unwrap();
+      let result = isolate.load_module(&spec, None).await;
+      assert!(result.is_ok());
+      js_check(isolate.mod_evaluate(result.unwrap()));
+
+      // hop3.js imports hop2.js, which was already registered as an alias
+      // while loading hop1.js.
+      let l = loads.lock().unwrap();
+      assert_eq!(
+        l.to_vec(),
+        vec!["file:///redirect_chain.js", "file:///hop1.js"]
+      );
+
+      let state_rc = EsIsolate::state(&isolate);
+      let state = state_rc.borrow();
+      let modules = &state.modules;
+      let hop3_id = modules.get_id("file:///dir/hop3.js").unwrap();
+      assert!(modules.is_alias("file:///hop1.js"));
+      assert!(modules.is_alias("file:///hop2.js"));
+      assert_eq!(modules.get_id("file:///hop1.js"), Some(hop3_id));
+      assert_eq!(modules.get_id("file:///hop2.js"), Some(hop3_id));
+    }
+    .boxed_local();
+
+    futures::executor::block_on(fut);
+  }
+
+  const REDIRECT_CHAIN_SRC: &str = r#"
+    import "./hop1.js";
+    Deno.core.print("redirect_chain");
+  "#;
+
+  const HOP3_SRC: &str = r#"
+    import "../hop2.js";
+    Deno.core.print("hop3");
+  "#;
+
   #[test]
   fn test_redirect_load() {
     let loader = MockLoader::new();
@@ -18,13 +18,34 @@ pub type ResourceId = u32;
 /// system ones.
 type ResourceMap = HashMap<ResourceId, (String, Box<dyn Resource>)>;
 
+/// Called when a resource is added while the table already holds the maximum
+/// number of resources.
+type ResourceLimitFn = dyn Fn();
+
 #[derive(Default)]
 pub struct ResourceTable {
   map: ResourceMap,
   next_id: u32,
+  limit: Option<(usize, Box<ResourceLimitFn>)>,
 }
 
 impl ResourceTable {
+  /// Returns the number of open resources.
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// Sets the maximum number of open resources. `on_exceeded` is called
+  /// every time a resource is added beyond that number; the resource is
+  /// added regardless.
+  pub fn set_limit(&mut self, max: usize, on_exceeded: Box<ResourceLimitFn>) {
+    self.limit = Some((max, on_exceeded));
+  }
+
   pub fn has(&self, rid: ResourceId) -> bool {
     self.map.contains_key(&rid)
   }
@@ -56,6 +77,11 @@ impl ResourceTable {
     let rid = self.next_rid();
     let r = self.map.insert(rid, (name.to_string(), resource));
     assert!(r.is_none());
+    if let Some((max, on_exceeded)) = &self.limit {
+      if self.map.len() > *max {
+        on_exceeded();
+      }
+    }
     rid
   }
 
@@ -97,6 +123,8 @@ impl_downcast!(Resource);
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::cell::Cell;
+  use std::rc::Rc;
 
   struct FakeResource {
     not_empty: u128,
@@ -130,6 +158,21 @@ mod tests {
     assert_eq!(rid1 + 1, rid2);
   }
 
+  #[test]
+  fn test_resource_table_limit() {
+    let exceeded = Rc::new(Cell::new(0));
+    let exceeded_ = exceeded.clone();
+    let mut table = ResourceTable::default();
+    table.set_limit(1, Box::new(move || exceeded_.set(exceeded_.get() + 1)));
+    let rid1 = table.add("fake1", Box::new(FakeResource::new(1)));
+    assert_eq!(exceeded.get(), 0);
+    table.add("fake2", Box::new(FakeResource::new(2)));
+    assert_eq!(exceeded.get(), 1);
+    table.close(rid1);
+    table.add("fake3", Box::new(FakeResource::new(3)));
+    assert_eq!(exceeded.get(), 2);
+  }
+
   #[test]
   fn test_get_from_resource_table_is_what_was_given() {
     let mut table = ResourceTable::default();
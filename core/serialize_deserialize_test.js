@@ -0,0 +1,48 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+function assert(cond) {
+  if (!cond) {
+    throw Error("assert");
+  }
+}
+
+function main() {
+  const cyclic = { name: "cyclic" };
+  cyclic.self = cyclic;
+  const value = {
+    map: new Map([["a", 1]]),
+    date: new Date(0),
+    bytes: new Uint8Array([1, 2, 3]),
+    cyclic,
+  };
+  const clone = Deno.core.deserialize(Deno.core.serialize(value));
+  assert(clone.map instanceof Map && clone.map.get("a") === 1);
+  assert(clone.date instanceof Date && clone.date.getTime() === 0);
+  assert(clone.bytes instanceof Uint8Array && clone.bytes[2] === 3);
+  assert(clone.cyclic.self === clone.cyclic);
+
+  const buf = new Uint8Array([4, 5, 6]).buffer;
+  const rid = Deno.core.serialize({ buf }, [buf]);
+  assert(buf.byteLength === 0);
+  const transferred = Deno.core.deserialize(rid);
+  assert(transferred.buf.byteLength === 3);
+  assert(new Uint8Array(transferred.buf)[0] === 4);
+
+  let threw = false;
+  try {
+    Deno.core.deserialize(rid);
+  } catch (e) {
+    threw = e instanceof TypeError;
+  }
+  assert(threw);
+
+  threw = false;
+  try {
+    Deno.core.serialize({ f() {} });
+  } catch (e) {
+    threw = true;
+  }
+  assert(threw);
+}
+
+main();
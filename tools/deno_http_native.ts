@@ -0,0 +1,16 @@
+// Used for benchmarking Deno's native HTTP server. See tools/http_benchmark.py
+const addr = Deno.args[0] || "127.0.0.1:4500";
+const [hostname, port] = addr.split(":");
+const listener = Deno.listen({ hostname, port: Number(port) });
+const body = new TextEncoder().encode("Hello World\n");
+
+async function handle(conn: Deno.Conn): Promise<void> {
+  for await (const { respondWith } of Deno.serveHttp(conn)) {
+    respondWith(new Response(body));
+  }
+}
+
+console.log("Listening on", addr);
+for await (const conn of listener) {
+  handle(conn);
+}
@@ -38,6 +38,9 @@ CERT_FILE = os.path.join(root_path, "std/http/testdata/tls/localhost.crt")
 KEY_FILE = os.path.join(root_path, "std/http/testdata/tls/localhost.key")
 QUIET = not args.verbose
 
+# Number of times each "flaky_" path has been requested so far.
+flaky_requests = {}
+
 
 class SSLTCPServer(SocketServer.TCPServer):
     def __init__(self,
@@ -97,6 +100,25 @@ class ContentTypeHandler(QuietSimpleHTTPRequestHandler):
             body.close()
             return
 
+        if "auth_required" in self.path:
+            authorization = self.headers.getheader('authorization')
+            if authorization != "Bearer abcdef123456789":
+                self.protocol_version = 'HTTP/1.1'
+                self.send_response(401, 'Unauthorized')
+                self.send_header('Content-Length', '0')
+                self.end_headers()
+                return
+
+        if "flaky_" in self.path:
+            count = flaky_requests.get(self.path, 0)
+            flaky_requests[self.path] = count + 1
+            if count < 2:
+                self.protocol_version = 'HTTP/1.1'
+                self.send_response(503, 'Service Unavailable')
+                self.send_header('Content-Length', '0')
+                self.end_headers()
+                return
+
         if "etag_script.ts" in self.path:
             self.protocol_version = 'HTTP/1.1'
             if_not_match = self.headers.getheader('if-none-match')
@@ -191,6 +213,18 @@ class ContentTypeHandler(QuietSimpleHTTPRequestHandler):
             return
         return SimpleHTTPServer.SimpleHTTPRequestHandler.do_GET(self)
 
+    def read_body(self):
+        if self.headers.getheader('transfer-encoding') == 'chunked':
+            data = b''
+            while True:
+                size = int(self.rfile.readline().strip(), 16)
+                if size == 0:
+                    self.rfile.readline()
+                    return data
+                data += self.rfile.read(size)
+                self.rfile.readline()
+        return self.rfile.read(int(self.headers['Content-Length']))
+
     def do_POST(self):
         # Simple echo server for request reflection
         if "echo_server" in self.path:
@@ -204,7 +238,7 @@ class ContentTypeHandler(QuietSimpleHTTPRequestHandler):
                 self.send_header('user-agent',
                                  self.headers.getheader('user-agent'))
             self.end_headers()
-            data_string = self.rfile.read(int(self.headers['Content-Length']))
+            data_string = self.read_body()
             self.wfile.write(bytes(data_string))
             return
         if "echo_multipart_file" in self.path: